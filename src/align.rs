@@ -0,0 +1,47 @@
+//! Mapping a reading position between two versions of a document
+//!
+//! When a file is edited while it is being read, the word under the reader
+//! is found again in the new version by comparing the words around it.
+
+/// Words compared on each side of the position
+const WINDOW: usize = 6;
+
+/// Position in `new` corresponding to `position` in `old`.
+///
+/// Every position in `new` is scored by how many words in a window around
+/// it match the window around `position` in `old`; ties go to the candidate
+/// nearest the same relative place in the document. Without any matching
+/// word, the relative place is used.
+#[must_use]
+pub fn map_position<S: AsRef<str>>(old: &[S], position: usize, new: &[S]) -> usize {
+    if old.is_empty() || new.is_empty() {
+        return 0;
+    }
+    let position = position.min(old.len() - 1);
+    let expected = position * new.len() / old.len();
+
+    let score = |candidate: usize| {
+        (0..=2 * WINDOW)
+            .filter(|&i| {
+                let old_index = (position + i).checked_sub(WINDOW);
+                let new_index = (candidate + i).checked_sub(WINDOW);
+                match (old_index, new_index) {
+                    (Some(o), Some(n)) => old
+                        .get(o)
+                        .zip(new.get(n))
+                        .is_some_and(|(a, b)| a.as_ref() == b.as_ref()),
+                    _ => false,
+                }
+            })
+            .count()
+    };
+
+    (0..new.len())
+        .map(|candidate| (score(candidate), candidate))
+        .filter(|&(score, _)| score > 0)
+        .max_by(|a, b| {
+            a.0.cmp(&b.0)
+                .then_with(|| b.1.abs_diff(expected).cmp(&a.1.abs_diff(expected)))
+        })
+        .map_or(expected.min(new.len() - 1), |(_, candidate)| candidate)
+}
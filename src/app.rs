@@ -1,24 +1,110 @@
-use crate::types::{Section, TimedToken};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::align::map_position;
+use crate::library::{self, LibraryEntry};
+use crate::links::find_anchor;
+use crate::parser::{ParseWarning, ParsedDocument};
+use crate::queue::QueueEntry;
+use crate::timing::timed_tokens;
+use crate::types::{
+    BlockContext, CodeBlock, DocumentMetadata, Footnote, PendingSection, ReadingProgress, Section,
+    SourceFile, TimedToken, Token, TokenStyle,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ViewMode {
     Reading,
     Outline,
+    /// Scrolling prose view for picking a word to resume from
+    Page,
+    /// List of queued documents with their progress
+    Queue,
+    /// Previously opened documents, filtered by typing
+    Library,
+}
+
+/// Popup drawn over the current view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlay {
+    /// Code block by index, scrolled to the given first line
+    Code { index: usize, scroll: usize },
+    /// Footnote by index, being read word by word; `resume` restores
+    /// playback of the document when the note closes
+    Footnote {
+        index: usize,
+        word: usize,
+        resume: bool,
+    },
+}
+
+/// How many words back a footnote reference can still be opened from
+pub const FOOTNOTE_LOOKBACK: usize = 8;
+
+/// How many words back a link can still be followed from
+pub const LINK_LOOKBACK: usize = 8;
+
+/// How long a notice stays in the status bar
+pub const NOTICE_DURATION: Duration = Duration::from_secs(3);
+
+/// Where to return to after following a link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// Document that was open, `None` if it wasn't read from a file
+    pub path: Option<PathBuf>,
+    pub position: usize,
 }
 
 pub struct App {
     tokens: Vec<TimedToken>,
     sections: Vec<Section>,
+    code_blocks: Vec<CodeBlock>,
+    footnotes: Vec<Footnote>,
+    metadata: DocumentMetadata,
+    anchors: HashMap<String, usize>,
+    files: Vec<SourceFile>,
+    /// Problems the parser worked around, such as skipped chapters
+    warnings: Vec<ParseWarning>,
+    /// Outline entries of parts still loading in the background
+    pending_sections: Vec<PendingSection>,
+    /// Estimated total words while the document is still loading
+    estimated_word_count: Option<usize>,
+    /// Position past the loaded part to jump to once it has loaded
+    awaited_position: Option<usize>,
+    /// Section in a part still loading to jump to once it has loaded
+    awaited_section: Option<PendingSection>,
+    history: Vec<HistoryEntry>,
+    queue: Vec<QueueEntry>,
+    queue_index: usize,
+    queue_selection: usize,
+    /// Queue entry waiting to be loaded by the caller
+    pending_document: Option<usize>,
+    library: Vec<LibraryEntry>,
+    library_filter: String,
+    /// True while typed letters go to the library filter, after `/`
+    library_filtering: bool,
+    library_selection: usize,
+    /// Message about something that just happened, e.g. a failed open or a
+    /// reload, and when it was set
+    notice: Option<(String, Instant)>,
+    overlay: Option<Overlay>,
     position: usize,
     wpm: u16,
     paused: bool,
     view_mode: ViewMode,
     outline_selection: usize,
+    page_cursor: usize,
     show_help: bool,
     context_width: usize,
     pub context_enabled: bool,
     pub hint_chars_enabled: bool,
     pub styling_enabled: bool,
+    pub big_text_enabled: bool,
+    /// Pause when reaching the start of a code block
+    pub pause_on_code: bool,
+    /// Pause when reaching an image's alt text or caption
+    pub pause_on_images: bool,
 }
 
 /// Default context width in characters
@@ -48,16 +134,41 @@ impl App {
         Self {
             tokens,
             sections,
+            code_blocks: Vec::new(),
+            footnotes: Vec::new(),
+            metadata: DocumentMetadata::default(),
+            anchors: HashMap::new(),
+            files: Vec::new(),
+            warnings: Vec::new(),
+            pending_sections: Vec::new(),
+            estimated_word_count: None,
+            awaited_position: None,
+            awaited_section: None,
+            history: Vec::new(),
+            queue: Vec::new(),
+            queue_index: 0,
+            queue_selection: 0,
+            pending_document: None,
+            library: Vec::new(),
+            library_filter: String::new(),
+            library_filtering: false,
+            library_selection: 0,
+            notice: None,
+            overlay: None,
             position: 0,
             wpm: 300,
             paused: false,
             view_mode: ViewMode::Reading,
             outline_selection: 0,
+            page_cursor: 0,
             show_help: false,
             context_width,
             context_enabled,
             hint_chars_enabled,
             styling_enabled,
+            big_text_enabled: false,
+            pause_on_code: true,
+            pause_on_images: false,
         }
     }
 
@@ -91,19 +202,162 @@ impl App {
         &self.sections
     }
     #[must_use]
+    pub fn code_blocks(&self) -> &[CodeBlock] {
+        &self.code_blocks
+    }
+    #[must_use]
+    pub fn footnotes(&self) -> &[Footnote] {
+        &self.footnotes
+    }
+    #[must_use]
+    pub const fn metadata(&self) -> &DocumentMetadata {
+        &self.metadata
+    }
+    #[must_use]
+    pub const fn anchors(&self) -> &HashMap<String, usize> {
+        &self.anchors
+    }
+    #[must_use]
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+    #[must_use]
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+    #[must_use]
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+    #[must_use]
+    pub fn queue(&self) -> &[QueueEntry] {
+        &self.queue
+    }
+    #[must_use]
+    pub const fn queue_index(&self) -> usize {
+        self.queue_index
+    }
+    #[must_use]
+    pub const fn queue_selection(&self) -> usize {
+        self.queue_selection
+    }
+    #[must_use]
+    pub fn library(&self) -> &[LibraryEntry] {
+        &self.library
+    }
+    #[must_use]
+    pub fn library_filter(&self) -> &str {
+        &self.library_filter
+    }
+    #[must_use]
+    pub const fn is_filtering_library(&self) -> bool {
+        self.library_filtering
+    }
+    #[must_use]
+    pub const fn library_selection(&self) -> usize {
+        self.library_selection
+    }
+    #[must_use]
+    pub fn notice(&self) -> Option<&str> {
+        self.notice.as_ref().map(|(text, _)| text.as_str())
+    }
+    /// Notice set within the last [`NOTICE_DURATION`]
+    #[must_use]
+    pub fn recent_notice(&self) -> Option<&str> {
+        self.notice
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() < NOTICE_DURATION)
+            .map(|(text, _)| text.as_str())
+    }
+    #[must_use]
+    pub const fn overlay(&self) -> Option<Overlay> {
+        self.overlay
+    }
+    #[must_use]
     pub const fn outline_selection(&self) -> usize {
         self.outline_selection
     }
+    #[must_use]
+    pub const fn page_cursor(&self) -> usize {
+        self.page_cursor
+    }
 
     #[must_use]
     pub fn progress(&self) -> f64 {
         if self.tokens.is_empty() {
             0.0
         } else {
-            self.position as f64 / self.tokens.len() as f64
+            self.position as f64 / self.word_count() as f64
         }
     }
 
+    /// Words in the document, estimated while it is still loading
+    #[must_use]
+    pub fn word_count(&self) -> usize {
+        self.estimated_word_count
+            .map_or(self.tokens.len(), |estimate| {
+                estimate.max(self.tokens.len())
+            })
+    }
+
+    /// True while later parts of the document are loading in the background
+    #[must_use]
+    pub const fn is_loading(&self) -> bool {
+        self.estimated_word_count.is_some()
+    }
+
+    /// The document is still loading: `estimated_word_count` is the
+    /// expected total and `pending_sections` the outline entries of the parts
+    /// not loaded yet
+    pub fn set_loading(
+        &mut self,
+        estimated_word_count: usize,
+        pending_sections: Vec<PendingSection>,
+    ) {
+        self.estimated_word_count = Some(estimated_word_count);
+        self.pending_sections = pending_sections;
+    }
+
+    /// The whole document has loaded, or loading stopped
+    pub fn finish_loading(&mut self) {
+        self.estimated_word_count = None;
+        self.pending_sections.clear();
+        self.awaited_position = None;
+        self.awaited_section = None;
+    }
+
+    #[must_use]
+    pub fn pending_sections(&self) -> &[PendingSection] {
+        &self.pending_sections
+    }
+
+    /// Position past the loaded part the reader is waiting for
+    #[must_use]
+    pub const fn awaited_position(&self) -> Option<usize> {
+        self.awaited_position
+    }
+
+    /// Part containing the outline entry the reader is waiting for
+    #[must_use]
+    pub fn awaited_part(&self) -> Option<usize> {
+        self.awaited_section.as_ref().map(|s| s.part)
+    }
+
+    /// Titles and levels of the outline: loaded sections, then the entries
+    /// of parts still loading
+    #[must_use]
+    pub fn outline_entries(&self) -> Vec<(&str, u8)> {
+        self.sections
+            .iter()
+            .map(|s| (s.title.as_str(), s.level))
+            .chain(
+                self.pending_sections
+                    .iter()
+                    .map(|s| (s.title.as_str(), s.level)),
+            )
+            .collect()
+    }
+
     // Mutations
     pub fn toggle_pause(&mut self) {
         self.paused = !self.paused;
@@ -121,9 +375,532 @@ impl App {
         self.wpm = self.wpm.saturating_sub(25).max(100);
     }
 
+    pub fn set_code_blocks(&mut self, code_blocks: Vec<CodeBlock>) {
+        self.code_blocks = code_blocks;
+    }
+
+    pub fn set_footnotes(&mut self, footnotes: Vec<Footnote>) {
+        self.footnotes = footnotes;
+    }
+
+    pub fn set_metadata(&mut self, metadata: DocumentMetadata) {
+        self.metadata = metadata;
+    }
+
+    pub fn set_anchors(&mut self, anchors: HashMap<String, usize>) {
+        self.anchors = anchors;
+    }
+
+    pub fn set_files(&mut self, files: Vec<SourceFile>) {
+        self.files = files;
+    }
+
+    pub fn set_warnings(&mut self, warnings: Vec<ParseWarning>) {
+        self.warnings = warnings;
+    }
+
+    /// Record warnings from a newly loaded part, showing the latest as a
+    /// notice
+    pub fn add_warnings(&mut self, warnings: Vec<ParseWarning>) {
+        if let Some(last) = warnings.last() {
+            self.set_notice(last.to_string());
+        }
+        self.warnings.extend(warnings);
+    }
+
+    /// File containing the current token, for documents combined from
+    /// several files
+    #[must_use]
+    pub fn current_file(&self) -> Option<&SourceFile> {
+        self.files
+            .iter()
+            .rev()
+            .find(|f| f.token_start <= self.position)
+    }
+
+    /// Replace the document being read, keeping settings, link history and
+    /// the queue. Progress in the current queue entry is saved first.
+    ///
+    /// Code blocks, footnotes, metadata, anchors, files and loading state
+    /// belong to the old document and are cleared; set them again for the new
+    /// one.
+    pub fn replace_document(&mut self, tokens: Vec<TimedToken>, sections: Vec<Section>) {
+        self.save_queue_progress();
+        self.record_reading();
+        self.tokens = tokens;
+        self.sections = sections;
+        self.code_blocks.clear();
+        self.footnotes.clear();
+        self.metadata = DocumentMetadata::default();
+        self.anchors.clear();
+        self.files.clear();
+        self.finish_loading();
+        self.overlay = None;
+        self.position = 0;
+        self.view_mode = ViewMode::Reading;
+        self.outline_selection = 0;
+        self.page_cursor = 0;
+    }
+
+    /// Read the given documents back to back, starting with the first
+    pub fn set_queue(&mut self, paths: Vec<PathBuf>) {
+        self.queue = paths.into_iter().map(QueueEntry::new).collect();
+        self.queue_index = 0;
+        self.queue_selection = 0;
+        self.pending_document = None;
+    }
+
+    /// Queue entry for the open document, unless a followed link led
+    /// somewhere else
+    fn open_queue_entry(&mut self) -> Option<&mut QueueEntry> {
+        let path = self.metadata.source_path.as_deref()?;
+        self.queue
+            .get_mut(self.queue_index)
+            .filter(|entry| entry.path == path)
+    }
+
+    /// Remember title, length and position of the open queue document
+    fn save_queue_progress(&mut self) {
+        let title = self.metadata.title.clone();
+        let position = self.position;
+        let token_count = self.word_count();
+        if let Some(entry) = self.open_queue_entry() {
+            entry.title = title;
+            entry.position = position;
+            entry.token_count = Some(token_count);
+        }
+    }
+
+    /// The document just shown is queue entry `index`: resume where it was
+    /// left, or from the start if it was finished
+    pub fn enter_queue_entry(&mut self, index: usize) {
+        if index >= self.queue.len() {
+            return;
+        }
+        self.queue_index = index;
+        self.queue_selection = index;
+        let entry = &self.queue[index];
+        // Entries not opened yet this session start from the saved position
+        if entry.token_count.is_some() && !entry.is_finished() {
+            self.jump_to(entry.position);
+        }
+        self.save_queue_progress();
+    }
+
+    /// Record that queue entry `index` couldn't be opened
+    pub fn set_queue_error(&mut self, index: usize, error: String) {
+        if let Some(entry) = self.queue.get_mut(index) {
+            entry.error = Some(error);
+        }
+    }
+
+    /// Record that queue entry `index` couldn't be opened, say so, and request
+    /// the next readable entry after it
+    pub fn skip_queue_entry(&mut self, index: usize, error: String) {
+        let Some(entry) = self.queue.get(index) else {
+            return;
+        };
+        self.set_notice(format!("Skipped {}: {error}", entry.label()));
+        self.set_queue_error(index, error);
+        self.pending_document = (index + 1..self.queue.len())
+            .find(|&i| self.queue[i].error.is_none())
+            .or(self.pending_document);
+    }
+
+    /// Queue entry the caller should load next, if any
+    pub fn take_pending_document(&mut self) -> Option<usize> {
+        self.pending_document.take()
+    }
+
+    /// Request the next readable document in the queue
+    pub fn next_document(&mut self) {
+        self.pending_document = (self.queue_index + 1..self.queue.len())
+            .find(|&i| self.queue[i].error.is_none())
+            .or(self.pending_document);
+    }
+
+    /// Request the previous readable document in the queue
+    pub fn previous_document(&mut self) {
+        self.pending_document = (0..self.queue_index.min(self.queue.len()))
+            .rev()
+            .find(|&i| self.queue[i].error.is_none())
+            .or(self.pending_document);
+    }
+
+    /// Fraction of queue entry `index` read, live for the open document
+    #[must_use]
+    pub fn queue_progress(&self, index: usize) -> f64 {
+        let is_open =
+            self.metadata.source_path.as_deref() == self.queue.get(index).map(|e| e.path.as_path());
+        if index == self.queue_index && is_open {
+            ReadingProgress {
+                position: self.position,
+                token_count: self.word_count(),
+            }
+            .fraction()
+        } else {
+            self.queue.get(index).map_or(0.0, QueueEntry::progress)
+        }
+    }
+
+    pub fn set_library(&mut self, entries: Vec<LibraryEntry>) {
+        self.library = entries;
+        self.library_selection = 0;
+    }
+
+    /// Save the reading position of the open document in the library.
+    /// Documents not read from a file aren't remembered.
+    pub fn record_reading(&mut self) {
+        let Some(path) = self.metadata.source_path.clone() else {
+            return;
+        };
+        if self.tokens.is_empty() {
+            return;
+        }
+        let key = self.metadata.persistence_key();
+        let title = self.metadata.title.clone().unwrap_or_else(|| {
+            path.file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned()
+        });
+        self.library.retain(|e| e.key != key);
+        self.library.insert(
+            0,
+            LibraryEntry {
+                key,
+                path,
+                title,
+                position: self.position,
+                token_count: self.word_count(),
+                last_read: library::now(),
+            },
+        );
+    }
+
+    /// Continue the open document from its saved library position, unless
+    /// it was read to the end
+    pub fn resume_saved_position(&mut self) {
+        let key = self.metadata.persistence_key();
+        let saved = self
+            .library
+            .iter()
+            .find(|e| e.key == key && !e.is_finished())
+            .map(|e| e.position);
+        if let Some(position) = saved {
+            self.jump_to(position);
+        }
+    }
+
+    /// Show the library view with an empty filter
+    pub fn open_library(&mut self) {
+        self.library_filter.clear();
+        self.library_filtering = false;
+        self.library_selection = 0;
+        self.view_mode = ViewMode::Library;
+    }
+
+    /// Send typed letters to the library filter instead of treating them
+    /// as commands
+    pub fn start_library_filter(&mut self) {
+        self.library_filtering = true;
+    }
+
+    /// Keep the library filter but treat letters as commands again
+    pub fn stop_library_filter(&mut self) {
+        self.library_filtering = false;
+    }
+
+    /// Indices of library entries matching the filter, best match first;
+    /// without a filter, most recently read first
+    #[must_use]
+    pub fn filtered_library(&self) -> Vec<usize> {
+        let mut matches: Vec<(u32, usize)> = self
+            .library
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let text = format!("{} {}", entry.title, entry.path.display());
+                library::fuzzy_score(&self.library_filter, &text).map(|score| (score, index))
+            })
+            .collect();
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        matches.into_iter().map(|(_, index)| index).collect()
+    }
+
+    pub fn library_input(&mut self, c: char) {
+        self.library_filter.push(c);
+        self.library_selection = 0;
+    }
+
+    pub fn library_backspace(&mut self) {
+        self.library_filter.pop();
+        self.library_selection = 0;
+    }
+
+    pub fn library_up(&mut self) {
+        self.library_selection = self.library_selection.saturating_sub(1);
+    }
+
+    pub fn library_down(&mut self) {
+        let count = self.filtered_library().len();
+        if count > 0 {
+            self.library_selection = (self.library_selection + 1).min(count - 1);
+        }
+    }
+
+    /// Library entry under the selection
+    #[must_use]
+    pub fn selected_library_entry(&self) -> Option<&LibraryEntry> {
+        let index = *self.filtered_library().get(self.library_selection)?;
+        self.library.get(index)
+    }
+
+    pub fn set_notice(&mut self, notice: String) {
+        self.notice = Some((notice, Instant::now()));
+    }
+
+    pub fn clear_notice(&mut self) {
+        self.notice = None;
+    }
+
+    /// Replace the document with an edited version of itself, keeping the
+    /// reader on the same words and the current view.
+    ///
+    /// Code blocks, footnotes, metadata, anchors and files are cleared as in
+    /// [`App::replace_document`]; set them again for the new version.
+    pub fn reload_document(&mut self, tokens: Vec<TimedToken>, sections: Vec<Section>) {
+        let old: Vec<&str> = self.tokens.iter().map(|t| t.token.word.as_str()).collect();
+        let new: Vec<&str> = tokens.iter().map(|t| t.token.word.as_str()).collect();
+        let position = map_position(&old, self.position, &new);
+        let page_cursor = map_position(&old, self.page_cursor, &new);
+
+        self.tokens = tokens;
+        self.sections = sections;
+        self.code_blocks.clear();
+        self.footnotes.clear();
+        self.metadata = DocumentMetadata::default();
+        self.anchors.clear();
+        self.files.clear();
+        self.finish_loading();
+        // Overlays refer to blocks by index, which may have shifted
+        self.overlay = None;
+        self.position = position;
+        self.page_cursor = page_cursor;
+        self.outline_selection = self
+            .outline_selection
+            .min(self.sections.len().saturating_sub(1));
+    }
+
+    /// Add newly arrived content to the end of the document, e.g. while
+    /// following a stream or loading a book. Positions in the part start
+    /// from zero and are shifted past the existing tokens; footnotes with a
+    /// label seen before are the same note.
+    pub fn append_document(&mut self, mut part: ParsedDocument) {
+        let first_new_section = self.sections.len();
+        part.shift(self.tokens.len(), &mut self.footnotes, true);
+        self.tokens.extend(timed_tokens(part.tokens, self.wpm));
+        self.sections.append(&mut part.sections);
+        self.code_blocks.append(&mut part.code_blocks);
+        for (name, position) in part.anchors {
+            self.anchors.entry(name).or_insert(position);
+        }
+
+        // The last section runs on into the new content
+        let total = self.tokens.len();
+        let starts: Vec<usize> = self.sections.iter().map(|s| s.token_start).collect();
+        for (i, section) in self.sections.iter_mut().enumerate() {
+            section.token_end = starts.get(i + 1).copied().unwrap_or(total);
+        }
+        self.metadata.word_count = total;
+
+        // Jumps into parts that weren't loaded yet
+        if let Some(position) = self.awaited_position.filter(|&p| p < total) {
+            self.position = position;
+            self.awaited_position = None;
+        }
+        if let Some(awaited) = &self.awaited_section {
+            let start = self.sections[first_new_section..]
+                .iter()
+                .find(|s| s.title == awaited.title && s.level == awaited.level)
+                .map(|s| s.token_start);
+            if let Some(start) = start {
+                self.position = start;
+                self.awaited_section = None;
+            }
+        }
+    }
+
+    /// Link URL at or shortly before the current token
+    #[must_use]
+    pub fn current_link(&self) -> Option<&str> {
+        let start = self.position.saturating_sub(LINK_LOOKBACK);
+        self.tokens
+            .get(start..=self.position)?
+            .iter()
+            .rev()
+            .find_map(|t| match &t.token.style {
+                TokenStyle::Link(url) => Some(url.as_str()),
+                _ => None,
+            })
+    }
+
+    /// Remember the current document and position for [`App::pop_history`]
+    pub fn push_history(&mut self) {
+        self.history.push(HistoryEntry {
+            path: self.metadata.source_path.clone(),
+            position: self.position,
+        });
+    }
+
+    /// Take the most recent history entry to go back to
+    pub fn pop_history(&mut self) -> Option<HistoryEntry> {
+        self.history.pop()
+    }
+
+    /// Jump to an anchor in this document, remembering where we came from.
+    /// Returns false if the anchor doesn't exist.
+    pub fn follow_anchor(&mut self, file: &str, fragment: Option<&str>) -> bool {
+        let Some(position) = find_anchor(&self.anchors, file, fragment) else {
+            return false;
+        };
+        self.push_history();
+        self.jump_to(position);
+        true
+    }
+
+    /// Move to the next word. At the last word of a queued document, the
+    /// next document in the queue is requested instead.
     pub fn advance(&mut self) {
         if self.position < self.tokens.len().saturating_sub(1) {
             self.position += 1;
+            if (self.pause_on_code && self.at_block_start(&BlockContext::CodeBlock))
+                || (self.pause_on_images && self.at_block_start(&BlockContext::Figure))
+            {
+                self.paused = true;
+            }
+        } else if self.pending_document.is_none() && !self.is_loading() {
+            // While loading, playback waits at the last word for more
+            self.next_document();
+        }
+    }
+
+    /// True if the current token is the first token of a block of the given kind
+    fn at_block_start(&self, block: &BlockContext) -> bool {
+        self.current_token()
+            .is_some_and(|t| &t.token.block == block && t.token.timing_hint.is_block_start)
+    }
+
+    /// Index of the code block containing the current token
+    #[must_use]
+    pub fn current_code_block(&self) -> Option<usize> {
+        self.code_blocks
+            .iter()
+            .position(|b| (b.token_start..b.token_end).contains(&self.position))
+    }
+
+    /// Open the code block under the current token in an overlay
+    pub fn open_code_overlay(&mut self) {
+        if let Some(index) = self.current_code_block() {
+            self.overlay = Some(Overlay::Code { index, scroll: 0 });
+            self.paused = true;
+        }
+    }
+
+    /// Footnote referenced at or shortly before the current token
+    #[must_use]
+    pub fn current_footnote(&self) -> Option<usize> {
+        let start = self.position.saturating_sub(FOOTNOTE_LOOKBACK);
+        self.tokens
+            .get(start..=self.position)?
+            .iter()
+            .rev()
+            .find_map(|t| match t.token.style {
+                TokenStyle::FootnoteRef(index) => Some(index),
+                _ => None,
+            })
+            .filter(|&index| index < self.footnotes.len())
+    }
+
+    /// Open the most recently referenced footnote and start reading it
+    pub fn open_footnote_overlay(&mut self) {
+        if let Some(index) = self.current_footnote() {
+            self.overlay = Some(Overlay::Footnote {
+                index,
+                word: 0,
+                resume: !self.paused,
+            });
+            self.paused = false;
+        }
+    }
+
+    /// Close the overlay, returning to the document where reading left off
+    pub fn close_overlay(&mut self) {
+        if let Some(Overlay::Footnote { resume, .. }) = self.overlay {
+            self.paused = !resume;
+        }
+        self.overlay = None;
+    }
+
+    /// Token being shown: the footnote word while a note is read, otherwise
+    /// the current document token
+    #[must_use]
+    pub fn active_token(&self) -> Option<&Token> {
+        match self.overlay {
+            Some(Overlay::Footnote { index, word, .. }) => {
+                self.footnotes.get(index)?.tokens.get(word)
+            }
+            _ => self.current_token().map(|t| &t.token),
+        }
+    }
+
+    /// Whether words should advance on their own
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        !self.paused
+            && self.view_mode == ViewMode::Reading
+            && matches!(self.overlay, None | Some(Overlay::Footnote { .. }))
+    }
+
+    /// Advance whichever word stream is active
+    pub fn tick(&mut self) {
+        match self.overlay {
+            Some(Overlay::Footnote {
+                index,
+                word,
+                resume,
+            }) => {
+                let len = self.footnotes.get(index).map_or(0, |f| f.tokens.len());
+                if word + 1 < len {
+                    self.overlay = Some(Overlay::Footnote {
+                        index,
+                        word: word + 1,
+                        resume,
+                    });
+                } else {
+                    self.close_overlay();
+                }
+            }
+            _ => self.advance(),
+        }
+    }
+
+    pub fn scroll_overlay_down(&mut self) {
+        if let Some(Overlay::Code { index, scroll }) = self.overlay {
+            let max = self.code_blocks[index].line_count().saturating_sub(1);
+            self.overlay = Some(Overlay::Code {
+                index,
+                scroll: (scroll + 1).min(max),
+            });
+        }
+    }
+
+    pub fn scroll_overlay_up(&mut self) {
+        if let Some(Overlay::Code { index, scroll }) = self.overlay {
+            self.overlay = Some(Overlay::Code {
+                index,
+                scroll: scroll.saturating_sub(1),
+            });
         }
     }
 
@@ -135,26 +912,123 @@ impl App {
         self.position = (self.position + 10).min(self.tokens.len().saturating_sub(1));
     }
 
+    /// Jump to a fraction (0.0-1.0) of the document
+    pub fn seek(&mut self, fraction: f64) {
+        // Safe: fraction is clamped, so the product is within 0..=word_count
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let position = (fraction.clamp(0.0, 1.0) * self.word_count() as f64) as usize;
+        self.jump_to(position);
+    }
+
+    /// Jump to a specific token. While the document is loading, a position
+    /// past the loaded part is reached once it has loaded.
+    pub fn jump_to(&mut self, position: usize) {
+        self.awaited_position =
+            (self.is_loading() && position >= self.tokens.len()).then_some(position);
+        self.position = position.min(self.tokens.len().saturating_sub(1));
+    }
+
     pub fn toggle_outline(&mut self) {
         self.view_mode = match self.view_mode {
-            ViewMode::Reading => ViewMode::Outline,
             ViewMode::Outline => ViewMode::Reading,
+            ViewMode::Reading | ViewMode::Page | ViewMode::Queue | ViewMode::Library => {
+                ViewMode::Outline
+            }
+        };
+    }
+
+    /// Switch between reading and the list of queued documents
+    pub fn toggle_queue(&mut self) {
+        self.view_mode = match self.view_mode {
+            ViewMode::Queue => ViewMode::Reading,
+            ViewMode::Reading | ViewMode::Outline | ViewMode::Page | ViewMode::Library => {
+                self.queue_selection = self.queue_index;
+                ViewMode::Queue
+            }
+        };
+    }
+
+    pub fn queue_up(&mut self) {
+        self.queue_selection = self.queue_selection.saturating_sub(1);
+    }
+
+    pub fn queue_down(&mut self) {
+        if !self.queue.is_empty() {
+            self.queue_selection = (self.queue_selection + 1).min(self.queue.len() - 1);
+        }
+    }
+
+    /// Request the selected queue entry and return to reading
+    pub fn open_queue_selection(&mut self) {
+        if self.queue_selection != self.queue_index
+            && self
+                .queue
+                .get(self.queue_selection)
+                .is_some_and(|e| e.error.is_none())
+        {
+            self.pending_document = Some(self.queue_selection);
+        }
+        self.view_mode = ViewMode::Reading;
+    }
+
+    /// Switch between RSVP and the page view, starting the cursor at the current word
+    pub fn toggle_page(&mut self) {
+        self.view_mode = match self.view_mode {
+            ViewMode::Page => ViewMode::Reading,
+            ViewMode::Reading | ViewMode::Outline | ViewMode::Queue | ViewMode::Library => {
+                self.page_cursor = self.position;
+                ViewMode::Page
+            }
         };
     }
 
+    pub fn set_page_cursor(&mut self, position: usize) {
+        self.page_cursor = position.min(self.tokens.len().saturating_sub(1));
+    }
+
+    pub fn page_cursor_prev(&mut self) {
+        self.page_cursor = self.page_cursor.saturating_sub(1);
+    }
+
+    pub fn page_cursor_next(&mut self) {
+        self.set_page_cursor(self.page_cursor + 1);
+    }
+
+    /// Resume RSVP reading from the page cursor
+    pub fn resume_from_page(&mut self) {
+        self.position = self.page_cursor;
+        self.view_mode = ViewMode::Reading;
+    }
+
     pub fn outline_up(&mut self) {
         self.outline_selection = self.outline_selection.saturating_sub(1);
     }
 
     pub fn outline_down(&mut self) {
-        if !self.sections.is_empty() {
-            self.outline_selection = (self.outline_selection + 1).min(self.sections.len() - 1);
+        let count = self.sections.len() + self.pending_sections.len();
+        if count > 0 {
+            self.outline_selection = (self.outline_selection + 1).min(count - 1);
         }
     }
 
+    pub fn select_section(&mut self, index: usize) {
+        if index < self.sections.len() + self.pending_sections.len() {
+            self.outline_selection = index;
+        }
+    }
+
+    /// Jump to the selected outline entry; entries in parts still loading
+    /// are reached once their part has loaded
     pub fn jump_to_section(&mut self) {
-        if let Some(section) = self.sections.get(self.outline_selection) {
+        let selection = self.outline_selection;
+        if let Some(section) = self.sections.get(selection) {
             self.position = section.token_start;
+            self.awaited_position = None;
+            self.awaited_section = None;
+            self.view_mode = ViewMode::Reading;
+        } else if let Some(pending) = self.pending_sections.get(selection - self.sections.len()) {
+            self.awaited_section = Some(pending.clone());
+            self.awaited_position = None;
             self.view_mode = ViewMode::Reading;
         }
     }
@@ -168,11 +1042,28 @@ impl App {
         self.show_help = !self.show_help;
     }
 
+    /// True when the mouse acts on the document: the view shows it and no
+    /// help screen or overlay covers it
+    #[must_use]
+    pub const fn mouse_active(&self) -> bool {
+        !self.show_help
+            && self.overlay.is_none()
+            && matches!(
+                self.view_mode,
+                ViewMode::Reading | ViewMode::Outline | ViewMode::Page
+            )
+    }
+
     /// Toggle visibility of context tokens (above/below RSVP line)
     pub fn toggle_context_tokens(&mut self) {
         self.context_enabled = !self.context_enabled;
     }
 
+    /// Toggle large block-glyph rendering of the focus word
+    pub fn toggle_big_text(&mut self) {
+        self.big_text_enabled = !self.big_text_enabled;
+    }
+
     #[must_use]
     pub const fn context_width(&self) -> usize {
         self.context_width
@@ -0,0 +1,80 @@
+//! Reading input that keeps growing: a pipe or a file being written
+//!
+//! Input is read on a background thread and handed over as text chunks
+//! through a channel, which disconnects when a pipe reaches its end.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use crate::parser::encoding::StreamDecoder;
+use crate::types::Encoding;
+
+/// How often a followed file is checked for new content
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Where followed input comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FollowSource {
+    /// Standard input, until it is closed
+    Stdin,
+    /// A file that is read from the start and then watched for appended
+    /// text, like `tail -f`
+    File(PathBuf),
+}
+
+/// Start reading `source` in the background, decoding it from `encoding`
+/// or the one its byte order mark names
+pub fn spawn(source: FollowSource, encoding: Option<Encoding>) -> io::Result<Receiver<String>> {
+    let (tx, rx) = mpsc::channel();
+    match source {
+        FollowSource::Stdin => {
+            thread::spawn(move || {
+                let mut decoder = StreamDecoder::new(encoding);
+                let _ = read_chunks(io::stdin().lock(), &mut decoder, |text| {
+                    tx.send(text).is_ok()
+                });
+            });
+        }
+        FollowSource::File(path) => {
+            let mut file = File::open(&path)?;
+            thread::spawn(move || {
+                let mut decoder = StreamDecoder::new(encoding);
+                loop {
+                    let open = read_chunks(&mut file, &mut decoder, |text| tx.send(text).is_ok());
+                    // Stop once the reader is gone or the file can't be read
+                    if !matches!(open, Ok(true)) {
+                        break;
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+            });
+        }
+    }
+    Ok(rx)
+}
+
+/// Read until end of input, sending decoded text. Returns false once `send`
+/// reports that nobody is listening anymore.
+fn read_chunks(
+    mut reader: impl Read,
+    decoder: &mut StreamDecoder,
+    mut send: impl FnMut(String) -> bool,
+) -> io::Result<bool> {
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(true),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let text = decoder.decode(&buf[..n]);
+        if !text.is_empty() && !send(text) {
+            return Ok(false);
+        }
+    }
+}
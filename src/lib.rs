@@ -1,8 +1,16 @@
+pub mod align;
 pub mod app;
+pub mod follow;
+pub mod library;
+pub mod links;
 pub mod orp;
 pub mod parser;
+pub mod queue;
 pub mod timing;
 pub mod types;
 pub mod ui;
 
-pub use types::{BlockContext, BlockHint, Section, TimedToken, TimingHint, Token, TokenStyle};
+pub use types::{
+    BlockContext, BlockHint, CodeBlock, DocumentMetadata, Footnote, Section, SourceFile,
+    TimedToken, TimingHint, Token, TokenStyle,
+};
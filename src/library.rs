@@ -0,0 +1,201 @@
+//! Reading state of previously opened documents
+//!
+//! The library is a tab-separated file under `$XDG_STATE_HOME/rsvp-term/`,
+//! one document per line keyed by
+//! [`DocumentMetadata::persistence_key`](crate::types::DocumentMetadata::persistence_key).
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::types::ReadingProgress;
+
+/// First line of the library file
+const HEADER: &str = "# rsvp-term library v1";
+
+/// A previously opened document and where reading stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryEntry {
+    pub key: String,
+    pub path: PathBuf,
+    pub title: String,
+    /// Token to resume reading from
+    pub position: usize,
+    pub token_count: usize,
+    /// Seconds since the Unix epoch
+    pub last_read: u64,
+}
+
+impl LibraryEntry {
+    /// How far the document has been read
+    #[must_use]
+    pub const fn reading(&self) -> ReadingProgress {
+        ReadingProgress {
+            position: self.position,
+            token_count: self.token_count,
+        }
+    }
+
+    /// Fraction read (0.0-1.0)
+    #[must_use]
+    pub fn progress(&self) -> f64 {
+        self.reading().fraction()
+    }
+
+    /// True once the last word has been reached
+    #[must_use]
+    pub const fn is_finished(&self) -> bool {
+        self.reading().is_finished()
+    }
+
+    /// Time to read the remaining words at `wpm`, ignoring pauses
+    #[must_use]
+    pub fn time_remaining(&self, wpm: u16) -> Duration {
+        let words = self.token_count.saturating_sub(self.position + 1) as u64;
+        Duration::from_secs(words * 60 / u64::from(wpm.max(1)))
+    }
+
+    fn to_line(&self) -> String {
+        let clean = |s: &str| s.replace(['\t', '\n', '\r'], " ");
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            clean(&self.key),
+            clean(&self.path.to_string_lossy()),
+            clean(&self.title),
+            self.position,
+            self.token_count,
+            self.last_read
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let entry = Self {
+            key: fields.next()?.to_string(),
+            path: PathBuf::from(fields.next()?),
+            title: fields.next()?.to_string(),
+            position: fields.next()?.parse().ok()?,
+            token_count: fields.next()?.parse().ok()?,
+            last_read: fields.next()?.parse().ok()?,
+        };
+        fields.next().is_none().then_some(entry)
+    }
+}
+
+/// Library file location: `$XDG_STATE_HOME/rsvp-term/library.tsv`, falling
+/// back to `~/.local/state`
+#[must_use]
+pub fn default_path() -> Option<PathBuf> {
+    let state = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state"))
+        })?;
+    Some(state.join("rsvp-term").join("library.tsv"))
+}
+
+/// Parse library file content, most recently read first. Malformed lines
+/// are skipped.
+#[must_use]
+pub fn parse(content: &str) -> Vec<LibraryEntry> {
+    let mut entries: Vec<LibraryEntry> = content
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(LibraryEntry::from_line)
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.last_read));
+    entries
+}
+
+/// Library file content for the given entries
+#[must_use]
+pub fn serialize(entries: &[LibraryEntry]) -> String {
+    let mut out = format!("{HEADER}\n");
+    for entry in entries {
+        out.push_str(&entry.to_line());
+        out.push('\n');
+    }
+    out
+}
+
+/// Load the library; a missing file is an empty library
+pub fn load(path: &Path) -> io::Result<Vec<LibraryEntry>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(parse(&content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Write the library, creating its directory if needed
+pub fn save(path: &Path, entries: &[LibraryEntry]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serialize(entries))
+}
+
+/// Current time in seconds since the Unix epoch
+#[must_use]
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Fuzzy match score of `query` against `text`, `None` if the query's
+/// characters don't all appear in order. Consecutive matches and matches at
+/// word starts score higher.
+#[must_use]
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let mut score = 0;
+    let mut chars = text.chars().flat_map(char::to_lowercase);
+    let mut prev: Option<char> = None;
+    let mut consecutive = false;
+    for q in query.chars().flat_map(char::to_lowercase) {
+        if q.is_whitespace() {
+            continue;
+        }
+        loop {
+            let c = chars.next()?;
+            let at_word_start = prev.is_none_or(|p| !p.is_alphanumeric());
+            prev = Some(c);
+            if c == q {
+                score += 1 + u32::from(consecutive) * 2 + u32::from(at_word_start) * 3;
+                consecutive = true;
+                break;
+            }
+            consecutive = false;
+        }
+    }
+    Some(score)
+}
+
+/// Short relative date such as "today", "3 days ago" or "2 months ago"
+#[must_use]
+pub fn format_last_read(last_read: u64, now: u64) -> String {
+    const DAY: u64 = 24 * 60 * 60;
+    let days = now.saturating_sub(last_read) / DAY;
+    match days {
+        0 => "today".to_string(),
+        1 => "yesterday".to_string(),
+        2..=13 => format!("{days} days ago"),
+        14..=59 => format!("{} weeks ago", days / 7),
+        60..=729 => format!("{} months ago", days / 30),
+        _ => format!("{} years ago", days / 365),
+    }
+}
+
+/// Reading time such as "45 min" or "2 h 10 min"
+#[must_use]
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs().div_ceil(60);
+    if minutes < 60 {
+        format!("{minutes} min")
+    } else if minutes.is_multiple_of(60) {
+        format!("{} h", minutes / 60)
+    } else {
+        format!("{} h {} min", minutes / 60, minutes % 60)
+    }
+}
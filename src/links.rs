@@ -0,0 +1,161 @@
+//! Resolving links between documents
+//!
+//! Links read as [`TokenStyle::Link`](crate::types::TokenStyle::Link) can
+//! point at a heading or anchor in the current document, an EPUB chapter, or
+//! another Markdown file next to the current one.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::parser::obsidian::find_note;
+
+/// Extensions of documents that can be opened by following a link
+const DOCUMENT_EXTENSIONS: &[&str] = &["md", "markdown", "epub"];
+
+/// Where a followable link leads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// Location in the current document. `file` is the EPUB chapter href,
+    /// empty for `#fragment` links.
+    Internal {
+        file: String,
+        fragment: Option<String>,
+    },
+    /// Another document, optionally at an anchor
+    Document {
+        path: PathBuf,
+        fragment: Option<String>,
+    },
+}
+
+/// Resolve a link URL found in the document at `current`.
+///
+/// EPUB hrefs always stay inside the book. Markdown links lead to other
+/// documents relative to the current file; wikilink targets without an
+/// extension are looked up as notes in `vault` (or the file's directory).
+/// Returns `None` for web links and targets that don't exist.
+#[must_use]
+pub fn resolve(url: &str, current: &Path, vault: Option<&Path>) -> Option<LinkTarget> {
+    if has_scheme(url) {
+        return None;
+    }
+    let url = percent_decode(url);
+    let (file, fragment) = match url.split_once('#') {
+        Some((file, fragment)) => (file, Some(fragment.to_string()).filter(|f| !f.is_empty())),
+        None => (url.as_str(), None),
+    };
+
+    let is_epub = current
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("epub"));
+    if is_epub || file.is_empty() {
+        if file.is_empty() && fragment.is_none() {
+            return None;
+        }
+        return Some(LinkTarget::Internal {
+            file: file.to_string(),
+            fragment,
+        });
+    }
+
+    let base = current
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let path = match Path::new(file).extension() {
+        Some(ext) => {
+            if !DOCUMENT_EXTENSIONS
+                .iter()
+                .any(|e| ext.eq_ignore_ascii_case(e))
+            {
+                return None;
+            }
+            Some(base.join(file)).filter(|p| p.is_file())?
+        }
+        None => find_note(vault.unwrap_or(base), file)?,
+    };
+    Some(LinkTarget::Document { path, fragment })
+}
+
+/// Token position of an anchor: a heading slug, an `<a id>` anchor, or a
+/// chapter (EPUB href or book file) with optional fragment
+#[must_use]
+pub fn find_anchor(
+    anchors: &HashMap<String, usize>,
+    file: &str,
+    fragment: Option<&str>,
+) -> Option<usize> {
+    let by_name = |name: &str| {
+        anchors
+            .get(name)
+            .or_else(|| anchors.get(&slugify(name)))
+            .copied()
+    };
+    if file.is_empty() {
+        return by_name(fragment?);
+    }
+
+    // Chapter hrefs are relative to the linking chapter, anchors are keyed
+    // by the chapter's full path in the book
+    let file = file.trim_start_matches("./").trim_start_matches("../");
+    let suffix = format!("/{file}");
+    let chapter = anchors
+        .keys()
+        .filter(|key| !key.contains('#'))
+        .filter(|key| *key == file || key.ends_with(&suffix))
+        .min_by_key(|key| (key.len(), key.as_str()))?;
+    fragment
+        .and_then(|f| {
+            anchors
+                .get(&format!("{chapter}#{f}"))
+                .or_else(|| anchors.get(&format!("{chapter}#{}", slugify(f))))
+                .copied()
+        })
+        .or_else(|| anchors.get(chapter).copied())
+}
+
+/// GitHub-style heading anchor: lowercase, spaces to hyphens, punctuation dropped
+#[must_use]
+pub fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// True for `https:`, `mailto:` and similar URLs; a single letter is a
+/// Windows drive rather than a scheme
+pub(crate) fn has_scheme(url: &str) -> bool {
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Decode `%20`-style escapes, leaving malformed ones as they are
+pub(crate) fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        if let Some(byte) = hex {
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).unwrap_or_else(|_| s.to_string())
+}
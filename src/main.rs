@@ -1,22 +1,32 @@
 use clap::Parser as ClapParser;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
-use ratatui::prelude::*;
+use ratatui::{layout::Rect, prelude::*};
 use std::{
-    io::stdout,
-    time::{Duration, Instant},
+    io::{stdout, IsTerminal},
+    path::{Path, PathBuf},
+    sync::mpsc::{Receiver, TryRecvError},
+    time::{Duration, Instant, SystemTime},
 };
 
 use rsvp_term::{
-    app::{App, ViewMode},
-    orp::calculate_orp,
-    parser::{DocumentParser, EpubParser, MarkdownParser},
-    timing::calculate_duration,
-    types::TimedToken,
-    ui,
+    app::{App, Overlay, ViewMode},
+    follow::{self, FollowSource},
+    library,
+    links::{self, LinkTarget},
+    parser::{
+        BookParser, ChapterLoader, DocumentParser, Encoding, EpubOptions, EpubParser,
+        ExportOptions, MarkdownOptions, MarkdownParser, ParseError, ParsedDocument, StreamParser,
+    },
+    timing::{calculate_duration, timed_tokens},
+    types::{Section, TimedToken},
+    ui::{self, HitTarget},
 };
 
 /// Guard struct that ensures terminal cleanup on all exit paths (including panics).
@@ -25,22 +35,625 @@ struct TerminalGuard;
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
         let _ = disable_raw_mode();
+        let _ = stdout().execute(DisableMouseCapture);
         let _ = stdout().execute(LeaveAlternateScreen);
     }
 }
 
+/// Maximum interval between two clicks to count as a double-click
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Exit code for an invalid command line, the code clap uses for its own
+/// usage errors
+const USAGE_ERROR: i32 = 2;
+
+/// Mouse interaction state carried between events
+#[derive(Default)]
+struct MouseState {
+    /// True while the left button is held after pressing on the progress bar
+    seeking: bool,
+    /// Time and section index of the last outline click (for double-click)
+    last_outline_click: Option<(Instant, usize)>,
+}
+
+/// Follow-up the main loop must perform after handling input
+enum Action {
+    None,
+    Quit,
+    /// Open the link under the reader
+    FollowLink,
+    /// Return to where the last followed link was opened from
+    Back,
+    /// Open a document picked from the library
+    Open(PathBuf),
+}
+
+fn handle_key(app: &mut App, key: KeyEvent, area: Rect) -> Action {
+    // Handle Ctrl+C globally
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        return Action::Quit;
+    }
+
+    // Overlays capture input until closed
+    if app.overlay().is_some() {
+        match key.code {
+            KeyCode::Char('q') => return Action::Quit,
+            KeyCode::Esc | KeyCode::Char('v' | 'f') => app.close_overlay(),
+            KeyCode::Char(' ') if matches!(app.overlay(), Some(Overlay::Footnote { .. })) => {
+                app.toggle_pause();
+            }
+            KeyCode::Char('j') | KeyCode::Down => app.scroll_overlay_down(),
+            KeyCode::Char('k') | KeyCode::Up => app.scroll_overlay_up(),
+            _ => {}
+        }
+        return Action::None;
+    }
+
+    // After `/` in the library, letters go to the filter instead of being commands
+    if app.view_mode() == ViewMode::Library && app.is_filtering_library() {
+        match key.code {
+            KeyCode::Esc => app.open_library(),
+            KeyCode::Enter => {
+                app.stop_library_filter();
+                return open_library_selection(app);
+            }
+            KeyCode::Up => app.library_up(),
+            KeyCode::Down => app.library_down(),
+            KeyCode::Backspace => app.library_backspace(),
+            KeyCode::Char(c) => app.library_input(c),
+            _ => {}
+        }
+        return Action::None;
+    }
+
+    match (app.view_mode(), key.code) {
+        // Global
+        (_, KeyCode::Char('q')) => return Action::Quit,
+        (_, KeyCode::Char('?')) => app.toggle_help(),
+
+        // Reading mode
+        (ViewMode::Reading, KeyCode::Char(' ')) => app.toggle_pause(),
+        (ViewMode::Reading, KeyCode::Char('j') | KeyCode::Down) => app.decrease_wpm(),
+        (ViewMode::Reading, KeyCode::Char('k') | KeyCode::Up) => app.increase_wpm(),
+        (ViewMode::Reading, KeyCode::Char('h') | KeyCode::Left) => app.rewind_sentence(),
+        (ViewMode::Reading, KeyCode::Char('l') | KeyCode::Right) => app.skip_sentence(),
+        (ViewMode::Reading, KeyCode::Char('o')) => app.toggle_outline(),
+        (ViewMode::Reading, KeyCode::Char('c')) => app.toggle_context_tokens(),
+        (ViewMode::Reading, KeyCode::Char('b')) => app.toggle_big_text(),
+        (ViewMode::Reading, KeyCode::Char('p')) => app.toggle_page(),
+        (ViewMode::Reading, KeyCode::Char('v')) => app.open_code_overlay(),
+        (ViewMode::Reading, KeyCode::Char('f')) => app.open_footnote_overlay(),
+        (ViewMode::Reading, KeyCode::Enter) => return Action::FollowLink,
+        (ViewMode::Reading, KeyCode::Backspace) => return Action::Back,
+        (ViewMode::Reading, KeyCode::Char('n')) => app.next_document(),
+        (ViewMode::Reading, KeyCode::Char('N')) => app.previous_document(),
+        (ViewMode::Reading, KeyCode::Char('u')) => app.toggle_queue(),
+
+        // Outline mode
+        (ViewMode::Outline, KeyCode::Char('j') | KeyCode::Down) => app.outline_down(),
+        (ViewMode::Outline, KeyCode::Char('k') | KeyCode::Up) => app.outline_up(),
+        (ViewMode::Outline, KeyCode::Enter) => app.jump_to_section(),
+        (ViewMode::Outline, KeyCode::Esc | KeyCode::Char('o')) => app.toggle_outline(),
+
+        // Queue mode
+        (ViewMode::Queue, KeyCode::Char('j') | KeyCode::Down) => app.queue_down(),
+        (ViewMode::Queue, KeyCode::Char('k') | KeyCode::Up) => app.queue_up(),
+        (ViewMode::Queue, KeyCode::Enter) => app.open_queue_selection(),
+        (ViewMode::Queue, KeyCode::Esc | KeyCode::Char('u')) => app.toggle_queue(),
+
+        // Library mode
+        (ViewMode::Library, KeyCode::Char('/')) => app.start_library_filter(),
+        (ViewMode::Library, KeyCode::Char('j') | KeyCode::Down) => app.library_down(),
+        (ViewMode::Library, KeyCode::Char('k') | KeyCode::Up) => app.library_up(),
+        (ViewMode::Library, KeyCode::Enter) => return open_library_selection(app),
+        (ViewMode::Library, KeyCode::Esc) if !app.library_filter().is_empty() => {
+            app.open_library();
+        }
+        (ViewMode::Library, KeyCode::Esc) => return Action::Quit,
+
+        // Page mode
+        (ViewMode::Page, KeyCode::Char('j') | KeyCode::Down) => {
+            app.set_page_cursor(ui::page_cursor_after_lines(app, area, 1));
+        }
+        (ViewMode::Page, KeyCode::Char('k') | KeyCode::Up) => {
+            app.set_page_cursor(ui::page_cursor_after_lines(app, area, -1));
+        }
+        (ViewMode::Page, KeyCode::PageDown) => {
+            let lines = ui::page_height(area) as isize;
+            app.set_page_cursor(ui::page_cursor_after_lines(app, area, lines));
+        }
+        (ViewMode::Page, KeyCode::PageUp) => {
+            let lines = ui::page_height(area) as isize;
+            app.set_page_cursor(ui::page_cursor_after_lines(app, area, -lines));
+        }
+        (ViewMode::Page, KeyCode::Char('h') | KeyCode::Left) => app.page_cursor_prev(),
+        (ViewMode::Page, KeyCode::Char('l') | KeyCode::Right) => app.page_cursor_next(),
+        (ViewMode::Page, KeyCode::Enter) => app.resume_from_page(),
+        (ViewMode::Page, KeyCode::Esc | KeyCode::Char('p')) => app.toggle_page(),
+
+        _ => {}
+    }
+    Action::None
+}
+
+/// Open the document selected in the library, if any
+fn open_library_selection(app: &App) -> Action {
+    app.selected_library_entry()
+        .map_or(Action::None, |entry| Action::Open(entry.path.clone()))
+}
+
+fn handle_mouse(app: &mut App, mouse: MouseEvent, area: Rect, state: &mut MouseState) {
+    // The help screen, overlays and lists of documents ignore the mouse
+    if !app.mouse_active() {
+        state.seeking = false;
+        return;
+    }
+    let target = ui::hit_test(app, area, mouse.column, mouse.row);
+
+    match mouse.kind {
+        // Scroll wheel adjusts speed while reading
+        MouseEventKind::ScrollUp if app.view_mode() == ViewMode::Reading => app.increase_wpm(),
+        MouseEventKind::ScrollDown if app.view_mode() == ViewMode::Reading => app.decrease_wpm(),
+
+        // Scroll wheel moves the cursor in the page view
+        MouseEventKind::ScrollUp if app.view_mode() == ViewMode::Page => {
+            app.set_page_cursor(ui::page_cursor_after_lines(app, area, -1))
+        }
+        MouseEventKind::ScrollDown if app.view_mode() == ViewMode::Page => {
+            app.set_page_cursor(ui::page_cursor_after_lines(app, area, 1))
+        }
+
+        MouseEventKind::Down(MouseButton::Left) => match target {
+            Some(HitTarget::Progress(fraction)) => {
+                state.seeking = true;
+                app.seek(fraction);
+            }
+            Some(HitTarget::Section(index)) => {
+                let is_double = state.last_outline_click.is_some_and(|(at, prev)| {
+                    prev == index && at.elapsed() < DOUBLE_CLICK_INTERVAL
+                });
+                app.select_section(index);
+                if is_double {
+                    state.last_outline_click = None;
+                    app.jump_to_section();
+                } else {
+                    state.last_outline_click = Some((Instant::now(), index));
+                }
+            }
+            Some(HitTarget::Token(position)) if app.view_mode() == ViewMode::Page => {
+                app.set_page_cursor(position)
+            }
+            Some(HitTarget::Token(position)) => app.jump_to(position),
+            None => {}
+        },
+
+        // Dragging along the progress bar keeps seeking
+        MouseEventKind::Drag(MouseButton::Left) if state.seeking => {
+            if let Some(HitTarget::Progress(fraction)) = target {
+                app.seek(fraction);
+            }
+        }
+        MouseEventKind::Up(MouseButton::Left) => state.seeking = false,
+
+        _ => {}
+    }
+}
+
+/// Parse a markdown file, book directory or EPUB with the reading options
+/// from the command line
+fn load_document(path: &Path, cli: &Cli) -> Result<ParsedDocument, ParseError> {
+    let options = markdown_options(cli);
+    let mut doc = if is_epub(path) {
+        EpubParser::with_options(epub_options(cli)).parse_file(path)
+    } else if BookParser::is_book(path) {
+        BookParser::with_options(options).parse_file(path)
+    } else {
+        MarkdownParser::with_options(options).parse_file(path)
+    }?;
+    // Canonical, so the reading position is found however the file is named
+    doc.metadata.source_path = Some(canonical(path));
+    Ok(doc)
+}
+
+/// Absolute path of `path` with links resolved, or `path` itself if it
+/// can't be resolved
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Parse a document to start reading it. EPUBs open with their first
+/// chapter, and `chapters` is set to load the rest in the background;
+/// with `--strict` they are parsed whole, so a malformed chapter stops the
+/// book from opening.
+fn open_document(
+    path: &Path,
+    cli: &Cli,
+    chapters: &mut Option<ChapterLoader>,
+) -> Result<ParsedDocument, ParseError> {
+    if is_epub(path) && !cli.strict {
+        let (doc, loader) = ChapterLoader::open(path, epub_options(cli))?;
+        *chapters = Some(loader);
+        Ok(doc)
+    } else {
+        let doc = load_document(path, cli)?;
+        *chapters = None;
+        Ok(doc)
+    }
+}
+
+fn is_epub(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("epub"))
+}
+
+/// Markdown reading options from the command line
+fn markdown_options(cli: &Cli) -> MarkdownOptions {
+    MarkdownOptions {
+        code_lines: cli.code_lines,
+        vault: cli.vault.clone(),
+        skip_folded_callouts: cli.skip_folded,
+        encoding: cli.encoding,
+    }
+}
+
+/// A parse error for `path` as a line for the terminal, naming the file
+/// unless the error's location already does
+fn describe_error(path: &Path, error: &ParseError) -> String {
+    if error.location().is_some_and(|l| l.file.is_some()) {
+        error.to_string()
+    } else {
+        format!("{}: {error}", path.display())
+    }
+}
+
+/// EPUB reading options from the command line
+const fn epub_options(cli: &Cli) -> EpubOptions {
+    EpubOptions { strict: cli.strict }
+}
+
+/// Show a parsed document in the app, resuming from its saved position.
+/// `chapters` is loading the rest of the document, if any.
+fn show_document(app: &mut App, doc: ParsedDocument, chapters: Option<&ChapterLoader>) {
+    install_document(app, doc, App::replace_document);
+    if let Some(warning) = app.warnings().first() {
+        app.set_notice(warning.to_string());
+    }
+    if let Some(loader) = chapters.filter(|l| !l.is_complete()) {
+        app.set_loading(loader.estimated_word_count(), loader.pending_sections());
+    }
+}
+
+/// Hand a parsed document to the app through `replace`, which takes the
+/// timed tokens and sections, then set the rest of its parts
+fn install_document(
+    app: &mut App,
+    doc: ParsedDocument,
+    replace: fn(&mut App, Vec<TimedToken>, Vec<Section>),
+) {
+    let timed_tokens = timed_tokens(doc.tokens, app.wpm());
+    replace(app, timed_tokens, doc.sections);
+    app.set_code_blocks(doc.code_blocks);
+    app.set_footnotes(doc.footnotes);
+    app.set_metadata(doc.metadata);
+    app.set_anchors(doc.anchors);
+    app.set_files(doc.files);
+    app.set_warnings(doc.warnings);
+}
+
+/// Quiet time after which followed lines are read without waiting for
+/// the block they are in to end
+const FOLLOW_IDLE: Duration = Duration::from_millis(500);
+
+/// Input being followed and the parser for what has arrived so far
+struct Follow {
+    input: Receiver<String>,
+    parser: StreamParser,
+    /// When input last arrived
+    received: Instant,
+}
+
+impl Follow {
+    /// Append everything received since the last call. Returns false once
+    /// the input has ended.
+    fn update(&mut self, app: &mut App) -> bool {
+        loop {
+            let parsed = match self.input.try_recv() {
+                Ok(text) => {
+                    self.received = Instant::now();
+                    self.parser.push(&text)
+                }
+                Err(TryRecvError::Empty) => {
+                    if self.received.elapsed() >= FOLLOW_IDLE {
+                        if let Ok(Some(doc)) = self.parser.flush() {
+                            append_parsed(app, doc);
+                        }
+                    }
+                    return true;
+                }
+                Err(TryRecvError::Disconnected) => {
+                    if let Ok(Some(doc)) = self.parser.finish() {
+                        append_parsed(app, doc);
+                    }
+                    app.set_notice("end of input".to_string());
+                    return false;
+                }
+            };
+            if let Ok(Some(doc)) = parsed {
+                append_parsed(app, doc);
+            }
+        }
+    }
+}
+
+/// Add newly parsed blocks to the end of the document
+fn append_parsed(app: &mut App, mut doc: ParsedDocument) {
+    // Front matter can only arrive with the first blocks
+    if app.tokens().is_empty() && app.metadata().title.is_none() {
+        app.set_metadata(std::mem::take(&mut doc.metadata));
+    }
+    app.add_warnings(doc.warnings.clone());
+    app.append_document(doc);
+}
+
+/// Append chapters that finished loading, and ask for the next one once the
+/// reader reaches the last or jumps past it. Returns false once the whole
+/// book has loaded.
+fn update_chapters(loader: &mut ChapterLoader, app: &mut App) -> bool {
+    // Another document was opened
+    if app.metadata().source_path.as_deref() != Some(loader.path()) {
+        return false;
+    }
+    while let Some(chapter) = loader.try_next() {
+        match chapter {
+            Ok(doc) => append_parsed(app, doc),
+            Err(e) => app.set_notice(e.to_string()),
+        }
+    }
+    if loader.is_complete() {
+        let awaited = app.awaited_position();
+        app.finish_loading();
+        // The estimate was too high; go as far as there is
+        if let Some(position) = awaited {
+            app.jump_to(position);
+        }
+        return false;
+    }
+
+    let reading_last = app.position() >= loader.last_chapter_start();
+    let awaiting = app
+        .awaited_position()
+        .is_some_and(|p| p >= loader.word_count());
+    if reading_last || awaiting {
+        loader.request_next();
+    }
+    if let Some(chapter) = app.awaited_part() {
+        loader.request(chapter);
+    }
+    app.set_loading(loader.estimated_word_count(), loader.pending_sections());
+    true
+}
+
+/// How often the open document is checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Modification time of the open document, to notice when it is saved
+#[derive(Default)]
+struct FileWatch {
+    /// Document being watched and the newest modification time of its files
+    state: Option<(PathBuf, SystemTime)>,
+    last_check: Option<Instant>,
+}
+
+impl FileWatch {
+    /// True if the open document changed on disk since the last check.
+    /// Switching to another document only starts watching it.
+    fn changed(&mut self, app: &App) -> bool {
+        if self
+            .last_check
+            .is_some_and(|at| at.elapsed() < WATCH_INTERVAL)
+        {
+            return false;
+        }
+        self.last_check = Some(Instant::now());
+
+        let Some(path) = app.metadata().source_path.clone() else {
+            self.state = None;
+            return false;
+        };
+        // Books are watched through their chapter files
+        let modified = if app.files().is_empty() {
+            modified_time(&path)
+        } else {
+            app.files()
+                .iter()
+                .filter_map(|f| modified_time(&f.path))
+                .max()
+        };
+        let Some(modified) = modified else {
+            // Missing while an editor replaces it; check again later
+            return false;
+        };
+
+        let changed =
+            matches!(&self.state, Some((watched, time)) if *watched == path && *time != modified);
+        self.state = Some((path, modified));
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Re-parse the open document after it was edited, keeping the reader's
+/// place. The whole document is parsed, so chapters stop loading.
+fn reload_document(app: &mut App, cli: &Cli, chapters: &mut Option<ChapterLoader>) {
+    let Some(path) = app.metadata().source_path.clone() else {
+        return;
+    };
+    // A half-written file fails to parse; the next save triggers a new reload
+    let Ok(doc) = load_document(&path, cli) else {
+        return;
+    };
+    *chapters = None;
+    install_document(app, doc, App::reload_document);
+    app.set_notice("reloaded".to_string());
+}
+
+/// Start reading a document picked from the library
+fn open_path(app: &mut App, path: PathBuf, cli: &Cli, chapters: &mut Option<ChapterLoader>) {
+    match open_document(&path, cli, chapters) {
+        Ok(doc) => {
+            app.clear_notice();
+            app.set_queue(vec![canonical(&path)]);
+            show_document(app, doc, chapters.as_ref());
+            app.resume_saved_position();
+            app.enter_queue_entry(0);
+        }
+        Err(e) => app.set_notice(format!("Cannot open {}: {e}", path.display())),
+    }
+}
+
+/// Follow the link under the reader to an anchor or another document
+fn follow_link(app: &mut App, cli: &Cli, chapters: &mut Option<ChapterLoader>) {
+    // Links in a book are relative to the chapter file being read
+    let book_file = app.current_file().map(|f| f.path.clone());
+    let Some(current) = book_file
+        .clone()
+        .or_else(|| app.metadata().source_path.clone())
+    else {
+        return;
+    };
+    let Some(target) = app
+        .current_link()
+        .and_then(|url| links::resolve(url, &current, cli.vault.as_deref()))
+    else {
+        return;
+    };
+
+    match target {
+        LinkTarget::Internal { file, fragment } => {
+            let file = match &book_file {
+                Some(path) if file.is_empty() => path.to_string_lossy().into_owned(),
+                _ => file,
+            };
+            app.follow_anchor(&file, fragment.as_deref());
+        }
+        LinkTarget::Document { path, fragment } => {
+            // Other chapters of the book being read are already loaded
+            if let Some(key) = app
+                .files()
+                .iter()
+                .find(|f| same_file(&f.path, &path))
+                .map(|f| f.path.to_string_lossy().into_owned())
+            {
+                app.follow_anchor(&key, fragment.as_deref());
+                return;
+            }
+
+            // Unreadable targets leave the current document open
+            let Ok(doc) = open_document(&path, cli, chapters) else {
+                return;
+            };
+            app.push_history();
+            show_document(app, doc, chapters.as_ref());
+            if let Some(position) =
+                fragment.and_then(|f| links::find_anchor(app.anchors(), "", Some(&f)))
+            {
+                app.jump_to(position);
+            }
+        }
+    }
+}
+
+/// True if both paths name the same file
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Open a queued document, resuming where it was left. Documents that fail
+/// to parse are marked in the queue and skipped for the next readable one.
+fn open_queue_entry(app: &mut App, index: usize, cli: &Cli, chapters: &mut Option<ChapterLoader>) {
+    let Some(path) = app.queue().get(index).map(|e| e.path.clone()) else {
+        return;
+    };
+    match open_document(&path, cli, chapters) {
+        Ok(doc) => {
+            show_document(app, doc, chapters.as_ref());
+            app.resume_saved_position();
+            app.enter_queue_entry(index);
+        }
+        Err(e) => app.skip_queue_entry(index, e.to_string()),
+    }
+}
+
+/// Return to the document and position a link was followed from
+fn go_back(app: &mut App, cli: &Cli, chapters: &mut Option<ChapterLoader>) {
+    let Some(entry) = app.pop_history() else {
+        return;
+    };
+    if entry.path != app.metadata().source_path {
+        let Some(doc) = entry
+            .path
+            .as_deref()
+            .and_then(|p| open_document(p, cli, chapters).ok())
+        else {
+            return;
+        };
+        show_document(app, doc, chapters.as_ref());
+    }
+    app.jump_to(entry.position);
+}
+
 #[derive(ClapParser)]
 #[command(name = "rsvp-term")]
 #[command(about = "TUI for RSVP reading of markdown and EPUB files")]
 #[command(version = env!("CARGO_PKG_VERSION"))]
 struct Cli {
-    /// File to read (markdown or EPUB)
-    file: std::path::PathBuf,
+    /// Files to read (markdown or EPUB) back to back; a directory of markdown
+    /// files is read as a book. Without files, the library is shown.
+    files: Vec<std::path::PathBuf>,
+
+    /// File listing documents to read, one path per line
+    #[arg(long, value_name = "FILE")]
+    queue: Option<std::path::PathBuf>,
+
+    /// Pick a previously opened document to resume
+    #[arg(long, conflicts_with_all = ["files", "queue"])]
+    library: bool,
+
+    /// Don't read or save reading positions in the library
+    #[arg(long, conflicts_with = "library")]
+    no_library: bool,
+
+    /// Read piped input, or a file that is still being written, as it grows
+    #[arg(long, conflicts_with_all = ["queue", "library", "export_md"])]
+    follow: bool,
 
     /// Export EPUB chapters to markdown files instead of reading
     #[arg(long)]
     export_md: bool,
 
+    /// Check that the files can be read and report problems, instead of
+    /// reading them
+    #[arg(long, conflicts_with_all = ["library", "follow", "export_md"])]
+    check: bool,
+
+    /// Stop at a malformed EPUB chapter instead of reading around it
+    #[arg(long)]
+    strict: bool,
+
+    /// Directory to export to (default: one named after the book)
+    #[arg(long, value_name = "DIR", requires = "export_md")]
+    output: Option<std::path::PathBuf>,
+
+    /// Export the whole book to a single markdown file
+    #[arg(long, requires = "export_md")]
+    single_file: bool,
+
     /// Maximum width of context lines in characters (prevents reflow on wide terminals)
     #[arg(long, default_value_t = rsvp_term::app::DEFAULT_CONTEXT_WIDTH)]
     context_width: usize,
@@ -53,6 +666,35 @@ struct Cli {
     #[arg(long)]
     no_styling: bool,
 
+    /// Draw the focus word in large block glyphs (for projectors and distant screens)
+    #[arg(long)]
+    big_text: bool,
+
+    /// Read code blocks line by line instead of showing a placeholder
+    #[arg(long)]
+    code_lines: bool,
+
+    /// Don't pause when reaching a code block
+    #[arg(long)]
+    no_code_pause: bool,
+
+    /// Pause when reaching an image's alt text or caption
+    #[arg(long)]
+    pause_on_images: bool,
+
+    /// Obsidian vault for resolving ![[embeds]] (default: the file's directory)
+    #[arg(long, value_name = "DIR")]
+    vault: Option<std::path::PathBuf>,
+
+    /// Read only the title of folded callouts (> [!note]-)
+    #[arg(long)]
+    skip_folded: bool,
+
+    /// Encoding of text files: utf-8, utf-16le, utf-16be, latin1 or
+    /// windows-1252 (default: detected)
+    #[arg(long, value_name = "ENCODING")]
+    encoding: Option<Encoding>,
+
     /// Initial reading speed in words per minute (default: 300)
     #[arg(short = 'w', long, default_value_t = 300)]
     wpm: u16,
@@ -74,70 +716,215 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let cli = Cli::parse_from(combined);
 
-    // Validate file exists
-    if !cli.file.exists() {
-        eprintln!("Error: File not found: {}", cli.file.display());
-        std::process::exit(1);
+    // Queue file entries come after files given on the command line
+    let mut files = cli.files.clone();
+    if let Some(queue) = &cli.queue {
+        match rsvp_term::queue::read_queue_file(queue) {
+            Ok(paths) => files.extend(paths),
+            Err(e) => {
+                eprintln!("Error: Cannot read queue {}: {e}", queue.display());
+                std::process::exit(USAGE_ERROR);
+            }
+        }
+        if files.is_empty() {
+            eprintln!("Error: Queue {} lists no files", queue.display());
+            std::process::exit(USAGE_ERROR);
+        }
     }
 
-    // Detect file type by extension
-    let ext = cli.file.extension().and_then(|e| e.to_str()).unwrap_or("");
-    let is_epub = ext.eq_ignore_ascii_case("epub");
+    // A single missing file is an error; missing queue entries are skipped
+    if let [file] = files.as_slice() {
+        if !file.exists() {
+            eprintln!("Error: File not found: {}", file.display());
+            std::process::exit(
+                ParseError::IoError(std::io::ErrorKind::NotFound.into()).exit_code(),
+            );
+        }
+    }
 
     // Handle EPUB export mode
     if cli.export_md {
+        let [file] = files.as_slice() else {
+            eprintln!("Error: --export-md takes a single EPUB file");
+            std::process::exit(USAGE_ERROR);
+        };
+        let is_epub = file
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("epub"));
         if !is_epub {
             eprintln!("Error: --export-md only works with EPUB files");
-            std::process::exit(1);
+            std::process::exit(USAGE_ERROR);
+        }
+        let options = ExportOptions {
+            output: cli.output.clone(),
+            single_file: cli.single_file,
+        };
+        let summary =
+            match EpubParser::with_options(epub_options(&cli)).export_chapters(file, &options) {
+                Ok(summary) => summary,
+                Err(e) => {
+                    eprintln!("Error: {}", describe_error(file, &e));
+                    std::process::exit(e.exit_code());
+                }
+            };
+        let destination = if options.single_file {
+            summary.path.display().to_string()
+        } else {
+            format!("{}/", summary.path.display())
+        };
+        let images = match summary.images {
+            0 => String::new(),
+            1 => " and 1 image".to_string(),
+            n => format!(" and {n} images"),
+        };
+        println!(
+            "Exported {} chapters{images} to {destination}",
+            summary.chapters
+        );
+        for warning in &summary.warnings {
+            eprintln!("  warning: {warning}");
         }
-        let parser = EpubParser::new();
-        let (book_title, count) = parser.export_chapters(&cli.file)?;
-        println!("Exported {} chapters to ./{}/", count, book_title);
         return Ok(());
     }
 
-    // Parse document based on file type
-    let doc = if is_epub {
-        EpubParser::new().parse_file(&cli.file)?
+    // Report problems without opening the reader
+    if cli.check {
+        if files.is_empty() {
+            eprintln!("Error: --check needs files to check");
+            std::process::exit(USAGE_ERROR);
+        }
+        // The first failure decides the exit code
+        let mut exit_code = 0;
+        for file in &files {
+            match load_document(file, &cli) {
+                Ok(doc) => {
+                    let encoding = doc
+                        .metadata
+                        .encoding
+                        .map(|e| format!(", {e}"))
+                        .unwrap_or_default();
+                    println!("{}: {} words{encoding}", file.display(), doc.tokens.len());
+                    for warning in &doc.warnings {
+                        eprintln!("  warning: {warning}");
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{}", describe_error(file, &e));
+                    if exit_code == 0 {
+                        exit_code = e.exit_code();
+                    }
+                }
+            }
+        }
+        std::process::exit(exit_code);
+    }
+
+    // Followed input is read in the background and parsed as it arrives
+    let mut follow = if cli.follow {
+        let source = match files.as_slice() {
+            [] if std::io::stdin().is_terminal() => {
+                eprintln!("Error: --follow needs piped input or a file");
+                std::process::exit(USAGE_ERROR);
+            }
+            [] => FollowSource::Stdin,
+            [file] => FollowSource::File(file.clone()),
+            _ => {
+                eprintln!("Error: --follow takes a single file");
+                std::process::exit(USAGE_ERROR);
+            }
+        };
+        Some(Follow {
+            input: follow::spawn(source, cli.encoding)?,
+            parser: StreamParser::with_options(markdown_options(&cli)),
+            received: Instant::now(),
+        })
     } else {
-        MarkdownParser::new().parse_file(&cli.file)?
+        None
     };
 
-    // Convert to timed tokens
-    let wpm = cli.wpm;
-    let timed_tokens: Vec<TimedToken> = doc
-        .tokens
-        .into_iter()
-        .map(|token| {
-            let duration = calculate_duration(&token, wpm);
-            let orp = calculate_orp(&token.word);
-            TimedToken {
-                token,
-                duration_ms: duration,
-                orp_position: orp,
+    // Only the first readable document is parsed up front; the rest load
+    // when reached
+    let mut chapters = None;
+    let mut doc = None;
+    let mut unreadable = Vec::new();
+    if follow.is_none() {
+        for (index, file) in files.iter().enumerate() {
+            match open_document(file, &cli, &mut chapters) {
+                Ok(parsed) => {
+                    doc = Some((index, parsed));
+                    break;
+                }
+                Err(e) => unreadable.push((index, e)),
             }
+        }
+    }
+    if doc.is_none() && !unreadable.is_empty() {
+        for (index, e) in &unreadable {
+            eprintln!("Error: {}", describe_error(&files[*index], e));
+        }
+        std::process::exit(unreadable[0].1.exit_code());
+    }
+
+    if doc.is_none() && follow.is_none() && cli.no_library {
+        eprintln!("Error: No file to read, and --no-library turns off the library");
+        std::process::exit(USAGE_ERROR);
+    }
+
+    // Reading positions of documents opened before
+    let library_path = library::default_path().filter(|_| !cli.no_library);
+    let saved = library_path
+        .as_deref()
+        .map(library::load)
+        .transpose()
+        .unwrap_or_else(|e| {
+            eprintln!("Warning: Cannot read library: {e}");
+            None
         })
-        .collect();
+        .unwrap_or_default();
 
     // Initialize app
     let mut app = App::with_options(
-        timed_tokens,
-        doc.sections,
+        Vec::new(),
+        Vec::new(),
         cli.context_width,
         !cli.no_hint_chars,
         !cli.no_styling,
         true, // context enabled by default
     );
     app.set_wpm(cli.wpm);
+    app.big_text_enabled = cli.big_text;
+    app.pause_on_code = !cli.no_code_pause;
+    app.pause_on_images = cli.pause_on_images;
+    app.set_library(saved.clone());
+    match doc {
+        Some((index, doc)) => {
+            app.set_queue(files.iter().map(|file| canonical(file)).collect());
+            show_document(&mut app, doc, chapters.as_ref());
+            app.enter_queue_entry(index);
+            for (index, e) in &unreadable {
+                app.set_queue_error(*index, e.to_string());
+            }
+            match unreadable.len() {
+                0 => {}
+                1 => app.set_notice("Skipped 1 unreadable document".to_string()),
+                n => app.set_notice(format!("Skipped {n} unreadable documents")),
+            }
+        }
+        None if follow.is_some() => {}
+        None => app.open_library(),
+    }
 
     // Setup terminal
     enable_raw_mode()?;
     stdout().execute(EnterAlternateScreen)?;
+    stdout().execute(EnableMouseCapture)?;
     let _guard = TerminalGuard; // Cleanup guaranteed on drop
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
 
     // Main loop
     let mut last_advance = Instant::now();
+    let mut mouse_state = MouseState::default();
+    let mut watch = FileWatch::default();
 
     loop {
         // Render
@@ -145,77 +932,81 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         // Calculate time until next word using CURRENT wpm (not pre-calculated)
         let next_duration = app
-            .current_token()
-            .map(|t| Duration::from_millis(calculate_duration(&t.token, app.wpm())))
+            .active_token()
+            .map(|t| Duration::from_millis(calculate_duration(t, app.wpm())))
             .unwrap_or(Duration::from_millis(200));
 
         // Handle input with timeout
-        let timeout = if app.is_paused() || app.view_mode() == ViewMode::Outline {
-            Duration::from_millis(100)
-        } else {
+        let timeout = if app.is_playing() {
             let elapsed = last_advance.elapsed();
             next_duration.saturating_sub(elapsed)
+        } else {
+            Duration::from_millis(100)
         };
 
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
+            let event = event::read()?;
+            let size = terminal.size()?;
+            let area = Rect::new(0, 0, size.width, size.height);
+            if let Event::Mouse(mouse) = event {
+                handle_mouse(&mut app, mouse, area, &mut mouse_state);
+            }
+            if let Event::Key(key) = event {
                 if key.kind == KeyEventKind::Press {
-                    // Handle Ctrl+C globally
-                    if key.modifiers.contains(KeyModifiers::CONTROL)
-                        && key.code == KeyCode::Char('c')
-                    {
-                        break;
-                    }
-
-                    match (app.view_mode(), key.code) {
-                        // Global
-                        (_, KeyCode::Char('q')) => break,
-                        (_, KeyCode::Char('?')) => app.toggle_help(),
-
-                        // Reading mode
-                        (ViewMode::Reading, KeyCode::Char(' ')) => app.toggle_pause(),
-                        (ViewMode::Reading, KeyCode::Char('j') | KeyCode::Down) => {
-                            app.decrease_wpm()
-                        }
-                        (ViewMode::Reading, KeyCode::Char('k') | KeyCode::Up) => app.increase_wpm(),
-                        (ViewMode::Reading, KeyCode::Char('h') | KeyCode::Left) => {
-                            app.rewind_sentence()
-                        }
-                        (ViewMode::Reading, KeyCode::Char('l') | KeyCode::Right) => {
-                            app.skip_sentence()
-                        }
-                        (ViewMode::Reading, KeyCode::Char('o')) => app.toggle_outline(),
-                        (ViewMode::Reading, KeyCode::Char('c')) => app.toggle_context_tokens(),
-
-                        // Outline mode
-                        (ViewMode::Outline, KeyCode::Char('j') | KeyCode::Down) => {
-                            app.outline_down()
-                        }
-                        (ViewMode::Outline, KeyCode::Char('k') | KeyCode::Up) => app.outline_up(),
-                        (ViewMode::Outline, KeyCode::Enter) => app.jump_to_section(),
-                        (ViewMode::Outline, KeyCode::Esc | KeyCode::Char('o')) => {
-                            app.toggle_outline()
-                        }
-
-                        _ => {}
+                    match handle_key(&mut app, key, area) {
+                        Action::Quit => break,
+                        Action::FollowLink => follow_link(&mut app, &cli, &mut chapters),
+                        Action::Back => go_back(&mut app, &cli, &mut chapters),
+                        Action::Open(path) => open_path(&mut app, path, &cli, &mut chapters),
+                        Action::None => {}
                     }
                 }
             }
         }
 
         // Advance word if not paused and in reading mode
-        if !app.is_paused()
-            && app.view_mode() == ViewMode::Reading
-            && last_advance.elapsed() >= next_duration
-        {
-            app.advance();
+        if app.is_playing() && last_advance.elapsed() >= next_duration {
+            app.tick();
             last_advance = Instant::now();
         }
+
+        // Append followed input; playback waits at the last word until more arrives
+        if let Some(input) = &mut follow {
+            if !input.update(&mut app) {
+                follow = None;
+            }
+        }
+
+        // Append chapters loaded in the background and ask for the next
+        if let Some(loader) = &mut chapters {
+            if !update_chapters(loader, &mut app) {
+                chapters = None;
+            }
+        }
+
+        // Load the queued document requested by a key or the end of the last one
+        if let Some(index) = app.take_pending_document() {
+            open_queue_entry(&mut app, index, &cli, &mut chapters);
+        }
+
+        // Pick up edits saved in another program
+        if watch.changed(&app) {
+            reload_document(&mut app, &cli, &mut chapters);
+        }
     }
 
     // Cleanup
     disable_raw_mode()?;
+    stdout().execute(DisableMouseCapture)?;
     stdout().execute(LeaveAlternateScreen)?;
 
+    // Remember where reading stopped, if anything was read
+    app.record_reading();
+    if let Some(path) = library_path.as_ref().filter(|_| app.library() != saved) {
+        if let Err(e) = library::save(path, app.library()) {
+            eprintln!("Warning: Cannot save library to {}: {e}", path.display());
+        }
+    }
+
     Ok(())
 }
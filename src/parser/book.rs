@@ -0,0 +1,431 @@
+//! Books made of several Markdown files
+//!
+//! A directory is read as one document. Chapters come from an mdBook
+//! `SUMMARY.md`, from the links in an index file, or else from the Markdown
+//! files in natural order (`ch2.md` before `ch10.md`).
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+
+use markdown_it::plugins::cmark::block::list::ListItem;
+use markdown_it::plugins::cmark::inline::link::Link;
+use markdown_it::{plugins::cmark, MarkdownIt, Node};
+
+use super::frontmatter::parse_toml;
+use super::markdown::{MarkdownOptions, MarkdownParser};
+use super::traits::{DocumentParser, Location, ParseError, ParseWarning, ParsedDocument};
+use crate::links::percent_decode;
+use crate::types::{Encoding, Section, SourceFile};
+
+/// mdBook's table of contents
+const SUMMARY_FILE: &str = "SUMMARY.md";
+
+/// Pages whose links give the chapter order when there is no summary
+const INDEX_FILES: &[&str] = &["index.md", "README.md", "readme.md"];
+
+/// Extensions of files read as chapters
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+/// A chapter file and where it sits in the table of contents
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Chapter {
+    path: PathBuf,
+    /// Title from the table of contents, if it lists one
+    title: Option<String>,
+    /// Nesting depth in the table of contents, 0 for top-level chapters
+    depth: usize,
+}
+
+/// Parser for a directory of Markdown files read as one book.
+pub struct BookParser {
+    md_parser: MarkdownParser,
+}
+
+impl BookParser {
+    /// Create a book parser with default Markdown options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_options(MarkdownOptions::default())
+    }
+
+    /// Create a book parser that reads chapters with the given options.
+    #[must_use]
+    pub fn with_options(options: MarkdownOptions) -> Self {
+        Self {
+            md_parser: MarkdownParser::with_options(options),
+        }
+    }
+
+    /// True if `path` should be read as a book: a directory, or an mdBook
+    /// `SUMMARY.md` standing for its directory.
+    #[must_use]
+    pub fn is_book(path: &Path) -> bool {
+        path.is_dir() || path.file_name().is_some_and(|name| name == SUMMARY_FILE)
+    }
+
+    /// Chapters of the book rooted at `root`, in reading order
+    fn chapters(&self, root: &Path) -> Vec<Chapter> {
+        // mdBook keeps its sources under `src/` next to `book.toml`
+        for dir in [root.to_path_buf(), root.join("src")] {
+            if let Ok((summary, _)) = self.md_parser.read_file(&dir.join(SUMMARY_FILE)) {
+                return summary_chapters(&summary, &dir);
+            }
+        }
+
+        let mut chapters = Vec::new();
+        if let Some(index) = INDEX_FILES
+            .iter()
+            .map(|f| root.join(f))
+            .find(|p| p.is_file())
+        {
+            let content = self
+                .md_parser
+                .read_file(&index)
+                .map(|(content, _)| content)
+                .unwrap_or_default();
+            chapters.push(Chapter {
+                path: index,
+                title: None,
+                depth: 0,
+            });
+            chapters.extend(linked_chapters(&content, root));
+        }
+        if chapters.len() <= 1 {
+            let index = chapters.pop();
+            chapters = markdown_files(root)
+                .into_iter()
+                .filter(|path| Some(path) != index.as_ref().map(|c| &c.path))
+                .map(|path| Chapter {
+                    path,
+                    title: None,
+                    depth: 0,
+                })
+                .collect();
+            chapters.splice(0..0, index);
+        }
+        dedup_chapters(chapters)
+    }
+}
+
+impl Default for BookParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DocumentParser for BookParser {
+    fn parse_file(&self, path: &Path) -> Result<ParsedDocument, ParseError> {
+        let root = if path.is_dir() {
+            path
+        } else {
+            path.parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(Path::new("."))
+        };
+
+        let mut metadata = std::fs::read_to_string(root.join("book.toml"))
+            .map(|config| parse_toml(&config))
+            .unwrap_or_default();
+        if metadata.title.is_none() {
+            let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+            metadata.title = root.file_name().map(|n| n.to_string_lossy().into_owned());
+        }
+
+        let chapters = self.chapters(root);
+        if chapters.is_empty() {
+            return Err(ParseError::EmptyDocument {
+                location: Box::new(Location::file(root)),
+            });
+        }
+
+        let mut book = ParsedDocument {
+            tokens: Vec::new(),
+            sections: Vec::new(),
+            code_blocks: Vec::new(),
+            footnotes: Vec::new(),
+            metadata: metadata.clone(),
+            anchors: std::collections::HashMap::new(),
+            files: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        for (index, chapter) in chapters.iter().enumerate() {
+            // A chapter that can't be read is skipped, like a broken EPUB chapter
+            let Ok((content, encoding)) = self.md_parser.read_file(&chapter.path) else {
+                let name = chapter
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| chapter.path.display().to_string());
+                let location = Location {
+                    file: Some(chapter.path.clone()),
+                    ..Location::chapter(index, name)
+                };
+                book.warnings
+                    .push(ParseWarning::new(location, "skipped unreadable chapter"));
+                continue;
+            };
+            // A book reports the first legacy encoding among its files
+            if metadata.encoding.is_none_or(|e| e == Encoding::Utf8) {
+                metadata.encoding = Some(encoding);
+            }
+            let doc = self.md_parser.parse_document(
+                &content,
+                metadata.language.as_deref(),
+                Some(&chapter.path),
+            )?;
+            append_chapter(&mut book, chapter, doc);
+        }
+
+        // Sections run until the next section, as in a single file
+        let total = book.tokens.len();
+        let starts: Vec<usize> = book.sections.iter().map(|s| s.token_start).collect();
+        for (i, section) in book.sections.iter_mut().enumerate() {
+            section.token_end = starts.get(i + 1).copied().unwrap_or(total);
+        }
+
+        metadata.word_count = total;
+        metadata.source_path = Some(path.to_path_buf());
+        book.metadata = metadata;
+        Ok(book)
+    }
+
+    fn parse_str(&self, _content: &str) -> Result<ParsedDocument, ParseError> {
+        Err(ParseError::UnsupportedFormat {
+            location: Box::default(),
+            message: "Book parser reads directories, not strings".to_string(),
+        })
+    }
+
+    /// A book is a directory of files, so there is nothing to read from a
+    /// single stream
+    fn parse_reader<R: Read + Seek>(&self, _reader: R) -> Result<ParsedDocument, ParseError> {
+        Err(ParseError::UnsupportedFormat {
+            location: Box::default(),
+            message: "Book parser reads directories, not readers".to_string(),
+        })
+    }
+}
+
+/// Append a parsed chapter to the book, shifting its positions and nesting
+/// its headings under a section for the file
+fn append_chapter(book: &mut ParsedDocument, chapter: &Chapter, mut doc: ParsedDocument) {
+    let offset = book.tokens.len();
+    // Notes in different files may share a label without being the same
+    doc.shift(offset, &mut book.footnotes, false);
+    let level = u8::try_from(chapter.depth + 1).unwrap_or(u8::MAX).min(6);
+
+    // A heading at the very start of the file is the chapter title
+    let title_heading = doc
+        .sections
+        .first()
+        .filter(|s| s.token_start == offset && s.level == 1);
+    let title = chapter
+        .title
+        .clone()
+        .or_else(|| doc.metadata.title.clone())
+        .or_else(|| title_heading.map(|s| s.title.clone()))
+        .unwrap_or_else(|| {
+            chapter
+                .path
+                .file_stem()
+                .map_or_else(String::new, |s| s.to_string_lossy().into_owned())
+        });
+
+    book.sections.push(Section {
+        title: title.clone(),
+        level,
+        token_start: offset,
+        token_end: 0,
+    });
+    let skip = usize::from(title_heading.is_some());
+    book.sections
+        .extend(doc.sections.into_iter().skip(skip).map(|s| Section {
+            level: s.level.saturating_add(level).min(6),
+            ..s
+        }));
+
+    let token_count = doc.tokens.len();
+    book.tokens.append(&mut doc.tokens);
+    book.code_blocks.append(&mut doc.code_blocks);
+    book.warnings.append(&mut doc.warnings);
+
+    // Anchors are keyed by file, so links between chapters can find them
+    let key = chapter.path.to_string_lossy().into_owned();
+    for (name, position) in doc.anchors {
+        book.anchors.insert(format!("{key}#{name}"), position);
+    }
+    book.anchors.insert(key, offset);
+
+    book.files.push(SourceFile {
+        path: chapter.path.clone(),
+        title,
+        token_start: offset,
+        token_end: offset + token_count,
+    });
+}
+
+/// Chapters listed in an mdBook `SUMMARY.md`, nested by list.
+///
+/// Draft chapters (`[Title]()`) and part titles are skipped.
+fn summary_chapters(summary: &str, dir: &Path) -> Vec<Chapter> {
+    let chapters = markdown_links(summary)
+        .into_iter()
+        .filter_map(|(title, target, depth)| {
+            Some(Chapter {
+                path: chapter_path(dir, &target)?,
+                title: Some(title),
+                depth,
+            })
+        })
+        .collect();
+    dedup_chapters(chapters)
+}
+
+/// Chapters linked from an index page, in link order
+fn linked_chapters(content: &str, dir: &Path) -> Vec<Chapter> {
+    markdown_links(content)
+        .into_iter()
+        .filter_map(|(title, target, _)| {
+            Some(Chapter {
+                path: chapter_path(dir, &target)?,
+                title: Some(title).filter(|t| !t.is_empty()),
+                depth: 0,
+            })
+        })
+        .collect()
+}
+
+/// Text, target and list depth of the links in a Markdown document, in
+/// order. Links in a top-level list, or outside lists, have depth 0.
+fn markdown_links(content: &str) -> Vec<(String, String, usize)> {
+    fn collect(node: &Node, items: usize, links: &mut Vec<(String, String, usize)>) {
+        if let Some(link) = node.cast::<Link>() {
+            let title = node.collect_text().trim().to_string();
+            links.push((title, link.url.clone(), items.saturating_sub(1)));
+            return;
+        }
+        let items = items + usize::from(node.is::<ListItem>());
+        for child in &node.children {
+            collect(child, items, links);
+        }
+    }
+
+    let mut md = MarkdownIt::new();
+    cmark::add(&mut md);
+    let mut links = Vec::new();
+    collect(&md.parse(content), 0, &mut links);
+    links
+}
+
+/// Existing Markdown file a chapter link points at
+fn chapter_path(dir: &Path, target: &str) -> Option<PathBuf> {
+    let target = percent_decode(target.split('#').next().unwrap_or_default());
+    if target.is_empty() || target.contains("://") || !is_markdown(Path::new(&target)) {
+        return None;
+    }
+    Some(dir.join(target)).filter(|p| p.is_file())
+}
+
+/// Keep the first occurrence of each chapter file
+fn dedup_chapters(chapters: Vec<Chapter>) -> Vec<Chapter> {
+    let mut seen = HashSet::new();
+    chapters
+        .into_iter()
+        .filter(|c| seen.insert(c.path.canonicalize().unwrap_or_else(|_| c.path.clone())))
+        .collect()
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        MARKDOWN_EXTENSIONS
+            .iter()
+            .any(|e| ext.eq_ignore_ascii_case(e))
+    })
+}
+
+/// Markdown files under `root` in natural order, skipping hidden directories
+/// and `SUMMARY.md`
+fn markdown_files(root: &Path) -> Vec<PathBuf> {
+    fn collect(dir: &Path, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for path in entries.filter_map(Result::ok).map(|e| e.path()) {
+            let hidden = path
+                .file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+            if path.is_dir() && !hidden {
+                collect(&path, files);
+            } else if path.is_file()
+                && is_markdown(&path)
+                && path.file_name().is_some_and(|n| n != SUMMARY_FILE)
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    collect(root, &mut files);
+    files.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    files
+}
+
+/// Compare strings with digit runs ordered by value, so "ch2" < "ch10"
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    /// Split off the leading run of digits or non-digits
+    fn chunk(s: &str) -> (&str, &str) {
+        let digits = s.starts_with(|c: char| c.is_ascii_digit());
+        let end = s
+            .find(|c: char| c.is_ascii_digit() != digits)
+            .unwrap_or(s.len());
+        s.split_at(end)
+    }
+
+    let (mut a, mut b) = (a, b);
+    while !a.is_empty() && !b.is_empty() {
+        let (chunk_a, rest_a) = chunk(a);
+        let (chunk_b, rest_b) = chunk(b);
+        let order = match (chunk_a.parse::<u64>(), chunk_b.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y).then(chunk_a.len().cmp(&chunk_b.len())),
+            _ => chunk_a.to_lowercase().cmp(&chunk_b.to_lowercase()),
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+        (a, b) = (rest_a, rest_b);
+    }
+    a.len().cmp(&b.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec!["ch10.md", "ch2.md", "Ch1.md", "appendix.md", "ch02.md"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec!["appendix.md", "Ch1.md", "ch2.md", "ch02.md", "ch10.md"]
+        );
+    }
+
+    #[test]
+    fn test_markdown_links() {
+        let link =
+            |title: &str, target: &str, depth| (title.to_string(), target.to_string(), depth);
+        assert_eq!(
+            markdown_links("- [One](one.md) and [*Two*](<two 2.md#x>)\n    - [Draft]()"),
+            vec![
+                link("One", "one.md", 0),
+                link("Two", "two%202.md#x", 0),
+                link("Draft", "", 1)
+            ]
+        );
+        assert!(markdown_links("no links [here] or `[code](x.md)`").is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+//! EPUB chapters parsed on demand
+//!
+//! Large books open as soon as their first chapter is parsed. A background
+//! thread parses later chapters in reading order when they are asked for,
+//! and the caller appends each one to the document as it arrives.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+use epub::doc::EpubDoc;
+use zip::result::ZipResult;
+use zip::ZipArchive;
+
+use super::epub::{
+    check_encryption, has_images, has_text, no_text_error, open_error, EpubOptions, EpubParser,
+};
+use super::traits::{ParseError, ParsedDocument};
+use super::xhtml::NoteLabels;
+use super::xhtml_tree::TocEntry;
+use crate::types::{DocumentMetadata, PendingSection};
+
+/// The next chapter in reading order from the loading thread, `None` if it
+/// couldn't be read
+type Event = Result<Option<Box<ParsedDocument>>, ParseError>;
+
+/// An EPUB whose chapters load in the background
+pub struct ChapterLoader {
+    path: PathBuf,
+    requests: Sender<usize>,
+    events: Receiver<Event>,
+    /// Table of contents entries with the chapter they point into
+    toc: Vec<(usize, TocEntry)>,
+    chapter_count: usize,
+    /// Uncompressed size of each chapter's XHTML, for estimating the length
+    /// of the book
+    sizes: Option<Vec<usize>>,
+    /// Chapters received so far
+    loaded: usize,
+    /// Chapters asked for so far
+    requested: usize,
+    /// Words received so far
+    word_count: usize,
+    /// Position where the last chapter with words starts
+    last_start: usize,
+}
+
+impl ChapterLoader {
+    /// Open an EPUB and parse chapters until one has words to read. The
+    /// document returned holds those chapters with the book's metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseError::UnsupportedFormat`] if the file isn't an EPUB
+    /// or only has images, [`ParseError::DrmProtected`] if its text is
+    /// encrypted, [`ParseError::EmptyDocument`] if it has nothing to read,
+    /// or [`ParseError::Malformed`] with [`EpubOptions::strict`] if a chapter
+    /// before the first words is malformed.
+    pub fn open(path: &Path, options: EpubOptions) -> Result<(ParsedDocument, Self), ParseError> {
+        let mut doc = EpubDoc::new(path).map_err(|e| open_error(e).in_file(path))?;
+        check_encryption(&mut doc).map_err(|e| e.in_file(path))?;
+        let has_images = has_images(&doc);
+
+        let spine = EpubParser::spine_paths(&doc);
+        let toc: Vec<(usize, TocEntry)> = EpubParser::table_of_contents(&mut doc)
+            .into_iter()
+            .filter_map(|entry| {
+                let file = entry.target.split('#').next()?;
+                let chapter = spine.iter().position(|p| p == file)?;
+                Some((chapter, entry))
+            })
+            .collect();
+        let mut metadata = EpubParser::get_metadata(&doc);
+        // Resolved once here so the persistence key and reload checks agree
+        let source = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        metadata.source_path = Some(source.clone());
+        let sizes = entry_sizes(path).ok().map(|sizes| {
+            spine
+                .iter()
+                .map(|chapter| sizes.get(chapter).copied().unwrap_or(0))
+                .collect()
+        });
+
+        let (requests, request_events) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+        let entries: Vec<TocEntry> = toc.iter().map(|(_, entry)| entry.clone()).collect();
+        let chapter_metadata = DocumentMetadata {
+            language: metadata.language.clone(),
+            ..DocumentMetadata::default()
+        };
+        thread::spawn(move || {
+            load_chapters(
+                doc,
+                options,
+                &entries,
+                &chapter_metadata,
+                &request_events,
+                &event_sender,
+            );
+        });
+
+        let mut loader = Self {
+            path: source,
+            requests,
+            events,
+            toc,
+            chapter_count: spine.len(),
+            sizes,
+            loaded: 0,
+            requested: 0,
+            word_count: 0,
+            last_start: 0,
+        };
+
+        let mut first = ParsedDocument {
+            tokens: Vec::new(),
+            sections: Vec::new(),
+            code_blocks: Vec::new(),
+            footnotes: Vec::new(),
+            metadata,
+            anchors: HashMap::new(),
+            files: Vec::new(),
+            warnings: Vec::new(),
+        };
+        while !has_text(&first.tokens) && !loader.is_complete() {
+            loader.request_next();
+            let Ok(event) = loader.events.recv() else {
+                loader.loaded = loader.chapter_count;
+                break;
+            };
+            if let Some(chapter) = loader.receive(event) {
+                first.append(chapter?);
+            }
+        }
+        if !has_text(&first.tokens) {
+            return Err(no_text_error(has_images).in_file(path));
+        }
+        first.metadata.word_count = first.tokens.len();
+        Ok((first, loader))
+    }
+
+    /// The EPUB being loaded
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Next chapter that has arrived, with positions starting from zero
+    pub fn try_next(&mut self) -> Option<Result<ParsedDocument, ParseError>> {
+        loop {
+            let event = match self.events.try_recv() {
+                Ok(event) => event,
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    self.loaded = self.chapter_count;
+                    return None;
+                }
+            };
+            if let Some(chapter) = self.receive(event) {
+                return Some(chapter);
+            }
+        }
+    }
+
+    /// Record an event, returning the chapter it carries
+    fn receive(&mut self, event: Event) -> Option<Result<ParsedDocument, ParseError>> {
+        self.loaded += 1;
+        match event {
+            Ok(Some(chapter)) => {
+                if !chapter.tokens.is_empty() {
+                    self.last_start = self.word_count;
+                }
+                self.word_count += chapter.tokens.len();
+                Some(Ok(*chapter))
+            }
+            Ok(None) => None,
+            Err(e) => {
+                // Nothing after a failed chapter is loaded
+                self.loaded = self.chapter_count;
+                Some(Err(e.in_file(&self.path)))
+            }
+        }
+    }
+
+    /// Ask for chapters up to and including spine index `chapter`
+    pub fn request(&mut self, chapter: usize) {
+        let count = chapter.saturating_add(1).min(self.chapter_count);
+        if count > self.requested {
+            self.requested = count;
+            let _ = self.requests.send(count);
+        }
+    }
+
+    /// Ask for the chapter after those loaded, unless it is already coming
+    pub fn request_next(&mut self) {
+        self.request(self.loaded);
+    }
+
+    /// True once every chapter has arrived, or loading stopped
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.loaded >= self.chapter_count
+    }
+
+    /// Words loaded so far
+    #[must_use]
+    pub const fn word_count(&self) -> usize {
+        self.word_count
+    }
+
+    /// Position where the last loaded chapter with words starts; reading
+    /// past it is the cue to load the next one
+    #[must_use]
+    pub const fn last_chapter_start(&self) -> usize {
+        self.last_start
+    }
+
+    /// Words in the whole book, estimated from the size of the chapters
+    /// loaded so far until every chapter has arrived
+    #[must_use]
+    pub fn estimated_word_count(&self) -> usize {
+        if self.is_complete() || self.loaded == 0 {
+            return self.word_count;
+        }
+        let loaded_size: usize = self
+            .sizes
+            .as_ref()
+            .map_or(0, |s| s[..self.loaded].iter().sum());
+        let estimate = match &self.sizes {
+            Some(sizes) if loaded_size > 0 => {
+                let total: usize = sizes.iter().sum();
+                self.word_count as f64 * total as f64 / loaded_size as f64
+            }
+            _ => self.word_count as f64 * self.chapter_count as f64 / self.loaded as f64,
+        };
+        // Safe: a word count estimate is far below usize::MAX
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let estimate = estimate.round() as usize;
+        estimate.max(self.word_count)
+    }
+
+    /// Outline entries in chapters that haven't arrived yet
+    #[must_use]
+    pub fn pending_sections(&self) -> Vec<PendingSection> {
+        self.toc
+            .iter()
+            .filter(|(chapter, _)| *chapter >= self.loaded)
+            .map(|(chapter, entry)| PendingSection {
+                title: entry.label.clone(),
+                level: entry.level,
+                part: *chapter,
+            })
+            .collect()
+    }
+}
+
+/// Parse chapters in order as they are asked for, until the receiver goes
+/// away or a chapter fails
+fn load_chapters(
+    mut doc: EpubDoc<std::io::BufReader<std::fs::File>>,
+    options: EpubOptions,
+    toc: &[TocEntry],
+    metadata: &DocumentMetadata,
+    requests: &Receiver<usize>,
+    events: &Sender<Event>,
+) {
+    let parser = EpubParser::with_options(options);
+    let mut labels = NoteLabels::default();
+    let mut wanted = 0;
+    for index in 0..doc.get_num_chapters() {
+        while wanted <= index {
+            let Ok(count) = requests.recv() else {
+                return;
+            };
+            wanted = wanted.max(count);
+        }
+
+        let result = parser
+            .parse_chapter(&mut doc, index, toc, &mut labels, metadata.clone())
+            .map(|chapter| chapter.map(Box::new));
+        let failed = result.is_err();
+        if events.send(result).is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// Uncompressed size of each file in the book's archive, read from the zip
+/// central directory without decompressing anything
+fn entry_sizes(path: &Path) -> ZipResult<HashMap<String, usize>> {
+    let mut archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+    (0..archive.len())
+        .map(|index| {
+            let entry = archive.by_index_raw(index)?;
+            let size = usize::try_from(entry.size()).unwrap_or(usize::MAX);
+            Ok((entry.name().to_string(), size))
+        })
+        .collect()
+}
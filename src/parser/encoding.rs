@@ -0,0 +1,196 @@
+//! Character encodings of text inputs
+//!
+//! Files with a byte order mark are read in the encoding it names. Without
+//! one, text that is valid UTF-8 is taken as UTF-8 and anything else as
+//! Windows-1252, the usual encoding of older Western text and a superset of
+//! Latin-1's printable characters.
+
+use super::traits::{Location, ParseError};
+use crate::types::Encoding;
+
+/// Decode `bytes` to text, in `encoding` or else the one detected, and
+/// return the encoding used. A byte order mark is dropped.
+///
+/// # Errors
+///
+/// Returns [`ParseError::Encoding`] if the text isn't valid in the
+/// encoding, locating the first bad byte for UTF-8.
+pub fn decode(bytes: &[u8], encoding: Option<Encoding>) -> Result<(String, Encoding), ParseError> {
+    let encoding = encoding.unwrap_or_else(|| detect(bytes));
+    let body = bytes.strip_prefix(encoding.bom()).unwrap_or(bytes);
+    let text = match encoding {
+        Encoding::Utf8 => decode_utf8(body)?,
+        Encoding::Utf16Le => decode_utf16(body, u16::from_le_bytes)?,
+        Encoding::Utf16Be => decode_utf16(body, u16::from_be_bytes)?,
+        Encoding::Latin1 => body.iter().map(|&b| char::from(b)).collect(),
+        Encoding::Windows1252 => body.iter().map(|&b| windows_1252(b)).collect(),
+    };
+    Ok((text, encoding))
+}
+
+/// Encoding named by a byte order mark, or UTF-8 for text that is valid
+/// UTF-8, or Windows-1252
+fn detect(bytes: &[u8]) -> Encoding {
+    [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be]
+        .into_iter()
+        .find(|e| bytes.starts_with(e.bom()))
+        .unwrap_or_else(|| {
+            if std::str::from_utf8(bytes).is_ok() {
+                Encoding::Utf8
+            } else {
+                Encoding::Windows1252
+            }
+        })
+}
+
+/// Text of UTF-8 `bytes`, or an encoding error locating the first byte
+/// that isn't
+fn decode_utf8(bytes: &[u8]) -> Result<String, ParseError> {
+    std::str::from_utf8(bytes).map(str::to_string).map_err(|e| {
+        let valid = &bytes[..e.valid_up_to()];
+        let line_start = valid.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        let column = std::str::from_utf8(&valid[line_start..]).map_or(0, |s| s.chars().count());
+        ParseError::Encoding {
+            location: Box::new(Location {
+                line: Some(valid.iter().filter(|&&b| b == b'\n').count() + 1),
+                column: Some(column + 1),
+                ..Location::default()
+            }),
+            message: "text is not valid UTF-8".to_string(),
+        }
+    })
+}
+
+/// Text of UTF-16 `bytes`, with code units read by `unit`
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Result<String, ParseError> {
+    let error = |message: &str| ParseError::Encoding {
+        location: Box::default(),
+        message: message.to_string(),
+    };
+    if !bytes.len().is_multiple_of(2) {
+        return Err(error("UTF-16 text has an odd number of bytes"));
+    }
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| unit([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16(&units).map_err(|_| error("text is not valid UTF-16"))
+}
+
+/// Character for a Windows-1252 byte. Bytes 0x80 to 0x9F are mostly
+/// typographic punctuation; the five the code page leaves undefined keep
+/// their Latin-1 control code.
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž',
+        '\u{8F}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}',
+        'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+/// Decoding of text read in pieces, such as followed input, holding back a
+/// character split across two reads.
+///
+/// A byte order mark at the start picks the encoding, as for [`decode`].
+/// Without one or a configured encoding the input is taken as UTF-8: the
+/// text can't be checked as a whole before it is read, so bytes that aren't
+/// valid UTF-8 become U+FFFD rather than switching to Windows-1252.
+#[derive(Debug, Default)]
+pub struct StreamDecoder {
+    encoding: Option<Encoding>,
+    /// Whether a possible byte order mark has been dealt with
+    started: bool,
+    pending: Vec<u8>,
+}
+
+impl StreamDecoder {
+    /// Decoder for input in `encoding`, or the one detected when `None`
+    #[must_use]
+    pub const fn new(encoding: Option<Encoding>) -> Self {
+        Self {
+            encoding,
+            started: false,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Decode the next piece of input; invalid text becomes U+FFFD
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        if !self.started && !self.start() {
+            return String::new();
+        }
+        let valid = match self.encoding.unwrap_or_default() {
+            Encoding::Utf8 => match std::str::from_utf8(&self.pending) {
+                // An incomplete sequence at the end waits for the next read
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                _ => self.pending.len(),
+            },
+            encoding @ (Encoding::Utf16Le | Encoding::Utf16Be) => {
+                let even = self.pending.len() & !1;
+                let last = self.pending.get(even.saturating_sub(2)..even);
+                let high_surrogate = last.is_some_and(|pair| {
+                    let unit = match encoding {
+                        Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                        _ => u16::from_be_bytes([pair[0], pair[1]]),
+                    };
+                    (0xD800..0xDC00).contains(&unit)
+                });
+                // A surrogate pair split across reads waits for its second half
+                if high_surrogate {
+                    even - 2
+                } else {
+                    even
+                }
+            }
+            Encoding::Latin1 | Encoding::Windows1252 => self.pending.len(),
+        };
+        let text = self.text(&self.pending[..valid]);
+        self.pending.drain(..valid);
+        text
+    }
+
+    /// Pick the encoding and drop a byte order mark from the pending input.
+    /// Returns false while the input so far may be the start of one.
+    fn start(&mut self) -> bool {
+        let candidates = match self.encoding {
+            Some(encoding) => vec![encoding],
+            None => vec![Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be],
+        };
+        if let Some(&encoding) = candidates
+            .iter()
+            .find(|e| self.pending.starts_with(e.bom()))
+        {
+            self.pending.drain(..encoding.bom().len());
+            self.encoding = Some(encoding);
+        } else if candidates
+            .iter()
+            .any(|e| e.bom().starts_with(&self.pending))
+        {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    /// Lossy text of complete `bytes` in the decoder's encoding
+    fn text(&self, bytes: &[u8]) -> String {
+        let utf16 = |unit: fn([u8; 2]) -> u16| {
+            let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+            char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect()
+        };
+        match self.encoding.unwrap_or_default() {
+            Encoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            Encoding::Utf16Le => utf16(u16::from_le_bytes),
+            Encoding::Utf16Be => utf16(u16::from_be_bytes),
+            Encoding::Latin1 => bytes.iter().map(|&b| char::from(b)).collect(),
+            Encoding::Windows1252 => bytes.iter().map(|&b| windows_1252(b)).collect(),
+        }
+    }
+}
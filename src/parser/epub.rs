@@ -1,29 +1,55 @@
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 use std::fs;
-use std::io::Cursor;
-use std::path::Path;
-
-use epub::doc::EpubDoc;
-
-use super::markdown::MarkdownParser;
-use super::traits::{DocumentParser, ParseError, ParsedDocument};
-
-/// EPUB parser that extracts content and converts to tokens via markdown.
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+
+use epub::doc::{DocError, EpubDoc, NavPoint};
+use markdown_it::plugins::cmark::inline::{image::Image, link::Link};
+use markdown_it::plugins::html::html_inline::HtmlInline;
+use markdown_it::Node;
+
+use super::markdown::{MarkdownParser, IMAGE_MARKER};
+use super::markdown_writer;
+use super::traits::{DocumentParser, Location, ParseError, ParseWarning, ParsedDocument};
+use super::xhtml::{self, NoteLabels};
+use super::xhtml_tree::{self, TocEntry};
+use crate::links::{has_scheme, percent_decode};
+use crate::types::{BlockContext, DocumentMetadata, Section, Token};
+
+/// EPUB parser that reads chapter XHTML into the markdown document tree.
 pub struct EpubParser {
     md_parser: MarkdownParser,
+    options: EpubOptions,
+}
+
+/// How [`EpubParser`] treats broken books
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EpubOptions {
+    /// Fail on a malformed chapter instead of reading around it
+    pub strict: bool,
 }
 
 impl EpubParser {
     #[must_use]
     pub fn new() -> Self {
+        Self::with_options(EpubOptions::default())
+    }
+
+    /// Create an EPUB parser that handles malformed chapters as `options` say
+    #[must_use]
+    pub fn with_options(options: EpubOptions) -> Self {
         Self {
             md_parser: MarkdownParser::new(),
+            options,
         }
     }
 
-    /// Convert XHTML content to markdown using html2text.
-    fn xhtml_to_markdown(xhtml: &str) -> String {
-        html2text::from_read(Cursor::new(xhtml), 10000).unwrap_or_default()
+    /// Path of the current chapter inside the book
+    fn current_chapter_path<R: Read + Seek>(doc: &EpubDoc<R>) -> String {
+        doc.get_current_path()
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default()
     }
 
     /// Sanitize a string for use as a filename.
@@ -42,9 +68,10 @@ impl EpubParser {
     }
 
     /// Get the book title from EPUB metadata or filename.
-    fn get_book_title(doc: &EpubDoc<std::io::BufReader<std::fs::File>>, path: &Path) -> String {
-        doc.mdata("title")
-            .map(|item| Self::sanitize_filename(&item.value))
+    fn get_book_title<R: Read + Seek>(doc: &EpubDoc<R>, path: &Path) -> String {
+        Self::get_metadata(doc)
+            .title
+            .map(|title| Self::sanitize_filename(&title))
             .filter(|s| !s.is_empty())
             .unwrap_or_else(|| {
                 path.file_stem()
@@ -54,75 +81,442 @@ impl EpubParser {
             })
     }
 
-    /// Get chapter title from TOC for current chapter index.
-    fn get_chapter_title(
-        doc: &EpubDoc<std::io::BufReader<std::fs::File>>,
-        chapter_idx: usize,
-    ) -> Option<String> {
-        // Get the current spine item's path
-        let spine_item = doc.spine.get(chapter_idx)?;
-        let resource = doc.resources.get(&spine_item.idref)?;
-        let resource_path = resource.path.to_string_lossy();
+    /// Title, authors, language, identifiers and subjects from the EPUB
+    /// package metadata.
+    pub(crate) fn get_metadata<R: Read + Seek>(doc: &EpubDoc<R>) -> DocumentMetadata {
+        let values = |property: &str| -> Vec<String> {
+            doc.metadata
+                .iter()
+                .filter(|item| item.property == property)
+                .map(|item| item.value.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect()
+        };
+        DocumentMetadata {
+            title: values("title").into_iter().next(),
+            authors: values("creator"),
+            language: values("language").into_iter().next(),
+            identifiers: values("identifier"),
+            tags: values("subject"),
+            ..DocumentMetadata::default()
+        }
+    }
 
-        // Find matching TOC entry
-        doc.toc
+    /// The book's table of contents, flattened in reading order.
+    ///
+    /// An EPUB 3 navigation document is preferred; EPUB 2 books use the NCX.
+    pub(crate) fn table_of_contents<R: Read + Seek>(doc: &mut EpubDoc<R>) -> Vec<TocEntry> {
+        let nav = doc.get_nav_id().and_then(|id| {
+            let path = doc
+                .resources
+                .get(&id)?
+                .path
+                .to_string_lossy()
+                .replace('\\', "/");
+            let (xhtml, _mime) = doc.get_resource_str(&id)?;
+            Some(xhtml_tree::nav_entries(&xhtml, &path))
+        });
+        match nav {
+            Some(entries) if !entries.is_empty() => entries,
+            _ => {
+                let mut entries = Vec::new();
+                Self::flatten_nav_points(&doc.toc, 1, &mut entries);
+                entries
+            }
+        }
+    }
+
+    fn flatten_nav_points(points: &[NavPoint], level: u8, entries: &mut Vec<TocEntry>) {
+        for point in points {
+            let content = point.content.to_string_lossy().replace('\\', "/");
+            entries.push(TocEntry {
+                label: point.label.split_whitespace().collect::<Vec<_>>().join(" "),
+                target: xhtml_tree::toc_target("", &content),
+                level,
+            });
+            Self::flatten_nav_points(&point.children, level.saturating_add(1), entries);
+        }
+    }
+
+    /// Title of the first TOC entry pointing into a chapter
+    fn chapter_title(toc: &[TocEntry], chapter_path: &str) -> Option<String> {
+        toc.iter()
+            .find(|entry| entry.target.split('#').next() == Some(chapter_path))
+            .map(|entry| entry.label.clone())
+    }
+
+    /// Nodes opening a chapter: an anchor so links between chapters can be
+    /// followed, then its title from the table of contents read as a
+    /// heading, unless the chapter's `body` already opens with that heading
+    fn chapter_start(toc: &[TocEntry], chapter_path: &str, body: &[Node]) -> Vec<Node> {
+        let mut nodes = vec![xhtml_tree::anchor(chapter_path)];
+        if let Some(title) = Self::chapter_title(toc, chapter_path)
+            .filter(|title| !xhtml_tree::opens_with_heading(body, title))
+        {
+            nodes.push(xhtml_tree::heading(1, &title));
+        }
+        nodes
+    }
+
+    /// Outline sections for TOC entries, placed through the document's
+    /// anchors. Entries whose target isn't found, such as a fragment missing
+    /// from its chapter or a file outside the spine, are dropped.
+    fn toc_sections(
+        toc: &[TocEntry],
+        anchors: &HashMap<String, usize>,
+        token_count: usize,
+    ) -> Vec<Section> {
+        let mut sections: Vec<Section> = toc
             .iter()
-            .find(|nav| nav.content.ends_with(resource_path.as_ref()))
-            .map(|nav| nav.label.clone())
+            .filter_map(|entry| {
+                let start = anchors.get(&entry.target)?;
+                Some(Section {
+                    title: entry.label.clone(),
+                    level: entry.level,
+                    token_start: (*start).min(token_count),
+                    token_end: token_count,
+                })
+            })
+            .collect();
+        sections.sort_by_key(|s| s.token_start);
+        for i in 1..sections.len() {
+            sections[i - 1].token_end = sections[i].token_start;
+        }
+        sections
     }
 
-    /// Export each chapter as a separate markdown file.
+    /// XHTML and book path of spine item `index`, `None` if it can't be
+    /// read.
     ///
-    /// Creates a directory named after the book title and writes each
-    /// chapter as a numbered markdown file.
+    /// Malformed chapters are read as far as they can be, with a warning
+    /// added to `warnings`; chapters that can't be read at all are skipped
+    /// with one.
     ///
     /// # Errors
     ///
-    /// Returns [`ParseError::ParseError`] if the EPUB cannot be opened.
-    /// Returns [`ParseError::IoError`] if directory creation or file writing fails.
-    pub fn export_chapters(&self, path: &Path) -> Result<(String, usize), ParseError> {
-        let mut doc = EpubDoc::new(path)
-            .map_err(|e| ParseError::ParseError(format!("Failed to open EPUB: {e}")))?;
+    /// Returns [`ParseError::Malformed`] if the chapter is malformed XHTML
+    /// and the parser is strict.
+    fn chapter_content<R: Read + Seek>(
+        &self,
+        doc: &mut EpubDoc<R>,
+        index: usize,
+        toc: &[TocEntry],
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Option<(String, String)>, ParseError> {
+        doc.set_current_chapter(index);
+        let chapter_path = Self::current_chapter_path(doc);
+        let location = || {
+            let name =
+                Self::chapter_title(toc, &chapter_path).unwrap_or_else(|| chapter_path.clone());
+            Location::chapter(index, name)
+        };
+        let Some((content, _mime)) = doc.get_current_str() else {
+            warnings.push(ParseWarning::new(location(), "skipped unreadable chapter"));
+            return Ok(None);
+        };
+
+        // A browser's error report means the chapter didn't parse as XML
+        if content.contains("<parsererror") {
+            if self.options.strict {
+                let (line, column) = error_position(&content);
+                return Err(ParseError::Malformed {
+                    location: Box::new(Location {
+                        line,
+                        column,
+                        ..location()
+                    }),
+                    message: "chapter is not well-formed XHTML".to_string(),
+                });
+            }
+            if !xhtml_tree::has_content(&content) {
+                warnings.push(ParseWarning::new(location(), "skipped malformed chapter"));
+                return Ok(None);
+            }
+            warnings.push(ParseWarning::new(
+                location(),
+                "malformed XHTML; read what could be recovered",
+            ));
+        }
+        Ok(Some((content, chapter_path)))
+    }
 
-        let book_title = Self::get_book_title(&doc, path);
-        let output_dir = Path::new(&book_title);
+    /// Parse spine item `index` on its own, with positions starting from
+    /// zero. With a table of contents, the chapter's sections are its TOC
+    /// entries; `labels` numbers notes across the book. A skipped chapter
+    /// comes back empty with its warning.
+    pub(crate) fn parse_chapter<R: Read + Seek>(
+        &self,
+        doc: &mut EpubDoc<R>,
+        index: usize,
+        toc: &[TocEntry],
+        labels: &mut NoteLabels,
+        metadata: DocumentMetadata,
+    ) -> Result<Option<ParsedDocument>, ParseError> {
+        let mut warnings = Vec::new();
+        let chapter = self.chapter_content(doc, index, toc, &mut warnings)?;
+        let Some((content, chapter_path)) = chapter else {
+            if warnings.is_empty() {
+                return Ok(None);
+            }
+            let mut skipped = self.md_parser.parse_tree(&xhtml_tree::root(), metadata);
+            skipped.warnings = warnings;
+            return Ok(Some(skipped));
+        };
+        let mut root = xhtml_tree::root();
+        root.children.push(xhtml_tree::anchor(&chapter_path));
+        root.children
+            .extend(xhtml_tree::chapter_nodes(&content, &chapter_path, labels));
+
+        let mut parsed = self.md_parser.parse_tree(&root, metadata);
+        parsed.warnings = warnings;
+        if !toc.is_empty() {
+            let entries: Vec<TocEntry> = toc
+                .iter()
+                .filter(|entry| entry.target.split('#').next() == Some(chapter_path.as_str()))
+                .cloned()
+                .collect();
+            parsed.sections = Self::toc_sections(&entries, &parsed.anchors, parsed.tokens.len());
+        }
+        Ok(Some(parsed))
+    }
+
+    /// Parse every chapter of an opened book into one document.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseError::DrmProtected`] if the text is encrypted,
+    /// [`ParseError::UnsupportedFormat`] or [`ParseError::EmptyDocument`] if
+    /// there are no words, with or without images, and
+    /// [`ParseError::Malformed`] if a chapter is malformed XHTML and the
+    /// parser is strict.
+    fn parse_book<R: Read + Seek>(
+        &self,
+        doc: &mut EpubDoc<R>,
+    ) -> Result<ParsedDocument, ParseError> {
+        check_encryption(doc)?;
+        let mut root = xhtml_tree::root();
+        let mut labels = NoteLabels::default();
+        let toc = Self::table_of_contents(doc);
+        let mut warnings = Vec::new();
+
+        for i in 0..doc.get_num_chapters() {
+            let Some((content, chapter_path)) =
+                self.chapter_content(doc, i, &toc, &mut warnings)?
+            else {
+                continue;
+            };
+
+            let nodes = xhtml_tree::chapter_nodes(&content, &chapter_path, &mut labels);
+            root.children
+                .extend(Self::chapter_start(&toc, &chapter_path, &nodes));
+            root.children.extend(nodes);
+        }
 
-        // Create output directory
-        fs::create_dir_all(output_dir)?;
+        // Walk all chapters at once so notes and anchors share one document
+        let metadata = Self::get_metadata(doc);
+        let mut parsed = self.md_parser.parse_tree(&root, metadata);
+        if !has_text(&parsed.tokens) {
+            return Err(no_text_error(has_images(doc)));
+        }
+        if parsed
+            .tokens
+            .iter()
+            .all(|t| t.block == BlockContext::Figure)
+        {
+            warnings.push(ParseWarning::new(
+                Location::default(),
+                "the book only has images; reading their descriptions",
+            ));
+        }
+        parsed.warnings = warnings;
 
-        let num_chapters = doc.get_num_chapters();
-        let mut exported_count = 0;
+        // The outline follows the book's own table of contents when it has one
+        let sections = Self::toc_sections(&toc, &parsed.anchors, parsed.tokens.len());
+        if !sections.is_empty() {
+            parsed.sections = sections;
+        }
+        Ok(parsed)
+    }
 
-        for i in 0..num_chapters {
-            doc.set_current_chapter(i);
+    /// Book paths of the spine items, in reading order
+    pub(crate) fn spine_paths<R: Read + Seek>(doc: &EpubDoc<R>) -> Vec<String> {
+        doc.spine
+            .iter()
+            .map(|item| {
+                doc.resources
+                    .get(&item.idref)
+                    .map(|r| r.path.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
 
-            // Get chapter content
-            let Some((content, _mime)) = doc.get_current_str() else {
-                continue; // Skip empty chapters
-            };
+    /// Export the book as markdown: a file per chapter and an `index.md`
+    /// listing them, or a single file with [`ExportOptions::single_file`].
+    ///
+    /// Files are written to [`ExportOptions::output`], or a directory named
+    /// after the book title. Each file starts with YAML front matter, images
+    /// are copied into `assets/`, and links between chapters point at the
+    /// exported files.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseError::UnsupportedFormat`] if the file isn't an EPUB.
+    /// Returns [`ParseError::DrmProtected`] if its text is encrypted.
+    /// Returns [`ParseError::Malformed`] if a chapter is malformed XHTML and
+    /// the parser is strict, before anything is written.
+    /// Returns [`ParseError::IoError`] if it can't be read, or directory
+    /// creation or file writing fails.
+    pub fn export_chapters(
+        &self,
+        path: &Path,
+        options: &ExportOptions,
+    ) -> Result<ExportSummary, ParseError> {
+        let mut doc = EpubDoc::new(path).map_err(|e| open_error(e).in_file(path))?;
+        check_encryption(&mut doc).map_err(|e| e.in_file(path))?;
 
-            // Convert to markdown
-            let markdown = Self::xhtml_to_markdown(&content);
-            if markdown.trim().is_empty() {
-                continue; // Skip empty content
+        let book_title = Self::get_book_title(&doc, path);
+        let output_dir = options
+            .output
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(&book_title));
+
+        let mut metadata = Self::get_metadata(&doc);
+        let title = metadata.title.take().unwrap_or_else(|| {
+            path.file_stem()
+                .map_or_else(String::new, |s| s.to_string_lossy().into_owned())
+        });
+        let toc = Self::table_of_contents(&mut doc);
+
+        // Name every chapter first, so links can point at chapters further on
+        let mut chapters = Vec::new();
+        let mut warnings = Vec::new();
+        let mut labels = NoteLabels::default();
+        for index in 0..doc.get_num_chapters() {
+            let Some((content, chapter_path)) = self
+                .chapter_content(&mut doc, index, &toc, &mut warnings)
+                .map_err(|e| e.in_file(path))?
+            else {
+                continue;
+            };
+            if !xhtml_tree::has_content(&content) {
+                continue; // Skip empty chapters such as cover pages
             }
 
-            exported_count += 1;
+            let number = chapters.len() + 1;
+            let title = Self::chapter_title(&toc, &chapter_path);
+            let target = if options.single_file {
+                format!("#chapter-{number:02}")
+            } else {
+                let name = title
+                    .as_deref()
+                    .map(Self::sanitize_filename)
+                    .filter(|s| !s.is_empty())
+                    .unwrap_or_else(|| format!("chapter-{number:02}"));
+                format!("{number:02}-{name}.md")
+            };
+            chapters.push(ExportChapter {
+                nodes: xhtml_tree::chapter_nodes(&content, &chapter_path, &mut labels),
+                path: chapter_path,
+                title: title.unwrap_or_else(|| format!("Chapter {number}")),
+                target,
+            });
+        }
 
-            // Get chapter title from TOC or use fallback
-            let chapter_title = Self::get_chapter_title(&doc, i)
-                .map(|t| Self::sanitize_filename(&t))
-                .filter(|s| !s.is_empty())
-                .unwrap_or_else(|| format!("chapter-{exported_count:02}"));
+        // Nothing is written for a book that fails a strict check
+        fs::create_dir_all(&output_dir)?;
 
-            // Write chapter file
-            let filename = format!("{exported_count:02}-{chapter_title}.md");
-            let filepath = output_dir.join(&filename);
+        let targets: HashMap<String, String> = chapters
+            .iter()
+            .map(|c| (c.path.clone(), c.target.clone()))
+            .collect();
+        // Element ids that links point to, as `path#id`
+        let mut linked = HashSet::new();
+        for chapter in &chapters {
+            for node in &chapter.nodes {
+                node.walk(|node, _| {
+                    if let Some(link) = node.cast::<Link>().filter(|l| !has_scheme(&l.url)) {
+                        linked.insert(xhtml::resolve_href(
+                            &chapter.path,
+                            &percent_decode(&link.url),
+                        ));
+                    }
+                });
+            }
+        }
+        let mut assets = ExportAssets::new(output_dir.join(ASSETS_DIR));
+        let mut book = String::new();
 
-            fs::write(&filepath, &markdown)?;
+        for (i, chapter) in chapters.iter_mut().enumerate() {
+            let link_target = |href: &str| {
+                if has_scheme(href) {
+                    return None;
+                }
+                let resolved = xhtml::resolve_href(&chapter.path, &percent_decode(href));
+                let (file, fragment) = resolved
+                    .split_once('#')
+                    .map_or((resolved.as_str(), None), |(file, id)| (file, Some(id)));
+                let target = targets.get(file)?;
+                // Element ids are kept as anchors in the exported chapters
+                Some(match fragment.filter(|id| !id.is_empty()) {
+                    Some(id) if options.single_file => {
+                        format!("#{}", link_path(&export_anchor(options, target, id)))
+                    }
+                    Some(id) => format!("{}#{}", link_path(target), link_path(id)),
+                    None => link_path(target),
+                })
+            };
+            // Point links and images at the exported files, and keep the
+            // anchors that links lead to
+            for node in &mut chapter.nodes {
+                node.walk_mut(|node, _| {
+                    if let Some(target) = xhtml_tree::anchor_target(node) {
+                        let id = target.split_once('#').map_or("", |(_, id)| id);
+                        let content = linked.contains(&target).then(|| {
+                            let name = export_anchor(options, &chapter.target, id);
+                            format!("<a id=\"{}\"></a>", name.replace('"', "&quot;"))
+                        });
+                        if let Some(html) = node.cast_mut::<HtmlInline>() {
+                            html.content = content.unwrap_or_default();
+                        }
+                    } else if let Some(link) = node.cast_mut::<Link>() {
+                        if let Some(target) = link_target(&link.url) {
+                            link.url = target;
+                        }
+                    } else if let Some(image) = node.cast_mut::<Image>() {
+                        image.url = assets.link(&mut doc, &chapter.path, &image.url);
+                    }
+                });
+            }
+            let markdown = markdown_writer::write(&chapter.nodes);
+            assets.check()?;
+
+            if options.single_file {
+                let _ = write!(book, "<a id=\"chapter-{:02}\"></a>\n\n{markdown}\n", i + 1);
+            } else {
+                let front_matter =
+                    export_front_matter(&chapter.title, Some((&title, i + 1)), &metadata);
+                fs::write(output_dir.join(&chapter.target), front_matter + &markdown)?;
+            }
         }
 
-        Ok((book_title, exported_count))
+        let mut index = export_front_matter(&title, None, &metadata);
+        let _ = write!(index, "# {title}\n\n{}", export_contents(&toc, &chapters));
+        let path = if options.single_file {
+            let file = output_dir.join(format!("{book_title}.md"));
+            fs::write(&file, index + "\n" + &book)?;
+            file
+        } else {
+            fs::write(output_dir.join(INDEX_FILE), index)?;
+            output_dir
+        };
+
+        Ok(ExportSummary {
+            path,
+            chapters: chapters.len(),
+            images: assets.count(),
+            warnings,
+        })
     }
 }
 
@@ -132,59 +526,306 @@ impl Default for EpubParser {
     }
 }
 
-impl DocumentParser for EpubParser {
-    fn parse_file(&self, path: &Path) -> Result<ParsedDocument, ParseError> {
-        let mut doc = EpubDoc::new(path)
-            .map_err(|e| ParseError::ParseError(format!("Failed to open EPUB: {e}")))?;
+/// Directory exported images are copied into, next to the chapters
+const ASSETS_DIR: &str = "assets";
 
-        let mut combined_markdown = String::new();
-        let num_chapters = doc.get_num_chapters();
+/// Table of contents written next to the exported chapters
+const INDEX_FILE: &str = "index.md";
 
-        for i in 0..num_chapters {
-            doc.set_current_chapter(i);
+/// How [`EpubParser::export_chapters`] writes a book
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Directory to write to, instead of one named after the book
+    pub output: Option<PathBuf>,
+    /// Write the whole book to one file instead of a file per chapter
+    pub single_file: bool,
+}
 
-            // Get chapter content
-            let Some((content, _mime)) = doc.get_current_str() else {
-                continue;
-            };
+/// What an export wrote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSummary {
+    /// The exported file, or the directory of chapter files
+    pub path: PathBuf,
+    pub chapters: usize,
+    pub images: usize,
+    /// Malformed chapters that were exported as far as they could be read,
+    /// or skipped
+    pub warnings: Vec<ParseWarning>,
+}
 
-            // Check for malformed XHTML - fail fast
-            if content.contains("<parsererror") {
-                let title = Self::get_chapter_title(&doc, i)
-                    .unwrap_or_else(|| format!("chapter {}", i + 1));
-                return Err(ParseError::ParseError(format!(
-                    "Failed to parse chapter {title}: malformed XHTML"
-                )));
-            }
+/// A chapter being exported
+struct ExportChapter {
+    /// Book path of the chapter's XHTML
+    path: String,
+    title: String,
+    /// Exported file, or anchor in a single-file export
+    target: String,
+    nodes: Vec<Node>,
+}
 
-            // Try to get chapter title from TOC
-            let chapter_title = Self::get_chapter_title(&doc, i);
+/// Images copied out of a book, by book path
+struct ExportAssets {
+    dir: PathBuf,
+    names: HashMap<String, String>,
+    /// First failure to write an image, reported after the chapter
+    error: Option<std::io::Error>,
+}
 
-            // Add chapter heading if we have a title
-            if let Some(title) = chapter_title {
-                if !combined_markdown.is_empty() {
-                    combined_markdown.push_str("\n\n");
-                }
-                let _ = write!(combined_markdown, "# {title}\n\n");
-            }
+impl ExportAssets {
+    fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            names: HashMap::new(),
+            error: None,
+        }
+    }
 
-            // Convert XHTML to markdown
-            let markdown = Self::xhtml_to_markdown(&content);
-            if markdown.trim().is_empty() {
-                continue;
+    /// Link to the exported copy of an image found in `chapter_path`,
+    /// copying it on first use. Images missing from the book keep `src`.
+    fn link<R: Read + Seek>(
+        &mut self,
+        doc: &mut EpubDoc<R>,
+        chapter_path: &str,
+        src: &str,
+    ) -> String {
+        if src.is_empty() || has_scheme(src) {
+            return src.to_string();
+        }
+        let resolved = xhtml::resolve_href(chapter_path, &percent_decode(src));
+        let book_path = resolved.split('#').next().unwrap_or_default();
+        if let Some(name) = self.names.get(book_path) {
+            return link_path(&format!("{ASSETS_DIR}/{name}"));
+        }
+
+        let id = doc
+            .resources
+            .iter()
+            .find(|(_, resource)| resource.path.to_string_lossy().replace('\\', "/") == book_path)
+            .map(|(id, _)| id.clone());
+        let Some((data, _mime)) = id.and_then(|id| doc.get_resource(&id)) else {
+            return src.to_string();
+        };
+
+        let file_name = book_path.rsplit('/').next().unwrap_or(book_path);
+        let (stem, extension) = file_name.rsplit_once('.').unwrap_or((file_name, ""));
+        let mut name = EpubParser::sanitize_filename(stem).replace(' ', "_");
+        if !extension.is_empty() {
+            name = format!("{name}.{}", EpubParser::sanitize_filename(extension));
+        }
+        // Images with the same name in different folders get a number
+        if self.names.values().any(|n| *n == name) {
+            name = format!("{}-{name}", self.names.len() + 1);
+        }
+
+        let written =
+            fs::create_dir_all(&self.dir).and_then(|()| fs::write(self.dir.join(&name), data));
+        if let Err(e) = written {
+            self.error.get_or_insert(e);
+            return src.to_string();
+        }
+        self.names.insert(book_path.to_string(), name.clone());
+        link_path(&format!("{ASSETS_DIR}/{name}"))
+    }
+
+    /// Report a failure to write an image
+    fn check(&mut self) -> Result<(), std::io::Error> {
+        self.error.take().map_or(Ok(()), Err)
+    }
+
+    fn count(&self) -> usize {
+        self.names.len()
+    }
+}
+
+/// Name of the anchor for element `id` in the exported chapter at `target`.
+/// In a single file it is prefixed with the chapter, so the same id in two
+/// chapters stays apart: `#chapter-02` and `note1` give `chapter-02-note1`.
+fn export_anchor(options: &ExportOptions, target: &str, id: &str) -> String {
+    if options.single_file {
+        format!("{}-{id}", target.trim_start_matches('#'))
+    } else {
+        id.to_string()
+    }
+}
+
+/// Exported file name as a markdown link destination
+fn link_path(path: &str) -> String {
+    path.replace(' ', "%20")
+}
+
+/// YAML front matter with the title, the book and chapter number for a
+/// chapter, and the book's authors and language
+fn export_front_matter(
+    title: &str,
+    chapter: Option<(&str, usize)>,
+    metadata: &DocumentMetadata,
+) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut out = format!("---\ntitle: {}\n", quote(title));
+    if let Some((book, number)) = chapter {
+        let _ = writeln!(out, "book: {}", quote(book));
+        let _ = writeln!(out, "chapter: {number}");
+    }
+    match metadata.authors.as_slice() {
+        [] => {}
+        [author] => {
+            let _ = writeln!(out, "author: {}", quote(author));
+        }
+        authors => {
+            out.push_str("author:\n");
+            for author in authors {
+                let _ = writeln!(out, "  - {}", quote(author));
             }
+        }
+    }
+    if let Some(language) = &metadata.language {
+        let _ = writeln!(out, "language: {}", quote(language));
+    }
+    out.push_str("---\n\n");
+    out
+}
+
+/// Markdown list of the book's contents, linking to the exported chapters.
+/// Without a table of contents, the chapters are listed in order.
+fn export_contents(toc: &[TocEntry], chapters: &[ExportChapter]) -> String {
+    let escape = |s: &str| s.replace('[', "\\[").replace(']', "\\]");
+    let mut out = String::new();
+    for entry in toc {
+        let file = entry.target.split('#').next().unwrap_or_default();
+        if let Some(chapter) = chapters.iter().find(|c| c.path == file) {
+            let indent = "  ".repeat(usize::from(entry.level.saturating_sub(1)));
+            let _ = writeln!(
+                out,
+                "{indent}- [{}]({})",
+                escape(&entry.label),
+                link_path(&chapter.target)
+            );
+        }
+    }
+    if out.is_empty() {
+        for chapter in chapters {
+            let _ = writeln!(
+                out,
+                "- [{}]({})",
+                escape(&chapter.title),
+                link_path(&chapter.target)
+            );
+        }
+    }
+    out
+}
+
+/// Error for a book `EpubDoc` couldn't open: reading failures stay I/O
+/// errors, anything else means the file isn't an EPUB it understands
+pub(crate) fn open_error(error: DocError) -> ParseError {
+    match error {
+        DocError::IOError(e) => ParseError::IoError(e),
+        e => ParseError::UnsupportedFormat {
+            location: Box::default(),
+            message: format!("not a readable EPUB ({e})"),
+        },
+    }
+}
+
+/// Encryption methods that only obfuscate embedded fonts, leaving the text
+/// readable
+const FONT_OBFUSCATION: &[&str] = &[
+    "http://www.idpf.org/2008/embedding",
+    "http://ns.adobe.com/pdf/enc#RC",
+];
+
+/// Check that the book's text isn't encrypted, before reading any of it.
+///
+/// # Errors
+///
+/// Returns [`ParseError::DrmProtected`] if `META-INF/encryption.xml` uses
+/// any encryption other than font obfuscation.
+pub(crate) fn check_encryption<R: Read + Seek>(doc: &mut EpubDoc<R>) -> Result<(), ParseError> {
+    let Some(xml) = doc.get_resource_str_by_path("META-INF/encryption.xml") else {
+        return Ok(());
+    };
+    let methods = xhtml_tree::encryption_methods(&xml);
+    let is_obfuscation = |algorithm: &Option<String>| {
+        algorithm
+            .as_deref()
+            .is_some_and(|a| FONT_OBFUSCATION.contains(&a))
+    };
+    if methods.iter().all(is_obfuscation) {
+        Ok(())
+    } else {
+        Err(ParseError::DrmProtected {
+            location: Box::default(),
+        })
+    }
+}
+
+/// Whether the book has any images, such as comic pages or scans
+pub(crate) fn has_images<R: Read + Seek>(doc: &EpubDoc<R>) -> bool {
+    doc.resources
+        .values()
+        .any(|resource| resource.mime.starts_with("image/"))
+}
+
+/// Whether `tokens` have anything to read besides markers for images
+/// without descriptions
+pub(crate) fn has_text(tokens: &[Token]) -> bool {
+    tokens
+        .iter()
+        .any(|t| t.block != BlockContext::Figure || t.word != IMAGE_MARKER)
+}
 
-            combined_markdown.push_str(&markdown);
+/// Error for a book without a word to read: a comic or scanned book when
+/// it has images
+pub(crate) fn no_text_error(has_images: bool) -> ParseError {
+    if has_images {
+        ParseError::UnsupportedFormat {
+            location: Box::default(),
+            message: "the book only has images, such as comic pages or scans, and no text to read"
+                .to_string(),
+        }
+    } else {
+        ParseError::EmptyDocument {
+            location: Box::default(),
         }
+    }
+}
+
+/// Line and column from a browser's parser error report, such as
+/// "error on line 3 at column 9"
+fn error_position(content: &str) -> (Option<usize>, Option<usize>) {
+    let report = content
+        .find("<parsererror")
+        .map_or("", |start| &content[start..]);
+    let number_after = |label: &str| {
+        let start = report.find(label)? + label.len();
+        let digits: String = report[start..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        digits.parse().ok()
+    };
+    (number_after("line "), number_after("column "))
+}
 
-        // Parse combined markdown through the markdown parser
-        self.md_parser.parse_str(&combined_markdown)
+impl DocumentParser for EpubParser {
+    fn parse_file(&self, path: &Path) -> Result<ParsedDocument, ParseError> {
+        let mut doc = EpubDoc::new(path).map_err(|e| open_error(e).in_file(path))?;
+        let mut parsed = self.parse_book(&mut doc).map_err(|e| e.in_file(path))?;
+        parsed.metadata.source_path = Some(path.to_path_buf());
+        Ok(parsed)
     }
 
     fn parse_str(&self, _content: &str) -> Result<ParsedDocument, ParseError> {
-        Err(ParseError::ParseError(
-            "EPUB parser does not support parsing from string".to_string(),
-        ))
+        Err(ParseError::UnsupportedFormat {
+            location: Box::default(),
+            message: "EPUB is a zip archive, parse it from bytes or a reader instead of a string"
+                .to_string(),
+        })
+    }
+
+    fn parse_reader<R: Read + Seek>(&self, reader: R) -> Result<ParsedDocument, ParseError> {
+        let mut doc = EpubDoc::from_reader(reader).map_err(open_error)?;
+        self.parse_book(&mut doc)
     }
 }
 
@@ -205,11 +846,143 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_nested_ncx_is_flattened_with_levels() {
+        let point = |label: &str, content: &str, children| NavPoint {
+            label: label.to_string(),
+            content: content.into(),
+            children,
+            play_order: None,
+        };
+        let toc = vec![point(
+            "Part  One",
+            "OEBPS/part1.xhtml",
+            vec![point(
+                "Chapter",
+                "OEBPS/./ch1.xhtml",
+                vec![point("Section", "OEBPS/ch1.xhtml#sec2", Vec::new())],
+            )],
+        )];
+        let mut entries = Vec::new();
+        EpubParser::flatten_nav_points(&toc, 1, &mut entries);
+
+        let flat: Vec<(&str, &str, u8)> = entries
+            .iter()
+            .map(|e| (e.label.as_str(), e.target.as_str(), e.level))
+            .collect();
+        assert_eq!(
+            flat,
+            vec![
+                ("Part One", "OEBPS/part1.xhtml", 1),
+                ("Chapter", "OEBPS/ch1.xhtml", 2),
+                ("Section", "OEBPS/ch1.xhtml#sec2", 3),
+            ]
+        );
+        assert_eq!(
+            EpubParser::chapter_title(&entries, "OEBPS/ch1.xhtml").as_deref(),
+            Some("Chapter")
+        );
+    }
+
+    #[test]
+    fn test_toc_sections_follow_anchors() {
+        let entry = |label: &str, target: &str, level| TocEntry {
+            label: label.to_string(),
+            target: target.to_string(),
+            level,
+        };
+        let toc = vec![
+            entry("One", "ch1.xhtml", 1),
+            entry("Deep", "ch1.xhtml#sec2", 2),
+            entry("Lost", "ch1.xhtml#missing", 2),
+            entry("Nowhere", "cover.xhtml", 1),
+            entry("Two", "ch2.xhtml", 1),
+        ];
+        let anchors = HashMap::from([
+            ("ch1.xhtml".to_string(), 0),
+            ("ch1.xhtml#sec2".to_string(), 10),
+            ("ch2.xhtml".to_string(), 25),
+        ]);
+        let sections = EpubParser::toc_sections(&toc, &anchors, 40);
+
+        let outline: Vec<(&str, u8, usize, usize)> = sections
+            .iter()
+            .map(|s| (s.title.as_str(), s.level, s.token_start, s.token_end))
+            .collect();
+        assert_eq!(
+            outline,
+            vec![("One", 1, 0, 10), ("Deep", 2, 10, 25), ("Two", 1, 25, 40),]
+        );
+    }
+
+    #[test]
+    fn test_chapter_start_reads_title_once() {
+        let toc = vec![TocEntry {
+            label: "The End".to_string(),
+            target: "ch2.xhtml#top".to_string(),
+            level: 1,
+        }];
+        let mut labels = NoteLabels::default();
+
+        let untitled = xhtml_tree::chapter_nodes("<p>Done.</p>", "ch2.xhtml", &mut labels);
+        let start = EpubParser::chapter_start(&toc, "ch2.xhtml", &untitled);
+        assert_eq!(start.len(), 2);
+        assert_eq!(start[1].collect_text(), "The End");
+
+        let titled = xhtml_tree::chapter_nodes(
+            "<h1 id=\"top\">  the   END </h1><p>Done.</p>",
+            "ch2.xhtml",
+            &mut labels,
+        );
+        assert_eq!(
+            EpubParser::chapter_start(&toc, "ch2.xhtml", &titled).len(),
+            1
+        );
+    }
+
+    /// Export conversion keeping image sources and links as they are
+    fn to_markdown(xhtml: &str) -> String {
+        let nodes = xhtml_tree::chapter_nodes(xhtml, "ch1.xhtml", &mut NoteLabels::default());
+        markdown_writer::write(&nodes)
+    }
+
     #[test]
     fn test_xhtml_to_markdown() {
         let xhtml = "<p>Hello <strong>world</strong>!</p>";
-        let md = EpubParser::xhtml_to_markdown(xhtml);
+        let md = to_markdown(xhtml);
         assert!(md.contains("Hello"));
         assert!(md.contains("world"));
     }
+
+    #[test]
+    fn test_figures_survive_conversion() {
+        let xhtml = "<p>See below.</p><figure><img src='a.png' alt='Chart'/>\
+                     <figcaption>Sales by year</figcaption></figure><p>After.</p>";
+        let md = to_markdown(xhtml);
+        let doc = MarkdownParser::new().parse_str(&md).unwrap();
+        let figure: Vec<&str> = doc
+            .tokens
+            .iter()
+            .filter(|t| t.block == crate::types::BlockContext::Figure)
+            .map(|t| t.word.as_str())
+            .collect();
+        assert_eq!(figure, vec!["Chart", "Sales", "by", "year"]);
+    }
+
+    #[test]
+    fn test_notes_become_footnotes() {
+        let xhtml = "<p>A claim<a epub:type=\"noteref\" href=\"#fn1\">1</a> stands.</p>\
+                     <aside epub:type=\"footnote\" id=\"fn1\"><p>Cited source.</p></aside>\
+                     <p>Next paragraph.</p>";
+        let md = to_markdown(xhtml);
+        let doc = MarkdownParser::new().parse_str(&md).unwrap();
+
+        let words: Vec<&str> = doc.tokens.iter().map(|t| t.word.as_str()).collect();
+        assert_eq!(
+            words,
+            vec!["A", "claim", "[1]", "stands.", "Next", "paragraph."]
+        );
+        assert_eq!(doc.footnotes.len(), 1);
+        assert_eq!(doc.footnotes[0].text(), "Cited source.");
+    }
 }
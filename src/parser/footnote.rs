@@ -0,0 +1,143 @@
+//! Footnote syntax for markdown-it
+//!
+//! `text[^label]` references a note defined elsewhere as `[^label]: Note text`.
+//! Definitions are collected during block parsing so only references with a
+//! matching definition are recognised.
+
+use std::collections::HashSet;
+
+use markdown_it::parser::block::{BlockRule, BlockState};
+use markdown_it::parser::extset::RootExt;
+use markdown_it::parser::inline::{InlineRoot, InlineRule, InlineState};
+use markdown_it::{MarkdownIt, Node, NodeValue};
+
+/// Labels of all footnote definitions in the document
+#[derive(Debug, Default)]
+struct FootnoteLabels(HashSet<String>);
+
+impl RootExt for FootnoteLabels {}
+
+/// Inline `[^label]` reference
+#[derive(Debug)]
+pub struct FootnoteReference {
+    pub label: String,
+}
+
+impl NodeValue for FootnoteReference {}
+
+/// `[^label]: ...` definition; children are the note content
+#[derive(Debug)]
+pub struct FootnoteDefinition {
+    pub label: String,
+}
+
+impl NodeValue for FootnoteDefinition {}
+
+/// Add footnote references and definitions to a parser
+pub fn add(md: &mut MarkdownIt) {
+    md.block
+        .add_rule::<FootnoteDefinitionScanner>()
+        .before_all();
+    md.inline
+        .add_rule::<FootnoteReferenceScanner>()
+        .before_all();
+}
+
+/// Parse `[^label]` at the start of `s`, returning the label and length
+fn parse_label(s: &str) -> Option<(&str, usize)> {
+    let rest = s.strip_prefix("[^")?;
+    let end = rest.find(']')?;
+    let label = &rest[..end];
+    if label.is_empty() || label.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((label, end + 3))
+}
+
+struct FootnoteDefinitionScanner;
+
+impl BlockRule for FootnoteDefinitionScanner {
+    fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+        if state.line_indent(state.line) >= state.md.max_indent {
+            return None;
+        }
+
+        let line = state.get_line(state.line);
+        let (label, len) = parse_label(line)?;
+        let first = line[len..].strip_prefix(':')?.trim().to_string();
+        let label = label.to_string();
+
+        // Continuation lines run until a blank line, or further paragraphs
+        // when they are indented under the definition
+        let start_line = state.line;
+        let mut next_line = start_line + 1;
+        let mut content = first;
+        while next_line < state.line_max {
+            if state.is_empty(next_line) {
+                let resume = state.skip_empty_lines(next_line);
+                if resume < state.line_max && state.line_indent(resume) >= state.md.max_indent {
+                    content.push_str("\n\n");
+                    next_line = resume;
+                    continue;
+                }
+                break;
+            }
+            if state.line_indent(next_line) < state.md.max_indent {
+                let old_line = state.line;
+                state.line = next_line;
+                let interrupted = state.test_rules_at_line();
+                state.line = old_line;
+                if interrupted {
+                    break;
+                }
+            }
+            content.push('\n');
+            content.push_str(state.get_line(next_line).trim());
+            next_line += 1;
+        }
+
+        state
+            .root_ext
+            .get_or_insert_default::<FootnoteLabels>()
+            .0
+            .insert(label.clone());
+
+        let offset = state.line_offsets[start_line].first_nonspace;
+        let mut node = Node::new(FootnoteDefinition { label });
+        node.children
+            .push(Node::new(InlineRoot::new(content, vec![(0, offset)])));
+        Some((node, next_line - start_line))
+    }
+}
+
+struct FootnoteReferenceScanner;
+
+impl InlineRule for FootnoteReferenceScanner {
+    const MARKER: char = '[';
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let (label, len) = parse_label(&state.src[state.pos..state.pos_max])?;
+        let labels = state.root_ext.get::<FootnoteLabels>()?;
+        if !labels.0.contains(label) {
+            return None;
+        }
+        let node = Node::new(FootnoteReference {
+            label: label.to_string(),
+        });
+        Some((node, len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(parse_label("[^1]: note"), Some(("1", 4)));
+        assert_eq!(parse_label("[^long-name] text"), Some(("long-name", 12)));
+        assert_eq!(parse_label("[^]"), None);
+        assert_eq!(parse_label("[^a b]"), None);
+        assert_eq!(parse_label("[link]"), None);
+    }
+}
@@ -0,0 +1,235 @@
+//! YAML (`---`) and TOML (`+++`) front matter at the top of markdown files
+//!
+//! Only the flat keys we display are read, so a small line-based reader is
+//! enough: scalar `key: value` / `key = "value"` pairs, inline arrays, and
+//! YAML block lists and block scalars.
+
+use std::ops::Range;
+
+use crate::types::DocumentMetadata;
+
+/// Split front matter from the start of a document.
+///
+/// Returns the metadata (if a front matter block was found) and the
+/// remaining body.
+#[must_use]
+pub fn split_front_matter(content: &str) -> (Option<DocumentMetadata>, &str) {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    // Unterminated: a lone `---` is a thematic break, not front matter
+    let Some((separator, fields, end)) = block(content) else {
+        return (None, content);
+    };
+    let body = &content[fields];
+    // Prose between two thematic breaks is not front matter
+    if !is_field_block(body, separator) {
+        return (None, content);
+    }
+    (Some(parse_fields(body, separator)), &content[end..])
+}
+
+/// Whether `content` opens with a `---` or `+++` fence, which may start
+/// front matter once the closing fence arrives
+pub(crate) fn opens_front_matter(content: &str) -> bool {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    fence(content).is_some()
+}
+
+/// Offset just past the fence closing the block that opens `content`, once
+/// it has arrived
+pub(crate) fn front_matter_end(content: &str) -> Option<usize> {
+    let bom = if content.starts_with('\u{feff}') {
+        '\u{feff}'.len_utf8()
+    } else {
+        0
+    };
+    block(&content[bom..]).map(|(_, _, end)| bom + end)
+}
+
+/// Closing fences and field separator for the fence opening `content`
+fn fence(content: &str) -> Option<(&'static [&'static str], char)> {
+    match content.lines().next().map(str::trim_end) {
+        Some("---") => Some((&["---", "..."], ':')),
+        Some("+++") => Some((&["+++"], '=')),
+        _ => None,
+    }
+}
+
+/// Field separator, the range of the lines between the fences, and the
+/// offset just past the closing fence of the block opening `content`
+fn block(content: &str) -> Option<(char, Range<usize>, usize)> {
+    let (closers, separator) = fence(content)?;
+    let start = content.find('\n').map_or(content.len(), |i| i + 1);
+    let mut offset = start;
+    for line in content[start..].split_inclusive('\n') {
+        let end = offset;
+        offset += line.len();
+        if closers.contains(&line.trim_end()) {
+            return Some((separator, start..end, offset));
+        }
+    }
+    None
+}
+
+/// Whether every line of `body` reads as front matter: `key: value` or
+/// `key = value` fields, YAML list items, TOML table headers, comments and
+/// blank lines. Indented lines after a field continue its value, as in YAML
+/// block scalars. Comments alone don't count, as `# text` may be a heading.
+fn is_field_block(body: &str, separator: char) -> bool {
+    let mut has_content = false;
+    let mut has_field = false;
+    for raw in body.lines() {
+        let line = raw.trim();
+        if line.is_empty() {
+            continue;
+        }
+        has_content = true;
+        if has_field && raw.starts_with(char::is_whitespace) {
+            continue;
+        }
+        if line.starts_with('#') || line == "-" || line.starts_with("- ") {
+            continue;
+        }
+        if separator == '=' && line.starts_with('[') && line.ends_with(']') {
+            continue;
+        }
+        let Some((key, _)) = line.split_once(separator) else {
+            return false;
+        };
+        let key = unquote(key);
+        let is_key = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if !is_key {
+            return false;
+        }
+        has_field = true;
+    }
+    has_field || !has_content
+}
+
+/// Read book metadata from a TOML config such as mdBook's `book.toml`
+pub(crate) fn parse_toml(content: &str) -> DocumentMetadata {
+    parse_fields(content, '=')
+}
+
+/// Read the fields we care about from the front matter body
+fn parse_fields(body: &str, separator: char) -> DocumentMetadata {
+    let mut metadata = DocumentMetadata::default();
+    let mut list_key: Option<String> = None;
+    // YAML block scalar being read: its key, whether it is folded (`>`)
+    // rather than literal (`|`), and its lines so far
+    let mut scalar: Option<(String, bool, Vec<&str>)> = None;
+
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if scalar.is_some() && (trimmed.is_empty() || line.starts_with(char::is_whitespace)) {
+            if let Some((_, _, lines)) = &mut scalar {
+                lines.push(trimmed);
+            }
+            continue;
+        }
+        if let Some((key, folded, lines)) = scalar.take() {
+            add_scalar(&mut metadata, &key, folded, &lines);
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        // YAML block list item under the previous key
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            if let Some(key) = &list_key {
+                add_value(&mut metadata, key, unquote(item));
+            }
+            continue;
+        }
+
+        let Some((key, value)) = trimmed.split_once(separator) else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+
+        if value.is_empty() {
+            list_key = Some(key);
+            continue;
+        }
+        list_key = None;
+
+        // `>` and `|`, with optional chomping and indentation indicators
+        if separator == ':' && value.starts_with(['>', '|']) {
+            let is_indicator = value[1..]
+                .chars()
+                .all(|c| matches!(c, '+' | '-') || c.is_ascii_digit());
+            if is_indicator {
+                scalar = Some((key, value.starts_with('>'), Vec::new()));
+                continue;
+            }
+        }
+
+        if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            for item in items.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+                add_value(&mut metadata, &key, unquote(item));
+            }
+        } else {
+            add_value(&mut metadata, &key, unquote(value));
+        }
+    }
+    if let Some((key, folded, lines)) = scalar {
+        add_scalar(&mut metadata, &key, folded, &lines);
+    }
+
+    metadata
+}
+
+/// Store a YAML block scalar, with folded lines joined by spaces and
+/// literal ones kept on their own lines
+fn add_scalar(metadata: &mut DocumentMetadata, key: &str, folded: bool, lines: &[&str]) {
+    let value = lines.join(if folded { " " } else { "\n" });
+    add_value(metadata, key, value.trim());
+}
+
+/// Store a value for a known key; tags accumulate, scalars keep the first value
+fn add_value(metadata: &mut DocumentMetadata, key: &str, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    match key {
+        "title" if metadata.title.is_none() => metadata.title = Some(value.to_string()),
+        "author" | "authors" if !metadata.authors.iter().any(|a| a == value) => {
+            metadata.authors.push(value.to_string());
+        }
+        "lang" | "language" if metadata.language.is_none() => {
+            metadata.language = Some(value.to_string());
+        }
+        "isbn" | "doi" | "uuid" | "identifier"
+            if !metadata.identifiers.iter().any(|i| i == value) =>
+        {
+            metadata.identifiers.push(value.to_string());
+        }
+        "tags" | "tag" => {
+            // Tags may be space separated, and Obsidian allows a leading '#'
+            for tag in value.split_whitespace() {
+                let tag = tag.trim_start_matches('#');
+                if !tag.is_empty() && !metadata.tags.iter().any(|t| t == tag) {
+                    metadata.tags.push(tag.to_string());
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Strip matching quotes around a value
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
@@ -1,8 +1,13 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
 
-use markdown_it::parser::inline::Text;
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::inline::builtin::InlineParserRule;
+use markdown_it::parser::inline::{InlineRule, InlineState, Text, TextSpecial};
 use markdown_it::plugins::cmark::block::{
     blockquote::Blockquote,
+    code::CodeBlock as IndentedCode,
     fence::CodeFence,
     heading::ATXHeading,
     list::{BulletList, ListItem, OrderedList},
@@ -14,26 +19,74 @@ use markdown_it::plugins::cmark::inline::{
     image::Image,
     link::Link,
 };
+use markdown_it::plugins::extra::linkify::Linkified;
+use markdown_it::plugins::extra::strikethrough::Strikethrough;
 use markdown_it::plugins::extra::tables::{Table, TableCell, TableRow};
+use markdown_it::plugins::html::html_inline::HtmlInline;
 use markdown_it::{plugins::cmark, plugins::extra, MarkdownIt, Node};
 
+use crate::links::slugify;
+use crate::parser::encoding;
+use crate::parser::footnote::{self, FootnoteDefinition, FootnoteReference};
+use crate::parser::frontmatter::split_front_matter;
+use crate::parser::obsidian::{self, Callout, CalloutTitle, WikiEmbed, WikiLink};
 use crate::parser::traits::{DocumentParser, ParseError, ParsedDocument};
 use crate::timing::generate_timing_hint;
-use crate::types::{BlockContext, BlockHint, Section, Token, TokenStyle};
+use crate::types::{
+    BlockContext, BlockHint, CodeBlock, DocumentMetadata, Encoding, Footnote, Section, Token,
+    TokenStyle,
+};
+
+/// Options controlling how Markdown is turned into tokens.
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownOptions {
+    /// Read code blocks line by line instead of emitting a single placeholder token
+    pub code_lines: bool,
+    /// Obsidian vault used to resolve `![[embeds]]`; defaults to the file's directory
+    pub vault: Option<PathBuf>,
+    /// Read only the title of folded callouts (`> [!note]-`)
+    pub skip_folded_callouts: bool,
+    /// Encoding of input files, detected when unset
+    pub encoding: Option<Encoding>,
+}
 
 /// Markdown parser that extracts tokens for RSVP reading.
 pub struct MarkdownParser {
     md: MarkdownIt,
+    options: MarkdownOptions,
 }
 
 impl MarkdownParser {
-    /// Create a new markdown parser with `CommonMark` and GFM table support.
+    /// Create a new markdown parser with `CommonMark` and GFM extensions.
     #[must_use]
     pub fn new() -> Self {
+        Self::with_options(MarkdownOptions::default())
+    }
+
+    /// Create a markdown parser with custom tokenization options.
+    #[must_use]
+    pub fn with_options(options: MarkdownOptions) -> Self {
         let mut md = MarkdownIt::new();
         cmark::add(&mut md);
         extra::tables::add(&mut md);
-        Self { md }
+        extra::strikethrough::add(&mut md);
+        extra::linkify::add(&mut md);
+        md.inline.add_rule::<AnchorTagScanner>();
+        footnote::add(&mut md);
+        md.add_rule::<TextJoin>().after::<InlineParserRule>();
+        obsidian::add(&mut md);
+        Self { md, options }
+    }
+
+    /// Text of the file at `path`, decoded from the encoding in the options
+    /// or the one detected, which is returned with it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseError::IoError`] if the file cannot be read.
+    /// Returns [`ParseError::Encoding`] if it isn't valid in the encoding.
+    pub(crate) fn read_file(&self, path: &Path) -> Result<(String, Encoding), ParseError> {
+        encoding::decode(&std::fs::read(path)?, self.options.encoding).map_err(|e| e.in_file(path))
     }
 }
 
@@ -54,7 +107,7 @@ struct ParserContext {
     quote_depth: usize,
     /// List depth tracking
     list_depth: usize,
-    /// Whether we're inside a skippable element (code block, image)
+    /// Whether we're inside a skippable element (image alt text already emitted)
     skip_depth: usize,
     /// Flag set when entering a new block (cleared after first token)
     new_block_entered: bool,
@@ -76,10 +129,24 @@ struct ParserContext {
     in_inline_code: bool,
     /// Stack of parent block hints for hierarchy tracking
     parent_stack: Vec<BlockHint>,
+    /// Emit one token per code line instead of a placeholder
+    code_lines: bool,
+    /// Code blocks collected so far
+    code_blocks: Vec<CodeBlock>,
+    /// Footnotes in order of first reference or definition
+    footnotes: Vec<Footnote>,
+    /// Set on entering a task list item until its `[ ]`/`[x]` marker is stripped
+    strip_task_marker: bool,
+    /// Word-splitting rules for the document language
+    script: Script,
+    /// Read only the title of folded callouts
+    skip_folded_callouts: bool,
+    /// Link targets found so far, by token position
+    anchors: HashMap<String, usize>,
 }
 
 impl ParserContext {
-    fn new() -> Self {
+    fn new(options: &MarkdownOptions) -> Self {
         Self {
             style_stack: vec![TokenStyle::Normal],
             block_stack: vec![BlockContext::Paragraph],
@@ -96,6 +163,13 @@ impl ParserContext {
             in_callout: false,
             in_inline_code: false,
             parent_stack: Vec::new(),
+            code_lines: options.code_lines,
+            code_blocks: Vec::new(),
+            footnotes: Vec::new(),
+            strip_task_marker: false,
+            script: Script::Default,
+            skip_folded_callouts: options.skip_folded_callouts,
+            anchors: HashMap::new(),
         }
     }
 
@@ -158,14 +232,177 @@ impl ParserContext {
     const fn should_skip(&self) -> bool {
         self.skip_depth > 0
     }
+
+    /// Anchor a heading at `position` by its slug, numbering repeated
+    /// headings `slug-1`, `slug-2`, ... as GitHub does
+    fn add_heading_anchor(&mut self, title: &str, position: usize) {
+        let slug = slugify(title);
+        let mut anchor = slug.clone();
+        let mut n = 0;
+        while self.anchors.contains_key(&anchor) {
+            n += 1;
+            anchor = format!("{slug}-{n}");
+        }
+        self.anchors.insert(anchor, position);
+    }
+
+    /// Index of the footnote with this label, adding it if not seen yet
+    fn footnote_index(&mut self, label: &str) -> usize {
+        self.footnotes
+            .iter()
+            .position(|f| f.label == label)
+            .unwrap_or_else(|| {
+                self.footnotes.push(Footnote {
+                    label: label.to_string(),
+                    tokens: Vec::new(),
+                });
+                self.footnotes.len() - 1
+            })
+    }
+}
+
+/// Reads `<a id="...">` and `<a name="...">` link targets, with an
+/// immediately following `</a>`. Other inline HTML stays plain text.
+struct AnchorTagScanner;
+
+impl InlineRule for AnchorTagScanner {
+    const MARKER: char = '<';
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let src = &state.src[state.pos..state.pos_max];
+        let rest = src.strip_prefix("<a")?;
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let tag = &src[..=src.find('>')?];
+        if tag[1..].contains('<') {
+            return None;
+        }
+        attr_value(tag, "id").or_else(|| attr_value(tag, "name"))?;
+        let len = tag.len()
+            + if src[tag.len()..].starts_with("</a>") {
+                4
+            } else {
+                0
+            };
+        let node = Node::new(HtmlInline {
+            content: tag.to_string(),
+        });
+        Some((node, len))
+    }
+}
+
+/// Merges escapes, entities and autolink text into neighbouring text nodes so
+/// words are split the same way as plain text.
+struct TextJoin;
+
+impl CoreRule for TextJoin {
+    fn run(root: &mut Node, _: &MarkdownIt) {
+        root.walk_mut(|node, _| {
+            if !node.children.iter().any(|c| c.is::<TextSpecial>()) {
+                return;
+            }
+            let mut joined: Vec<Node> = Vec::with_capacity(node.children.len());
+            for child in std::mem::take(&mut node.children) {
+                let content = if let Some(special) = child.cast::<TextSpecial>() {
+                    special.content.clone()
+                } else if let Some(text) = child.cast::<Text>() {
+                    text.content.clone()
+                } else {
+                    joined.push(child);
+                    continue;
+                };
+                match joined.last_mut().and_then(|n| n.cast_mut::<Text>()) {
+                    Some(text) => text.content.push_str(&content),
+                    None => joined.push(Node::new(Text { content })),
+                }
+            }
+            node.children = joined;
+        });
+    }
+}
+
+/// Value of a quoted attribute within a single HTML tag
+fn attr_value<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut offset = 0;
+    while let Some(pos) = tag[offset..].find(name) {
+        let start = offset + pos;
+        let before = tag[..start].chars().next_back();
+        let after = &tag[start + name.len()..];
+        if before.is_some_and(char::is_whitespace) {
+            if let Some(value) = after.trim_start().strip_prefix('=') {
+                let value = value.trim_start();
+                let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+                let value = &value[1..];
+                return value.find(quote).map(|end| &value[..end]);
+            }
+        }
+        offset = start + name.len();
+    }
+    None
+}
+
+/// Detect a task list checkbox at the start of a list item's text.
+/// Returns whether it is checked.
+fn detect_task_marker(text: &str) -> Option<bool> {
+    let rest = text.strip_prefix('[')?;
+    let (mark, rest) = rest.split_at_checked(1)?;
+    let rest = rest.strip_prefix(']')?;
+    if !(rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+        return None;
+    }
+    match mark {
+        " " => Some(false),
+        "x" | "X" => Some(true),
+        _ => None,
+    }
+}
+
+/// First text of a list item (tight items hold their text directly,
+/// loose items wrap it in a paragraph)
+fn list_item_text(node: &Node) -> Option<&str> {
+    let mut first = node.children.first()?;
+    if first.is::<Paragraph>() {
+        first = first.children.first()?;
+    }
+    first.cast::<Text>().map(|t| t.content.as_str())
+}
+
+/// Maximum ideographs per token for scripts written without spaces
+const CJK_CHUNK: usize = 4;
+
+/// Word-splitting rules that depend on the document language
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Script {
+    #[default]
+    Default,
+    /// French: punctuation is set off by a (narrow) space, e.g. `mot !`
+    French,
+    /// Chinese and Japanese: no spaces between words
+    Cjk,
+}
+
+impl Script {
+    /// Pick rules from a BCP 47 language tag
+    fn from_language(language: Option<&str>) -> Self {
+        let primary = language
+            .and_then(|l| l.split(['-', '_']).next())
+            .map(str::to_lowercase);
+        match primary.as_deref() {
+            Some("fr") => Self::French,
+            Some("zh" | "ja") => Self::Cjk,
+            _ => Self::Default,
+        }
+    }
 }
 
 /// Split text into words, respecting Unicode boundaries.
 /// Em-dashes (—) and en-dashes (–) are treated as word separators.
 /// Hyphenated words are split when portions are more than 3 characters long,
 /// keeping the hyphen on the tail of the preceding portion.
-fn split_into_words(text: &str) -> Vec<String> {
-    text.split_whitespace()
+fn split_into_words(text: &str, script: Script) -> Vec<String> {
+    let words = text
+        .split_whitespace()
         .flat_map(|part| {
             // Split on em-dash (—) and en-dash (–) as word separators
             part.split(['—', '–'])
@@ -178,8 +415,91 @@ fn split_into_words(text: &str) -> Vec<String> {
                 vec![part.to_string()]
             }
         })
-        .filter(|w| !w.is_empty())
-        .collect()
+        .filter(|w| !w.is_empty());
+
+    match script {
+        Script::Default => words.collect(),
+        Script::French => join_french_punctuation(words),
+        Script::Cjk => words.flat_map(|w| split_cjk(&w)).collect(),
+    }
+}
+
+/// Attach punctuation that French sets off with spaces to its word, so
+/// "Quoi ?" and "« Oui »" don't flash bare punctuation marks.
+fn join_french_punctuation(words: impl Iterator<Item = String>) -> Vec<String> {
+    let is_closing = |w: &str| w.chars().all(|c| "!?:;»%".contains(c));
+    let mut result: Vec<String> = Vec::new();
+    let mut pending_open: Option<String> = None;
+
+    for word in words {
+        if word == "«" {
+            pending_open = Some(word);
+            continue;
+        }
+        let word = match pending_open.take() {
+            Some(open) => format!("{open}\u{a0}{word}"),
+            None => word,
+        };
+        match result.last_mut() {
+            Some(prev) if is_closing(&word) => {
+                prev.push('\u{a0}');
+                prev.push_str(&word);
+            }
+            _ => result.push(word),
+        }
+    }
+    result.extend(pending_open);
+    result
+}
+
+/// Ideographs and kana, which are written without spaces between words
+const fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'     // Hiragana, Katakana
+        | '\u{3400}'..='\u{4dbf}'   // CJK Extension A
+        | '\u{4e00}'..='\u{9fff}'   // CJK Unified Ideographs
+        | '\u{f900}'..='\u{faff}') // CJK Compatibility Ideographs
+}
+
+/// Full-width punctuation that ends a chunk
+const fn is_cjk_punctuation(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{303f}' | '\u{ff01}'..='\u{ff0f}' | '\u{ff1a}'..='\u{ff1f}')
+}
+
+/// Break a run of unspaced CJK text into short chunks, keeping punctuation
+/// on the preceding chunk and Latin runs whole.
+fn split_cjk(word: &str) -> Vec<String> {
+    if !word.chars().any(is_cjk) {
+        return vec![word.to_string()];
+    }
+
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    let mut ideographs = 0;
+    let mut in_cjk = false;
+
+    for c in word.chars() {
+        if is_cjk_punctuation(c) {
+            chunk.push(c);
+            chunks.push(std::mem::take(&mut chunk));
+            ideographs = 0;
+            continue;
+        }
+        let cjk = is_cjk(c);
+        if !chunk.is_empty() && (cjk != in_cjk || (cjk && ideographs == CJK_CHUNK)) {
+            chunks.push(std::mem::take(&mut chunk));
+            ideographs = 0;
+        }
+        in_cjk = cjk;
+        if cjk {
+            ideographs += 1;
+        }
+        chunk.push(c);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
 }
 
 /// Split hyphenated words when portions are > 3 characters.
@@ -222,33 +542,94 @@ fn split_hyphenated_word(word: &str) -> Vec<String> {
     result
 }
 
-/// Detect callout type from text like "[!folder]" or "[!note]"
-/// Returns Some(callout_type) if found, None otherwise
-fn detect_callout_type(text: &str) -> Option<String> {
-    let trimmed = text.trim();
-    if trimmed.starts_with("[!") {
-        if let Some(end_pos) = trimmed.find(']') {
-            let callout_type = trimmed[2..end_pos].to_lowercase();
-            return Some(callout_type);
-        }
-    }
-    None
-}
-
 impl DocumentParser for MarkdownParser {
     fn parse_file(&self, path: &Path) -> Result<ParsedDocument, ParseError> {
-        let content = std::fs::read_to_string(path)?;
-        self.parse_str(&content)
+        let (content, encoding) = self.read_file(path)?;
+        let mut doc = self
+            .parse_document(&content, None, Some(path))
+            .map_err(|e| e.in_file(path))?;
+        doc.metadata.source_path = Some(path.to_path_buf());
+        doc.metadata.encoding = Some(encoding);
+        Ok(doc)
     }
 
     fn parse_str(&self, content: &str) -> Result<ParsedDocument, ParseError> {
-        let ast = self.md.parse(content);
+        self.parse_with_language(content, None)
+    }
+
+    fn parse_reader<R: Read + Seek>(&self, mut reader: R) -> Result<ParsedDocument, ParseError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let (content, encoding) = encoding::decode(&bytes, self.options.encoding)?;
+        let mut doc = self.parse_str(&content)?;
+        doc.metadata.encoding = Some(encoding);
+        Ok(doc)
+    }
+}
+
+impl MarkdownParser {
+    /// Parse markdown, tokenizing for `language` unless front matter names one.
+    pub(crate) fn parse_with_language(
+        &self,
+        content: &str,
+        language: Option<&str>,
+    ) -> Result<ParsedDocument, ParseError> {
+        self.parse_document(content, language, None)
+    }
+
+    /// Parse markdown read from `path`, if any, which also locates the vault
+    /// for embeds when no vault is configured.
+    pub(crate) fn parse_document(
+        &self,
+        content: &str,
+        language: Option<&str>,
+        path: Option<&Path>,
+    ) -> Result<ParsedDocument, ParseError> {
+        let (metadata, body) = split_front_matter(content);
+        let mut metadata = metadata.unwrap_or_default();
+        if metadata.language.is_none() {
+            metadata.language = language.map(str::to_string);
+        }
+        self.parse_body(body, metadata, path)
+    }
+
+    /// Parse Markdown without front matter, filling in `metadata.word_count`
+    pub(crate) fn parse_body(
+        &self,
+        body: &str,
+        metadata: DocumentMetadata,
+        path: Option<&Path>,
+    ) -> Result<ParsedDocument, ParseError> {
+        let mut ast = self.md.parse(body);
+
+        let vault = self.options.vault.as_deref().or_else(|| {
+            let parent = path?.parent()?;
+            Some(if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            })
+        });
+        if let Some(vault) = vault {
+            let mut stack: Vec<PathBuf> = path
+                .map(|p| p.canonicalize().unwrap_or_else(|_| p.to_path_buf()))
+                .into_iter()
+                .collect();
+            obsidian::expand_embeds(&self.md, &mut ast, vault, self.options.encoding, &mut stack);
+        }
+
+        Ok(self.parse_tree(&ast, metadata))
+    }
 
+    /// Tokenize an already built document tree, filling in
+    /// `metadata.word_count`
+    pub(crate) fn parse_tree(&self, ast: &Node, mut metadata: DocumentMetadata) -> ParsedDocument {
         let mut tokens = Vec::new();
         let mut sections = Vec::new();
-        let mut ctx = ParserContext::new();
+        let mut ctx = ParserContext::new(&self.options);
+        ctx.script = Script::from_language(metadata.language.as_deref());
 
-        walk_ast(&ast, &mut ctx, &mut tokens, &mut sections);
+        walk_ast(ast, &mut ctx, &mut tokens, &mut sections);
 
         // Update section token_end values
         for i in 0..sections.len() {
@@ -259,7 +640,17 @@ impl DocumentParser for MarkdownParser {
             }
         }
 
-        Ok(ParsedDocument { tokens, sections })
+        metadata.word_count = tokens.len();
+        ParsedDocument {
+            tokens,
+            sections,
+            code_blocks: ctx.code_blocks,
+            footnotes: ctx.footnotes,
+            metadata,
+            anchors: ctx.anchors,
+            files: Vec::new(),
+            warnings: Vec::new(),
+        }
     }
 }
 
@@ -314,8 +705,140 @@ fn enter_node(
     let mut restore_list_depth = false;
     let mut restore_quote_depth = false;
 
-    // Skip code blocks and images entirely
-    if node.is::<CodeFence>() || node.is::<Image>() {
+    // Code blocks are kept out of the word stream behind a placeholder
+    let code = if let Some(fence) = node.cast::<CodeFence>() {
+        let language = fence.info.split_whitespace().next().map(str::to_string);
+        Some((language, fence.content.as_str()))
+    } else {
+        node.cast::<IndentedCode>()
+            .map(|code| (None, code.content.as_str()))
+    };
+    if let Some((language, content)) = code {
+        if !ctx.should_skip() {
+            push_code_block(ctx, tokens, language, content);
+        }
+        return (
+            restore_style,
+            restore_block,
+            restore_skip,
+            restore_list_depth,
+            restore_quote_depth,
+        );
+    }
+
+    // Footnote bodies are collected separately so they aren't read inline
+    if let Some(definition) = node.cast::<FootnoteDefinition>() {
+        if !ctx.should_skip() {
+            let mut note_tokens = Vec::new();
+            let mut note_sections = Vec::new();
+            for child in &node.children {
+                walk_ast(child, ctx, &mut note_tokens, &mut note_sections);
+            }
+            let index = ctx.footnote_index(&definition.label);
+            ctx.footnotes[index].tokens = note_tokens;
+        }
+        ctx.skip_depth += 1;
+        restore_skip = true;
+        return (
+            restore_style,
+            restore_block,
+            restore_skip,
+            restore_list_depth,
+            restore_quote_depth,
+        );
+    }
+    if let Some(reference) = node.cast::<FootnoteReference>() {
+        if !ctx.should_skip() {
+            let index = ctx.footnote_index(&reference.label);
+            let word = format!("[{}]", reference.label);
+            let timing_hint = generate_timing_hint(
+                &word,
+                false,
+                ctx.new_block_entered || tokens.is_empty(),
+                ctx.is_last_table_cell,
+                ctx.is_cell_start,
+                ctx.current_table_column,
+            );
+            tokens.push(Token {
+                word,
+                style: TokenStyle::FootnoteRef(index),
+                block: ctx.current_block(),
+                parent_context: ctx.current_parent(),
+                timing_hint,
+            });
+            ctx.new_block_entered = false;
+            ctx.is_cell_start = false;
+        }
+        return (
+            restore_style,
+            restore_block,
+            restore_skip,
+            restore_list_depth,
+            restore_quote_depth,
+        );
+    }
+
+    if node.is::<CalloutTitle>() {
+        if !ctx.should_skip() {
+            let kind = match ctx.current_block() {
+                BlockContext::Callout(kind) => kind,
+                _ => String::new(),
+            };
+            ctx.push_block(BlockContext::CalloutTitle(kind));
+            ctx.push_style(TokenStyle::Bold);
+            for child in &node.children {
+                walk_ast(child, ctx, tokens, sections);
+            }
+            ctx.pop_style();
+            ctx.pop_block();
+            // The callout body starts a new block after the title
+            ctx.new_block_entered = true;
+        }
+        ctx.skip_depth += 1;
+        restore_skip = true;
+        return (
+            restore_style,
+            restore_block,
+            restore_skip,
+            restore_list_depth,
+            restore_quote_depth,
+        );
+    }
+
+    // Attachment embeds (`![[diagram.png]]`) read like images
+    if let Some(embed) = node.cast::<WikiEmbed>() {
+        if !embed.resolved && !embed.is_note() {
+            if !ctx.should_skip() {
+                push_figure(ctx, tokens, &node.collect_text(), None);
+            }
+            ctx.skip_depth += 1;
+            restore_skip = true;
+            return (
+                restore_style,
+                restore_block,
+                restore_skip,
+                restore_list_depth,
+                restore_quote_depth,
+            );
+        }
+    }
+
+    // `<a id="...">` and `<a name="...">` mark link targets
+    if let Some(html) = node.cast::<HtmlInline>() {
+        if !ctx.should_skip() {
+            if let Some(name) =
+                attr_value(&html.content, "id").or_else(|| attr_value(&html.content, "name"))
+            {
+                ctx.anchors.entry(name.to_string()).or_insert(tokens.len());
+            }
+        }
+    }
+
+    // Images are read as their alt text and title; children are the alt text
+    if let Some(image) = node.cast::<Image>() {
+        if !ctx.should_skip() {
+            push_figure(ctx, tokens, &node.collect_text(), image.title.as_deref());
+        }
         ctx.skip_depth += 1;
         restore_skip = true;
         return (
@@ -336,6 +859,7 @@ fn enter_node(
 
             // Extract section title and create section entry
             let title = node.collect_text();
+            ctx.add_heading_anchor(&title, tokens.len());
             sections.push(Section {
                 title,
                 level,
@@ -355,36 +879,27 @@ fn enter_node(
             ctx.push_block(BlockContext::Paragraph);
             restore_block = true;
         }
-    } else if node.is::<Blockquote>() {
+    } else if let Some(callout) = node.cast::<Callout>() {
+        // Callouts (e.g. "> [!note] Title") read their title as a separate block
         ctx.quote_depth += 1;
+        ctx.in_callout = true;
+        ctx.push_block(BlockContext::Callout(callout.kind.clone()));
+        restore_block = true;
+        restore_quote_depth = true;
+        ctx.push_parent(BlockHint::Quote);
 
-        // Check if this is a callout (e.g., "[!folder]", "[!note]")
-        // We need to look at paragraph text nodes inside the blockquote
-        let mut callout_type: Option<String> = None;
-        for child in &node.children {
-            // Check if this is a paragraph with callout syntax
-            if child.is::<Paragraph>() {
-                for grandchild in &child.children {
-                    if let Some(text) = grandchild.cast::<Text>() {
-                        callout_type = detect_callout_type(&text.content);
-                        if callout_type.is_some() {
-                            break;
-                        }
-                    }
-                }
-            }
-            if callout_type.is_some() {
-                break;
+        // Folded callouts can be read as just their title
+        if ctx.skip_folded_callouts && callout.folded == Some(true) {
+            if let Some(title) = node.children.first() {
+                walk_ast(title, ctx, tokens, sections);
             }
+            ctx.skip_depth += 1;
+            restore_skip = true;
         }
-
-        if let Some(ct) = callout_type {
-            ctx.in_callout = true;
-            ctx.push_block(BlockContext::Callout(ct));
-        } else {
-            ctx.in_callout = false;
-            ctx.push_block(BlockContext::Quote(ctx.quote_depth));
-        }
+    } else if node.is::<Blockquote>() {
+        ctx.quote_depth += 1;
+        ctx.in_callout = false;
+        ctx.push_block(BlockContext::Quote(ctx.quote_depth));
         restore_block = true;
         restore_quote_depth = true;
         ctx.push_parent(BlockHint::Quote);
@@ -392,7 +907,13 @@ fn enter_node(
         ctx.list_depth += 1;
         restore_list_depth = true;
     } else if node.is::<ListItem>() {
-        ctx.push_block(BlockContext::ListItem(ctx.list_depth));
+        match list_item_text(node).and_then(detect_task_marker) {
+            Some(checked) => {
+                ctx.push_block(BlockContext::TaskItem(ctx.list_depth, checked));
+                ctx.strip_task_marker = true;
+            }
+            None => ctx.push_block(BlockContext::ListItem(ctx.list_depth)),
+        }
         restore_block = true;
     } else if node.is::<Table>() {
         // Reset counters when entering a table
@@ -430,6 +951,9 @@ fn enter_node(
     } else if node.is::<Em>() {
         ctx.push_style(TokenStyle::Italic);
         restore_style = true;
+    } else if node.is::<Strikethrough>() {
+        ctx.push_style(TokenStyle::Strikethrough);
+        restore_style = true;
     } else if node.is::<CodeInline>() {
         ctx.in_inline_code = true;
         ctx.push_style(TokenStyle::Code);
@@ -439,16 +963,32 @@ fn enter_node(
             ctx.push_style(TokenStyle::Link(link.url.clone()));
             restore_style = true;
         }
+    } else if let Some(link) = node.cast::<Linkified>() {
+        ctx.push_style(TokenStyle::Link(link.url.clone()));
+        restore_style = true;
+    } else if let Some(link) = node.cast::<WikiLink>() {
+        ctx.push_style(TokenStyle::Link(link.target.clone()));
+        restore_style = true;
+    } else if let Some(embed) = node.cast::<WikiEmbed>() {
+        // Embeds that couldn't be resolved read as a link to the note
+        if !embed.resolved {
+            ctx.push_style(TokenStyle::Link(embed.target.clone()));
+            restore_style = true;
+        }
     }
 
     // Handle text nodes - extract words
     if let Some(text) = node.cast::<Text>() {
         if !ctx.should_skip() {
             // If inside inline code, preserve the entire text as a single token
+            let mut content = text.content.as_str();
+            if std::mem::take(&mut ctx.strip_task_marker) && detect_task_marker(content).is_some() {
+                content = &content[3..];
+            }
             let words = if ctx.in_inline_code {
-                vec![text.content.clone()]
+                vec![content.to_string()]
             } else {
-                split_into_words(&text.content)
+                split_into_words(content, ctx.script)
             };
             let word_count = words.len();
 
@@ -493,6 +1033,83 @@ fn enter_node(
     )
 }
 
+/// Record a code block and emit its tokens: a single placeholder, or one token
+/// per non-empty line when reading code line by line.
+fn push_code_block(
+    ctx: &mut ParserContext,
+    tokens: &mut Vec<Token>,
+    language: Option<String>,
+    content: &str,
+) {
+    let mut block = CodeBlock {
+        language,
+        content: content.trim_end_matches('\n').to_string(),
+        token_start: tokens.len(),
+        token_end: tokens.len(),
+    };
+
+    let words: Vec<String> = if ctx.code_lines {
+        block
+            .content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    } else {
+        vec![block.placeholder()]
+    };
+
+    for (i, word) in words.into_iter().enumerate() {
+        let timing_hint = generate_timing_hint(&word, false, i == 0, false, false, None);
+        tokens.push(Token {
+            word,
+            style: TokenStyle::Code,
+            block: BlockContext::CodeBlock,
+            parent_context: ctx.current_parent(),
+            timing_hint,
+        });
+    }
+
+    block.token_end = tokens.len();
+    ctx.code_blocks.push(block);
+    // Whatever follows the code starts a new block
+    ctx.new_block_entered = true;
+}
+
+/// Word standing in for an image without alt text or title
+pub(crate) const IMAGE_MARKER: &str = "[image]";
+
+/// Emit an image's alt text and title as figure tokens, falling back to a
+/// generic marker so the figure still shows up in the word stream.
+fn push_figure(ctx: &mut ParserContext, tokens: &mut Vec<Token>, alt: &str, title: Option<&str>) {
+    let mut words = split_into_words(alt, ctx.script);
+    words.extend(
+        title
+            .map(|t| split_into_words(t, ctx.script))
+            .unwrap_or_default(),
+    );
+    if words.is_empty() {
+        words.push(IMAGE_MARKER.to_string());
+    }
+
+    let word_count = words.len();
+    for (i, word) in words.into_iter().enumerate() {
+        let is_end = i == word_count - 1;
+        let timing_hint = generate_timing_hint(&word, is_end, i == 0, false, false, None);
+        tokens.push(Token {
+            word,
+            style: TokenStyle::Image,
+            block: BlockContext::Figure,
+            parent_context: ctx.current_parent(),
+            timing_hint,
+        });
+    }
+
+    // Text after an inline image resumes as a new block
+    ctx.new_block_entered = true;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -506,10 +1123,17 @@ mod tests {
 
     #[test]
     fn test_word_splitting() {
-        let words = split_into_words("Hello   world\ntest");
+        let words = split_into_words("Hello   world\ntest", Script::Default);
         assert_eq!(words, vec!["Hello", "world", "test"]);
     }
 
+    #[test]
+    fn test_split_cjk() {
+        assert_eq!(split_cjk("吾輩は猫である。"), vec!["吾輩は猫", "である。"]);
+        assert_eq!(split_cjk("Rust言語"), vec!["Rust", "言語"]);
+        assert_eq!(split_cjk("plain"), vec!["plain"]);
+    }
+
     #[test]
     fn test_empty_input() {
         let parser = MarkdownParser::new();
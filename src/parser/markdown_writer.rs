@@ -0,0 +1,366 @@
+//! Markdown written back out from document nodes, for exporting EPUBs
+//!
+//! Chapters are read into markdown-it nodes by
+//! [`xhtml_tree`](super::xhtml_tree); this writes those nodes as Markdown
+//! that reads back the same. Footnote definitions are gathered at the end.
+
+use markdown_it::parser::inline::Text;
+use markdown_it::plugins::cmark::block::{
+    blockquote::Blockquote,
+    fence::CodeFence,
+    heading::ATXHeading,
+    list::{BulletList, ListItem, OrderedList},
+    paragraph::Paragraph,
+};
+use markdown_it::plugins::cmark::inline::{
+    backticks::CodeInline,
+    emphasis::{Em, Strong},
+    image::Image,
+    link::Link,
+};
+use markdown_it::plugins::extra::strikethrough::Strikethrough;
+use markdown_it::plugins::extra::tables::Table;
+use markdown_it::plugins::html::html_inline::HtmlInline;
+use markdown_it::Node;
+
+use crate::parser::footnote::{FootnoteDefinition, FootnoteReference};
+
+/// Markdown for a run of block nodes
+pub(crate) fn write(nodes: &[Node]) -> String {
+    let mut writer = Writer::default();
+    let mut out = writer.blocks(nodes);
+    // Notes can hold notes of their own
+    while !writer.notes.is_empty() {
+        for note in std::mem::take(&mut writer.notes) {
+            if !out.is_empty() {
+                out.push_str("\n\n");
+            }
+            out.push_str(&note);
+        }
+    }
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+#[derive(Default)]
+struct Writer {
+    /// Footnote definitions, written after the text
+    notes: Vec<String>,
+}
+
+impl Writer {
+    /// Blocks separated by blank lines
+    fn blocks(&mut self, nodes: &[Node]) -> String {
+        let blocks: Vec<String> = nodes.iter().filter_map(|node| self.block(node)).collect();
+        blocks.join("\n\n")
+    }
+
+    fn block(&mut self, node: &Node) -> Option<String> {
+        let block = if let Some(heading) = node.cast::<ATXHeading>() {
+            let level = usize::from(heading.level);
+            format!("{} {}", "#".repeat(level), self.inline(&node.children))
+        } else if node.is::<Paragraph>() {
+            escape_line_start(self.inline(&node.children))
+        } else if node.is::<Blockquote>() {
+            self.blocks(&node.children)
+                .lines()
+                .map(|line| format!("> {line}").trim_end().to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else if node.is::<BulletList>() || node.is::<OrderedList>() {
+            self.list(node)
+        } else if node.is::<ListItem>() {
+            self.list_item(node, "- ", "")
+        } else if node.is::<Table>() {
+            self.table(node)
+        } else if let Some(fence) = node.cast::<CodeFence>() {
+            code_fence(fence)
+        } else if let Some(note) = node.cast::<FootnoteDefinition>() {
+            self.note(&note.label, &node.children);
+            return None;
+        } else {
+            // Anchors and figures outside a paragraph
+            escape_line_start(self.inline(std::slice::from_ref(node)))
+        };
+        (!block.trim().is_empty()).then_some(block)
+    }
+
+    fn list(&mut self, node: &Node) -> String {
+        let mut number = node.cast::<OrderedList>().map(|list| list.start);
+        let mut items = Vec::new();
+        // Anchors for an item come just before it
+        let mut anchors = String::new();
+        for child in &node.children {
+            if let Some(html) = child.cast::<HtmlInline>() {
+                anchors.push_str(&html.content);
+            } else if child.is::<ListItem>() {
+                let marker = number.map_or_else(|| "- ".to_string(), |n| format!("{n}. "));
+                number = number.map(|n| n + 1);
+                items.push(self.list_item(child, &marker, &std::mem::take(&mut anchors)));
+            } else {
+                items.extend(self.block(child));
+            }
+        }
+        items.join("\n")
+    }
+
+    /// List item with its continuation lines indented under the marker
+    fn list_item(&mut self, node: &Node, marker: &str, anchors: &str) -> String {
+        let is_tight = node.children.iter().all(is_inline);
+        let content = if is_tight {
+            self.inline(&node.children)
+        } else {
+            self.blocks(&node.children)
+        };
+        indent(&format!("{anchors}{content}"), marker)
+    }
+
+    fn table(&mut self, node: &Node) -> String {
+        let rows: Vec<Vec<String>> = node
+            .children
+            .iter()
+            .map(|row| {
+                row.children
+                    .iter()
+                    .map(|cell| self.inline(&cell.children).replace('|', "\\|"))
+                    .collect()
+            })
+            .collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut lines = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            let cells: Vec<&str> = (0..columns)
+                .map(|c| row.get(c).map_or("", String::as_str))
+                .collect();
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if i == 0 {
+                lines.push(format!("|{}", "---|".repeat(columns)));
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn note(&mut self, label: &str, children: &[Node]) {
+        let body = if children.iter().all(is_inline) {
+            self.inline(children)
+        } else {
+            self.blocks(children)
+        };
+        if !body.trim().is_empty() {
+            let note = indent(&body, &format!("[^{label}]: "));
+            self.notes.push(note);
+        }
+    }
+
+    /// Inline content on one line, with runs of spaces and line breaks
+    /// collapsed as in HTML
+    fn inline(&mut self, nodes: &[Node]) -> String {
+        let mut out = String::new();
+        for node in nodes {
+            self.inline_node(node, &mut out);
+        }
+        out.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    fn inline_node(&mut self, node: &Node, out: &mut String) {
+        if let Some(text) = node.cast::<Text>() {
+            out.push_str(&escape(&text.content));
+        } else if node.is::<Em>() {
+            self.delimited(node, "*", "*", out);
+        } else if node.is::<Strong>() {
+            self.delimited(node, "**", "**", out);
+        } else if node.is::<Strikethrough>() {
+            self.delimited(node, "~~", "~~", out);
+        } else if node.is::<CodeInline>() {
+            out.push_str(&code_span(&node.collect_text()));
+        } else if let Some(link) = node.cast::<Link>() {
+            let close = format!("]({})", destination(&link.url, link.title.as_deref()));
+            self.delimited(node, "[", &close, out);
+        } else if let Some(image) = node.cast::<Image>() {
+            let alt = escape(&node.collect_text());
+            out.push_str(&format!(
+                "![{alt}]({})",
+                destination(&image.url, image.title.as_deref())
+            ));
+        } else if let Some(reference) = node.cast::<FootnoteReference>() {
+            out.push_str(&format!("[^{}]", reference.label));
+        } else if let Some(note) = node.cast::<FootnoteDefinition>() {
+            self.note(&note.label, &node.children);
+        } else if let Some(html) = node.cast::<HtmlInline>() {
+            out.push_str(&html.content);
+        } else {
+            for child in &node.children {
+                self.inline_node(child, out);
+            }
+        }
+    }
+
+    /// Styled or linked text between `open` and `close`, with spaces at its
+    /// edges moved outside so the delimiters still open and close it
+    fn delimited(&mut self, node: &Node, open: &str, close: &str, out: &mut String) {
+        let mut inner = String::new();
+        for child in &node.children {
+            self.inline_node(child, &mut inner);
+        }
+        let trimmed = inner.trim_matches(|c: char| c.is_ascii_whitespace());
+        if trimmed.is_empty() {
+            out.push_str(&inner);
+            return;
+        }
+        if inner.starts_with(|c: char| c.is_ascii_whitespace()) {
+            out.push(' ');
+        }
+        out.push_str(&format!("{open}{trimmed}{close}"));
+        if inner.ends_with(|c: char| c.is_ascii_whitespace()) {
+            out.push(' ');
+        }
+    }
+}
+
+fn is_inline(node: &Node) -> bool {
+    !(node.is::<Paragraph>()
+        || node.is::<ATXHeading>()
+        || node.is::<Blockquote>()
+        || node.is::<BulletList>()
+        || node.is::<OrderedList>()
+        || node.is::<Table>()
+        || node.is::<CodeFence>())
+}
+
+/// `text` with `first` before its first line and the rest indented to match
+fn indent(text: &str, first: &str) -> String {
+    let pad = " ".repeat(first.chars().count());
+    let mut out = String::new();
+    for (i, line) in text.lines().enumerate() {
+        if i > 0 {
+            out.push('\n');
+            if !line.is_empty() {
+                out.push_str(&pad);
+            }
+        } else {
+            out.push_str(first);
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+/// Backslash-escape characters that would start Markdown syntax
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '~') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Escape text that would otherwise start a heading, quote or list
+fn escape_line_start(mut line: String) -> String {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && line[digits..].starts_with(['.', ')']) {
+        line.insert(digits, '\\');
+    } else if line.starts_with(['#', '>', '-', '+', '=']) {
+        line.insert(0, '\\');
+    }
+    line
+}
+
+/// Link destination in angle brackets, so spaces and parentheses are kept,
+/// and its title
+fn destination(url: &str, title: Option<&str>) -> String {
+    let mut out = if url.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    };
+    if let Some(title) = title {
+        out.push_str(&format!(" \"{}\"", title.replace('"', "\\\"")));
+    }
+    out
+}
+
+/// Code span fenced by more backticks than `code` has in a row
+fn code_span(code: &str) -> String {
+    let longest = longest_run(code, '`');
+    let fence = "`".repeat(longest + 1);
+    let pad = if code.starts_with('`') || code.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{fence}{pad}{code}{pad}{fence}")
+}
+
+fn code_fence(fence: &CodeFence) -> String {
+    let marker = "`".repeat(longest_run(&fence.content, '`').max(2) + 1);
+    let content = fence.content.trim_end_matches('\n');
+    format!("{marker}{}\n{content}\n{marker}", fence.info)
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    text.split(|x| x != c).map(str::len).max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::xhtml::NoteLabels;
+    use crate::parser::xhtml_tree::{chapter_nodes, root};
+    use crate::parser::{DocumentParser, MarkdownParser, ParsedDocument};
+    use crate::types::{BlockContext, DocumentMetadata, TokenStyle};
+
+    /// Words, styles and blocks, for comparing a chapter with its export
+    fn summary(doc: &ParsedDocument) -> Vec<(String, TokenStyle, BlockContext)> {
+        doc.tokens
+            .iter()
+            .map(|t| (t.word.clone(), t.style.clone(), t.block.clone()))
+            .collect()
+    }
+
+    /// The chapter as read, and its export read back
+    fn read_both(xhtml: &str) -> (ParsedDocument, ParsedDocument, String) {
+        let nodes = chapter_nodes(xhtml, "ch1.xhtml", &mut NoteLabels::default());
+        let markdown = write(&nodes);
+        let mut tree = root();
+        tree.children = nodes;
+        let read = MarkdownParser::new().parse_tree(&tree, DocumentMetadata::default());
+        let exported = MarkdownParser::new().parse_str(&markdown).unwrap();
+        (read, exported, markdown)
+    }
+
+    #[test]
+    fn test_export_reads_like_the_chapter() {
+        let (read, exported, _) = read_both(
+            "<h2>The <em>Start</em></h2>\
+             <p>Some <i>very </i><strong>bold <em>and</em></strong> <code>x = `1`</code> \
+             <a href=\"https://example.com/(a)\"> link</a> <del>gone</del> *literal* [x].</p>\
+             <p>1999. A year</p>\
+             <blockquote><p>Quoted words.</p><p>Two.</p></blockquote>\
+             <ul><li>One</li><li>Two <b>items</b></li></ul><ol start=\"3\"><li>Third</li></ol>\
+             <table><tr><th>Name</th><th>A|B</th></tr><tr><td>Ann</td></tr></table>\
+             <pre><code class=\"language-rust\">fn main() {}\n</code></pre>",
+        );
+        assert_eq!(summary(&exported), summary(&read));
+        assert_eq!(exported.code_blocks, read.code_blocks);
+    }
+
+    #[test]
+    fn test_notes_follow_the_text() {
+        let (read, exported, markdown) = read_both(
+            "<p>A claim<a epub:type=\"noteref\" href=\"#fn1\">1</a> stands.</p>\
+             <aside epub:type=\"footnote\" id=\"fn1\"><p>Cited <em>source</em>.</p></aside>\
+             <p>Next.</p>",
+        );
+        assert_eq!(
+            markdown,
+            "A claim[^1] stands.\n\nNext.\n\n[^1]: Cited *source*.\n"
+        );
+        assert_eq!(summary(&exported), summary(&read));
+        assert_eq!(exported.footnotes[0].text(), read.footnotes[0].text());
+    }
+}
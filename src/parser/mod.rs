@@ -1,7 +1,21 @@
+pub mod book;
+pub mod chapters;
+pub mod encoding;
 pub mod epub;
+pub mod footnote;
+pub mod frontmatter;
 pub mod markdown;
+mod markdown_writer;
+pub mod obsidian;
+pub mod stream;
 pub mod traits;
+mod xhtml;
+mod xhtml_tree;
 
-pub use epub::EpubParser;
-pub use markdown::MarkdownParser;
-pub use traits::{DocumentParser, ParseError, ParsedDocument};
+pub use crate::types::Encoding;
+pub use book::BookParser;
+pub use chapters::ChapterLoader;
+pub use epub::{EpubOptions, EpubParser, ExportOptions, ExportSummary};
+pub use markdown::{MarkdownOptions, MarkdownParser};
+pub use stream::StreamParser;
+pub use traits::{DocumentParser, Location, ParseError, ParseWarning, ParsedDocument};
@@ -0,0 +1,439 @@
+//! Obsidian syntax for markdown-it
+//!
+//! `[[Note|alias]]` wikilinks read as links, `![[Note]]` embeds are inlined
+//! from the vault, and `> [!type]- Title` callouts get a title node and a
+//! fold state.
+
+use std::path::{Component, Path, PathBuf};
+
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::inline::{InlineRule, InlineState, Text};
+use markdown_it::plugins::cmark::block::{
+    blockquote::Blockquote, heading::ATXHeading, paragraph::Paragraph,
+};
+use markdown_it::plugins::cmark::inline::newline::{Hardbreak, Softbreak};
+use markdown_it::{MarkdownIt, Node, NodeValue};
+
+use crate::parser::encoding;
+use crate::parser::frontmatter::split_front_matter;
+use crate::parser::traits::ParseError;
+use crate::types::Encoding;
+
+/// File extensions read as notes when embedded; anything else is an attachment
+const NOTE_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+/// `[[target|alias]]` link; the child is the display text
+#[derive(Debug)]
+pub struct WikiLink {
+    pub target: String,
+}
+
+impl NodeValue for WikiLink {}
+
+/// `![[target|alias]]` embed.
+///
+/// The child is the display text until the embed is resolved, after which
+/// the children are the embedded note's blocks.
+#[derive(Debug)]
+pub struct WikiEmbed {
+    pub target: String,
+    pub resolved: bool,
+}
+
+impl WikiEmbed {
+    /// True if the target is a note rather than an image or other attachment
+    #[must_use]
+    pub fn is_note(&self) -> bool {
+        let (name, _) = split_target(&self.target);
+        Path::new(name)
+            .extension()
+            .is_none_or(|ext| NOTE_EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+    }
+}
+
+impl NodeValue for WikiEmbed {}
+
+/// Blockquote starting with a `[!type]` marker
+#[derive(Debug)]
+pub struct Callout {
+    pub kind: String,
+    /// `Some(true)` for `[!type]-` (collapsed), `Some(false)` for `[!type]+`
+    pub folded: Option<bool>,
+}
+
+impl NodeValue for Callout {}
+
+/// First child of a [`Callout`]; children are the title inlines
+#[derive(Debug)]
+pub struct CalloutTitle;
+
+impl NodeValue for CalloutTitle {}
+
+/// Add wikilinks, embeds and callouts to a parser.
+///
+/// Should be added after rules that merge text nodes, since the callout
+/// marker is read from the first text node of a blockquote.
+pub fn add(md: &mut MarkdownIt) {
+    md.inline.add_rule::<WikiLinkScanner>().before_all();
+    md.inline.add_rule::<WikiEmbedScanner>().before_all();
+    md.add_rule::<CalloutRule>().after_all();
+}
+
+/// Parse `[[target|alias]]` at the start of `s`, returning target, alias and length
+fn parse_wikilink(s: &str) -> Option<(&str, Option<&str>, usize)> {
+    let rest = s.strip_prefix("[[")?;
+    let end = rest.find("]]")?;
+    let inner = &rest[..end];
+    if inner.contains(['[', ']', '\n']) {
+        return None;
+    }
+    let (target, alias) = match inner.split_once('|') {
+        Some((target, alias)) => (target.trim(), Some(alias.trim())),
+        None => (inner.trim(), None),
+    };
+    if target.is_empty() {
+        return None;
+    }
+    Some((target, alias.filter(|a| !a.is_empty()), end + 4))
+}
+
+/// Split `Note#Heading` into the note name and the optional heading
+fn split_target(target: &str) -> (&str, Option<&str>) {
+    match target.split_once('#') {
+        Some((name, heading)) => (name.trim(), Some(heading.trim())),
+        None => (target, None),
+    }
+}
+
+/// Text shown for a link without an alias: the note name without folders,
+/// or the heading for links within the same note
+fn display_text(target: &str) -> String {
+    let (name, heading) = split_target(target);
+    let name = name.rsplit('/').next().unwrap_or(name);
+    let name = name
+        .strip_suffix(".md")
+        .or_else(|| name.strip_suffix(".markdown"))
+        .unwrap_or(name);
+    // Block references (`#^id`) have no readable text
+    match heading.filter(|h| !h.starts_with('^')) {
+        Some(heading) if name.is_empty() => heading.to_string(),
+        Some(heading) => format!("{name} {heading}"),
+        None => name.to_string(),
+    }
+}
+
+/// Node with a single text child
+fn text_node(content: String) -> Node {
+    Node::new(Text { content })
+}
+
+struct WikiLinkScanner;
+
+impl InlineRule for WikiLinkScanner {
+    const MARKER: char = '[';
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let (target, alias, len) = parse_wikilink(&state.src[state.pos..state.pos_max])?;
+        let display = alias.map_or_else(|| display_text(target), str::to_string);
+        let mut node = Node::new(WikiLink {
+            target: target.to_string(),
+        });
+        node.children.push(text_node(display));
+        Some((node, len))
+    }
+}
+
+struct WikiEmbedScanner;
+
+impl InlineRule for WikiEmbedScanner {
+    const MARKER: char = '!';
+
+    fn run(state: &mut InlineState) -> Option<(Node, usize)> {
+        let src = state.src[state.pos..state.pos_max].strip_prefix('!')?;
+        let (target, alias, len) = parse_wikilink(src)?;
+        let display = alias.map_or_else(|| display_text(target), str::to_string);
+        let mut node = Node::new(WikiEmbed {
+            target: target.to_string(),
+            resolved: false,
+        });
+        node.children.push(text_node(display));
+        Some((node, len + 1))
+    }
+}
+
+/// Parse a `[!type]` callout marker with optional `-`/`+` fold marker,
+/// returning the lowercase type, fold state and the rest of the line
+fn parse_callout_marker(text: &str) -> Option<(String, Option<bool>, &str)> {
+    let rest = text.trim_start().strip_prefix("[!")?;
+    let end = rest.find(']')?;
+    let kind = rest[..end].trim().to_lowercase();
+    if kind.is_empty() {
+        return None;
+    }
+    let rest = &rest[end + 1..];
+    let (folded, rest) = match rest.chars().next() {
+        Some('-') => (Some(true), &rest[1..]),
+        Some('+') => (Some(false), &rest[1..]),
+        _ => (None, rest),
+    };
+    Some((kind, folded, rest.trim_start()))
+}
+
+/// Default callout title: the type with its first letter capitalised
+fn default_title(kind: &str) -> String {
+    let mut chars = kind.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// Turns blockquotes that start with a callout marker into [`Callout`]
+/// nodes, moving the rest of the marker line into a [`CalloutTitle`]
+struct CalloutRule;
+
+impl CoreRule for CalloutRule {
+    fn run(root: &mut Node, _: &MarkdownIt) {
+        root.walk_mut(|node, _| {
+            if !node.is::<Blockquote>() {
+                return;
+            }
+            let Some(paragraph) = node.children.first_mut().filter(|c| c.is::<Paragraph>()) else {
+                return;
+            };
+            let Some(marker) = paragraph.children.first().and_then(|c| c.cast::<Text>()) else {
+                return;
+            };
+            let Some((kind, folded, rest)) = parse_callout_marker(&marker.content) else {
+                return;
+            };
+            let rest = rest.to_string();
+
+            // The title runs to the end of the marker line
+            let line_end = paragraph
+                .children
+                .iter()
+                .position(|c| c.is::<Softbreak>() || c.is::<Hardbreak>())
+                .unwrap_or(paragraph.children.len());
+            let mut title: Vec<Node> = paragraph.children.drain(..line_end).collect();
+            if !paragraph.children.is_empty() {
+                paragraph.children.remove(0);
+            }
+            if rest.is_empty() {
+                title.remove(0);
+            } else if let Some(text) = title[0].cast_mut::<Text>() {
+                text.content = rest;
+            }
+            if title.is_empty() {
+                title.push(text_node(default_title(&kind)));
+            }
+            if paragraph.children.is_empty() {
+                node.children.remove(0);
+            }
+
+            let mut title_node = Node::new(CalloutTitle);
+            title_node.children = title;
+            node.children.insert(0, title_node);
+            node.replace(Callout { kind, folded });
+        });
+    }
+}
+
+/// Replace note embeds below `node` with the embedded notes' content.
+///
+/// Notes are looked up in `vault`, by relative path first and then by file
+/// name anywhere in the vault. `stack` holds the notes being expanded, so an
+/// embed cycle is left unresolved instead of recursing forever. Notes are
+/// decoded from `encoding`, or the one detected when `None`.
+pub(crate) fn expand_embeds(
+    md: &MarkdownIt,
+    node: &mut Node,
+    vault: &Path,
+    encoding: Option<Encoding>,
+    stack: &mut Vec<PathBuf>,
+) {
+    for child in &mut node.children {
+        let Some(embed) = child.cast::<WikiEmbed>() else {
+            expand_embeds(md, child, vault, encoding, stack);
+            continue;
+        };
+        if !embed.is_note() {
+            continue;
+        }
+        let (name, heading) = split_target(&embed.target);
+        let heading = heading.map(str::to_string);
+        let Some(path) = find_note(vault, name) else {
+            continue;
+        };
+        let key = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if stack.contains(&key) {
+            continue;
+        }
+        let Ok((content, _)) = std::fs::read(&path)
+            .map_err(ParseError::from)
+            .and_then(|bytes| encoding::decode(&bytes, encoding))
+        else {
+            continue;
+        };
+
+        let (_, body) = split_front_matter(&content);
+        let mut root = md.parse(body);
+        stack.push(key);
+        expand_embeds(md, &mut root, vault, encoding, stack);
+        stack.pop();
+
+        let mut blocks = std::mem::take(&mut root.children);
+        if let Some(heading) = heading.as_deref().filter(|h| !h.starts_with('^')) {
+            blocks = heading_section(blocks, heading);
+        }
+        child.children = blocks;
+        if let Some(embed) = child.cast_mut::<WikiEmbed>() {
+            embed.resolved = true;
+        }
+    }
+}
+
+/// The heading named `heading` and the blocks under it, or all blocks if
+/// no heading matches
+fn heading_section(mut blocks: Vec<Node>, heading: &str) -> Vec<Node> {
+    let level_of = |node: &Node| node.cast::<ATXHeading>().map(|h| h.level);
+    let Some(start) = blocks.iter().position(|b| {
+        b.is::<ATXHeading>() && b.collect_text().trim().eq_ignore_ascii_case(heading)
+    }) else {
+        return blocks;
+    };
+    let level = level_of(&blocks[start]).unwrap_or(1);
+    let end = blocks[start + 1..]
+        .iter()
+        .position(|b| level_of(b).is_some_and(|l| l <= level))
+        .map_or(blocks.len(), |i| start + 1 + i);
+    blocks.drain(start..end).collect()
+}
+
+/// How many directory levels below the vault a note is searched for by name
+const SEARCH_DEPTH: usize = 6;
+
+/// Directories with more entries than this are not searched, so a vault
+/// that defaults to a large directory such as `$HOME` stays cheap
+const SEARCH_DIR_LIMIT: usize = 1000;
+
+/// Find a note in the vault by relative path, then by file name. Names
+/// that could reach outside the vault, such as `../secret` or absolute
+/// paths, are not looked up.
+pub(crate) fn find_note(vault: &Path, name: &str) -> Option<PathBuf> {
+    let inside_vault = Path::new(name)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !inside_vault {
+        return None;
+    }
+    let file = if Path::new(name).extension().is_some() {
+        name.to_string()
+    } else {
+        format!("{name}.md")
+    };
+    let direct = vault.join(&file);
+    if direct.is_file() {
+        return Some(direct);
+    }
+    let file_name = Path::new(&file).file_name()?;
+    search_vault(vault, file_name, SEARCH_DEPTH)
+}
+
+/// Depth-first search for a file name at most `depth` levels down, skipping
+/// hidden directories such as `.obsidian` and `.git` and very large ones
+fn search_vault(dir: &Path, file_name: &std::ffi::OsStr, depth: usize) -> Option<PathBuf> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .take(SEARCH_DIR_LIMIT + 1)
+        .collect();
+    if entries.len() > SEARCH_DIR_LIMIT {
+        return None;
+    }
+    entries.sort();
+
+    if let Some(found) = entries
+        .iter()
+        .find(|p| p.is_file() && p.file_name() == Some(file_name))
+    {
+        return Some(found.clone());
+    }
+    if depth == 0 {
+        return None;
+    }
+    entries
+        .iter()
+        .filter(|p| p.is_dir())
+        .filter(|p| {
+            !p.file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+        })
+        .find_map(|p| search_vault(p, file_name, depth - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wikilink() {
+        assert_eq!(parse_wikilink("[[Note]] x"), Some(("Note", None, 8)));
+        assert_eq!(
+            parse_wikilink("[[Dir/Note|the note]]"),
+            Some(("Dir/Note", Some("the note"), 21))
+        );
+        assert_eq!(parse_wikilink("[[]]"), None);
+        assert_eq!(parse_wikilink("[[a\nb]]"), None);
+        assert_eq!(parse_wikilink("[link]"), None);
+    }
+
+    #[test]
+    fn test_display_text() {
+        assert_eq!(display_text("Dir/Note"), "Note");
+        assert_eq!(display_text("Note.md"), "Note");
+        assert_eq!(display_text("Note#Usage"), "Note Usage");
+        assert_eq!(display_text("#Usage"), "Usage");
+        assert_eq!(display_text("Note#^abc123"), "Note");
+    }
+
+    #[test]
+    fn test_parse_callout_marker() {
+        assert_eq!(
+            parse_callout_marker("[!NOTE] Read this"),
+            Some(("note".to_string(), None, "Read this"))
+        );
+        assert_eq!(
+            parse_callout_marker("[!tip]- Hidden"),
+            Some(("tip".to_string(), Some(true), "Hidden"))
+        );
+        assert_eq!(
+            parse_callout_marker("[!faq]+"),
+            Some(("faq".to_string(), Some(false), ""))
+        );
+        assert_eq!(parse_callout_marker("[!] x"), None);
+        assert_eq!(parse_callout_marker("plain"), None);
+    }
+
+    #[test]
+    fn test_find_note_search_is_bounded() {
+        let vault = std::env::temp_dir().join(format!("rsvp-search-{}", std::process::id()));
+        let deep = (0..=SEARCH_DEPTH).fold(vault.clone(), |p, i| p.join(format!("d{i}")));
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::write(deep.join("Deep.md"), "").unwrap();
+        std::fs::write(deep.parent().unwrap().join("Near.md"), "").unwrap();
+        let crowded = vault.join("crowded");
+        std::fs::create_dir_all(&crowded).unwrap();
+        for i in 0..SEARCH_DIR_LIMIT {
+            std::fs::write(crowded.join(format!("{i}.txt")), "").unwrap();
+        }
+        std::fs::write(crowded.join("Crowded.md"), "").unwrap();
+
+        assert!(find_note(&vault, "Near").is_some());
+        assert_eq!(find_note(&vault, "Deep"), None, "too deep");
+        assert_eq!(find_note(&vault, "Crowded"), None, "too many entries");
+        assert!(find_note(&vault, "crowded/Crowded").is_some());
+
+        std::fs::remove_dir_all(&vault).unwrap();
+    }
+}
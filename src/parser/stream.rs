@@ -0,0 +1,235 @@
+//! Markdown arriving in pieces, e.g. piped command output
+//!
+//! Text is parsed one run of complete blocks at a time. A blank line outside
+//! a fenced code block ends every block, and a line of plain text ends once
+//! the next line shows it isn't a heading or table header, so output without
+//! blank lines is read line by line. Lists, quotes, tables and footnotes wait
+//! for a blank line, unless the input goes quiet. Front matter opening the
+//! stream waits for its closing fence. The rest waits for more input.
+
+use crate::parser::frontmatter::{front_matter_end, opens_front_matter, split_front_matter};
+use crate::parser::markdown::{MarkdownOptions, MarkdownParser};
+use crate::parser::traits::{ParseError, ParsedDocument};
+use crate::types::DocumentMetadata;
+
+/// Incremental Markdown parser for growing input
+pub struct StreamParser {
+    md_parser: MarkdownParser,
+    /// Received text not parsed yet
+    pending: String,
+    /// Metadata from front matter at the start of the stream
+    metadata: Option<DocumentMetadata>,
+}
+
+impl StreamParser {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_options(MarkdownOptions::default())
+    }
+
+    #[must_use]
+    pub fn with_options(options: MarkdownOptions) -> Self {
+        Self {
+            md_parser: MarkdownParser::with_options(options),
+            pending: String::new(),
+            metadata: None,
+        }
+    }
+
+    /// Add received text. Returns the blocks it completed, positioned from
+    /// zero; the caller appends them to what was parsed before.
+    pub fn push(&mut self, text: &str) -> Result<Option<ParsedDocument>, ParseError> {
+        self.pending.push_str(text);
+        self.take(complete_prefix(&self.pending, false))
+    }
+
+    /// Parse every complete line outside a code block, for when no input has
+    /// arrived for a while and the last lines shouldn't wait for the next
+    pub fn flush(&mut self) -> Result<Option<ParsedDocument>, ParseError> {
+        self.take(complete_prefix(&self.pending, true))
+    }
+
+    /// Parse whatever is left once the input has ended
+    pub fn finish(&mut self) -> Result<Option<ParsedDocument>, ParseError> {
+        if self.pending.trim().is_empty() {
+            return Ok(None);
+        }
+        let chunk = std::mem::take(&mut self.pending);
+        self.parse_chunk(&chunk).map(Some)
+    }
+
+    fn take(&mut self, end: usize) -> Result<Option<ParsedDocument>, ParseError> {
+        let end = self.front_matter_hold(end);
+        if end == 0 {
+            return Ok(None);
+        }
+        let chunk: String = self.pending.drain(..end).collect();
+        self.parse_chunk(&chunk).map(Some)
+    }
+
+    /// Length to parse given `end`, the complete blocks: nothing while front
+    /// matter opening the stream waits for its closing fence, and at least
+    /// the whole block once it has arrived
+    fn front_matter_hold(&self, end: usize) -> usize {
+        if self.metadata.is_some() || !opens_front_matter(&self.pending) {
+            return end;
+        }
+        front_matter_end(&self.pending).map_or(0, |block_end| end.max(block_end))
+    }
+
+    fn parse_chunk(&mut self, chunk: &str) -> Result<ParsedDocument, ParseError> {
+        // Front matter can only open the stream; a later `---` is a rule
+        let (metadata, body) = match &self.metadata {
+            Some(metadata) => (metadata.clone(), chunk),
+            None => {
+                let (metadata, body) = split_front_matter(chunk);
+                let metadata = metadata.unwrap_or_default();
+                self.metadata = Some(metadata.clone());
+                (metadata, body)
+            }
+        };
+        self.md_parser.parse_body(body, metadata, None)
+    }
+}
+
+impl Default for StreamParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Length of the leading text made of complete blocks: up to the last blank
+/// line that isn't inside a fenced code block, or the last line of plain
+/// text followed by a line that doesn't continue it. When `idle`, every
+/// complete line outside a fenced code block.
+fn complete_prefix(text: &str, idle: bool) -> usize {
+    let mut end = 0;
+    let mut offset = 0;
+    let mut fence: Option<(char, usize)> = None;
+    // A list, quote, table or footnote runs on until a blank line
+    let mut open_block = false;
+    // End of the previous line when it was plain text
+    let mut plain_end = None;
+
+    for line in text.split_inclusive('\n') {
+        if !line.ends_with('\n') {
+            break;
+        }
+        offset += line.len();
+        let trimmed = line.trim_start();
+        let marker = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'));
+        let mut fence_line = false;
+        if let Some(c) = marker {
+            let run = trimmed.chars().take_while(|&x| x == c).count();
+            if run >= 3 {
+                fence_line = true;
+                fence = match fence {
+                    None => Some((c, run)),
+                    Some((open, len))
+                        if open == c && run >= len && trimmed[run..].trim().is_empty() =>
+                    {
+                        None
+                    }
+                    open => open,
+                };
+            }
+        }
+        if fence.is_some() || fence_line {
+            // An opening fence ends the paragraph before it
+            if let Some(previous) = plain_end.take().filter(|_| fence_line) {
+                end = previous;
+            }
+            continue;
+        }
+        if line.trim().is_empty() {
+            end = offset;
+            open_block = false;
+            plain_end = None;
+            continue;
+        }
+        if let Some(previous) = plain_end.take() {
+            if !continues_paragraph(trimmed) {
+                end = previous;
+            }
+        }
+        open_block = open_block || opens_block(line);
+        if !open_block {
+            plain_end = Some(offset);
+        }
+        if idle {
+            end = offset;
+        }
+    }
+    end
+}
+
+/// True if `line` makes the paragraph before it a setext heading or a table
+/// header
+fn continues_paragraph(line: &str) -> bool {
+    let line = line.trim_end();
+    !line.is_empty()
+        && (line.chars().all(|c| c == '=')
+            || (line.contains('-')
+                && line
+                    .chars()
+                    .all(|c| matches!(c, '-' | '|' | ':' | ' ' | '\t'))))
+}
+
+/// True if `line` starts a block that can run on over several lines: a list
+/// item, quote, table row, footnote definition or indented code
+fn opens_block(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() >= 4 || line.starts_with('\t') {
+        return true;
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    let after_marker = match trimmed.as_bytes().first() {
+        Some(b'-' | b'*' | b'+') => &trimmed[1..],
+        Some(b'0'..=b'9') if matches!(trimmed.as_bytes().get(digits), Some(b'.' | b')')) => {
+            &trimmed[digits + 1..]
+        }
+        _ => return trimmed.starts_with(['>', '|']) || trimmed.starts_with("[^"),
+    };
+    after_marker.starts_with([' ', '\t', '\n'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_prefix_ends_at_blank_line() {
+        assert_eq!(complete_prefix("one\n\ntwo", false), 5);
+        assert_eq!(complete_prefix("partial line", false), 0);
+        assert_eq!(complete_prefix("partial line", true), 0);
+    }
+
+    #[test]
+    fn test_complete_prefix_ends_plain_lines_once_the_next_arrives() {
+        assert_eq!(complete_prefix("one\ntwo\n", false), 4);
+        assert_eq!(complete_prefix("one\ntwo\n", true), 8);
+        // The next line may turn the last into a heading or table header
+        assert_eq!(complete_prefix("Title\n=====\n", false), 0);
+        assert_eq!(complete_prefix("| a | b |\n|---|---|\n", false), 0);
+    }
+
+    #[test]
+    fn test_complete_prefix_waits_for_open_blocks() {
+        assert_eq!(complete_prefix("- one\n- two\n", false), 0);
+        assert_eq!(complete_prefix("> quote\nlazy\n", false), 0);
+        assert_eq!(complete_prefix("[^1]: note\n", false), 0);
+        assert_eq!(complete_prefix("- one\n- two\n", true), 12);
+    }
+
+    #[test]
+    fn test_complete_prefix_waits_for_closing_fence() {
+        let open = "intro\n\n```\ncode\n\nmore\n";
+        assert_eq!(complete_prefix(open, false), 7);
+        assert_eq!(complete_prefix(open, true), 7);
+        let closed = format!("{open}```\n\nafter");
+        assert_eq!(
+            complete_prefix(&closed, false),
+            closed.len() - "after".len()
+        );
+    }
+}
@@ -1,5 +1,7 @@
-use crate::types::{Section, Token};
-use std::path::Path;
+use crate::types::{CodeBlock, DocumentMetadata, Footnote, Section, SourceFile, Token, TokenStyle};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
 
 /// Trait for document parsers (enables future EPUB support)
 pub trait DocumentParser {
@@ -8,34 +10,305 @@ pub trait DocumentParser {
     /// # Errors
     ///
     /// Returns [`ParseError::IoError`] if the file cannot be read.
-    /// Returns [`ParseError::ParseError`] if the content is malformed.
+    /// Returns [`ParseError::Malformed`] if the content is malformed, or
+    /// another structured variant naming the file for other problems.
     fn parse_file(&self, path: &Path) -> Result<ParsedDocument, ParseError>;
 
     /// Parse document from string content.
     ///
     /// # Errors
     ///
-    /// Returns [`ParseError::ParseError`] if the content is malformed.
+    /// Returns [`ParseError::Malformed`] if the content is malformed, or
+    /// [`ParseError::UnsupportedFormat`] from parsers that don't read text,
+    /// such as [`EpubParser`](super::EpubParser).
     fn parse_str(&self, content: &str) -> Result<ParsedDocument, ParseError>;
+
+    /// Parse document from a reader, e.g. a blob loaded from a database.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ParseError::IoError`] if the reader fails.
+    /// Returns [`ParseError::Malformed`] or [`ParseError::Encoding`] if the
+    /// content is malformed or can't be decoded, and
+    /// [`ParseError::UnsupportedFormat`] from parsers for documents that
+    /// aren't a single file, such as [`BookParser`](super::BookParser) for
+    /// directories.
+    fn parse_reader<R: Read + Seek>(&self, reader: R) -> Result<ParsedDocument, ParseError>
+    where
+        Self: Sized;
+
+    /// Parse document from bytes in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`parse_reader`](Self::parse_reader).
+    fn parse_bytes(&self, bytes: &[u8]) -> Result<ParsedDocument, ParseError>
+    where
+        Self: Sized,
+    {
+        self.parse_reader(Cursor::new(bytes))
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ParsedDocument {
     pub tokens: Vec<Token>,
     pub sections: Vec<Section>,
+    pub code_blocks: Vec<CodeBlock>,
+    pub footnotes: Vec<Footnote>,
+    pub metadata: DocumentMetadata,
+    /// Link targets (heading slugs, `<a id>` anchors, EPUB chapters) by token position
+    pub anchors: HashMap<String, usize>,
+    /// File boundaries when the document was combined from several files
+    pub files: Vec<SourceFile>,
+    /// Problems that were worked around, such as skipped chapters
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl ParsedDocument {
+    /// Move every position `offset` tokens along, for a part that goes after
+    /// `offset` tokens of earlier content, and hand its footnotes over to
+    /// `notes`, renumbering the references to match. With `merge_labels` a
+    /// note whose label is already in `notes` is the same note: a reference
+    /// may arrive before its note, or the note before a later reference.
+    pub fn shift(&mut self, offset: usize, notes: &mut Vec<Footnote>, merge_labels: bool) {
+        let indices: Vec<usize> = std::mem::take(&mut self.footnotes)
+            .into_iter()
+            .map(|note| {
+                let existing = notes
+                    .iter()
+                    .position(|f| f.label == note.label)
+                    .filter(|_| merge_labels);
+                match existing {
+                    Some(index) => {
+                        if notes[index].tokens.is_empty() {
+                            notes[index].tokens = note.tokens;
+                        }
+                        index
+                    }
+                    None => {
+                        notes.push(note);
+                        notes.len() - 1
+                    }
+                }
+            })
+            .collect();
+        for token in &mut self.tokens {
+            if let TokenStyle::FootnoteRef(index) = token.style {
+                token.style = TokenStyle::FootnoteRef(indices.get(index).copied().unwrap_or(index));
+            }
+        }
+        for section in &mut self.sections {
+            section.token_start += offset;
+            section.token_end += offset;
+        }
+        for block in &mut self.code_blocks {
+            block.token_start += offset;
+            block.token_end += offset;
+        }
+        for position in self.anchors.values_mut() {
+            *position += offset;
+        }
+        for file in &mut self.files {
+            file.token_start += offset;
+            file.token_end += offset;
+        }
+    }
+
+    /// Add a part parsed on its own, e.g. the next chapter, to the end.
+    /// Anchors already defined keep their position.
+    pub fn append(&mut self, mut part: ParsedDocument) {
+        part.shift(self.tokens.len(), &mut self.footnotes, true);
+        self.tokens.append(&mut part.tokens);
+        self.sections.append(&mut part.sections);
+        self.code_blocks.append(&mut part.code_blocks);
+        for (name, position) in part.anchors {
+            self.anchors.entry(name).or_insert(position);
+        }
+        self.files.append(&mut part.files);
+        self.warnings.append(&mut part.warnings);
+    }
+}
+
+/// Where in a document a problem was found. Every part is optional, as
+/// parsers know different amounts about their input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    pub file: Option<PathBuf>,
+    /// Zero-based position of the chapter in the book
+    pub chapter_index: Option<usize>,
+    /// Chapter title, or its path inside the book without one
+    pub chapter: Option<String>,
+    /// One-based line within the file or chapter
+    pub line: Option<usize>,
+    /// One-based column within the line
+    pub column: Option<usize>,
+}
+
+impl Location {
+    /// Location of a whole file
+    #[must_use]
+    pub fn file(path: &Path) -> Self {
+        Self {
+            file: Some(path.to_path_buf()),
+            ..Self::default()
+        }
+    }
+
+    /// Location of chapter `index` of a book, named `name`
+    #[must_use]
+    pub fn chapter(index: usize, name: impl Into<String>) -> Self {
+        Self {
+            chapter_index: Some(index),
+            chapter: Some(name.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Whether nothing is known about the location
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(file) = &self.file {
+            parts.push(file.display().to_string());
+        }
+        match (self.chapter_index, &self.chapter) {
+            (Some(index), Some(name)) => parts.push(format!("chapter {} \"{name}\"", index + 1)),
+            (Some(index), None) => parts.push(format!("chapter {}", index + 1)),
+            (None, Some(name)) => parts.push(format!("chapter \"{name}\"")),
+            (None, None) => {}
+        }
+        if let Some(line) = self.line {
+            parts.push(format!("line {line}"));
+        }
+        if let Some(column) = self.column {
+            parts.push(format!("column {column}"));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// A problem that was worked around, such as a skipped chapter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    pub location: Location,
+    pub message: String,
+}
+
+impl ParseWarning {
+    #[must_use]
+    pub fn new(location: Location, message: impl Into<String>) -> Self {
+        Self {
+            location,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.location.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.location, self.message)
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum ParseError {
     IoError(std::io::Error),
-    ParseError(String),
+    /// Content that doesn't follow its format, such as broken XHTML
+    Malformed {
+        location: Box<Location>,
+        message: String,
+    },
+    /// Input that isn't a format the parser reads
+    UnsupportedFormat {
+        location: Box<Location>,
+        message: String,
+    },
+    /// Text that isn't in an encoding the parser can decode
+    Encoding {
+        location: Box<Location>,
+        message: String,
+    },
+    /// EPUB whose content is encrypted
+    DrmProtected {
+        location: Box<Location>,
+    },
+    /// Input without anything to read
+    EmptyDocument {
+        location: Box<Location>,
+    },
+}
+
+impl ParseError {
+    /// Where the problem was found, for errors that know
+    #[must_use]
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Self::IoError(_) => None,
+            Self::Malformed { location, .. }
+            | Self::UnsupportedFormat { location, .. }
+            | Self::Encoding { location, .. }
+            | Self::DrmProtected { location }
+            | Self::EmptyDocument { location } => Some(location.as_ref()),
+        }
+    }
+
+    /// Fill in the file the error was found in, if it doesn't name one
+    #[must_use]
+    pub fn in_file(mut self, path: &Path) -> Self {
+        match &mut self {
+            Self::IoError(_) => {}
+            Self::Malformed { location, .. }
+            | Self::UnsupportedFormat { location, .. }
+            | Self::Encoding { location, .. }
+            | Self::DrmProtected { location }
+            | Self::EmptyDocument { location } => {
+                location.file.get_or_insert_with(|| path.to_path_buf());
+            }
+        }
+        self
+    }
+
+    /// Process exit code for the kind of error, so scripts can tell them
+    /// apart. 1 is left for other failures and 2 for command line usage
+    /// errors.
+    #[must_use]
+    pub const fn exit_code(&self) -> i32 {
+        match self {
+            Self::IoError(_) => 3,
+            Self::Malformed { .. } => 4,
+            Self::UnsupportedFormat { .. } => 5,
+            Self::Encoding { .. } => 6,
+            Self::DrmProtected { .. } => 7,
+            Self::EmptyDocument { .. } => 8,
+        }
+    }
 }
 
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(location) = self.location().filter(|l| !l.is_empty()) {
+            write!(f, "{location}: ")?;
+        }
         match self {
             Self::IoError(e) => write!(f, "IO error: {e}"),
-            Self::ParseError(s) => write!(f, "Parse error: {s}"),
+            Self::Malformed { message, .. } => write!(f, "Malformed: {message}"),
+            Self::UnsupportedFormat { message, .. } => write!(f, "Unsupported format: {message}"),
+            Self::Encoding { message, .. } => write!(f, "Encoding error: {message}"),
+            Self::DrmProtected { .. } => {
+                write!(f, "DRM-protected: the book's text is encrypted")
+            }
+            Self::EmptyDocument { .. } => write!(f, "Empty document: nothing to read"),
         }
     }
 }
@@ -44,7 +317,7 @@ impl std::error::Error for ParseError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Self::IoError(e) => Some(e),
-            Self::ParseError(_) => None,
+            _ => None,
         }
     }
 }
@@ -0,0 +1,79 @@
+//! Helpers shared by reading and exporting EPUB chapters
+//!
+//! Both go through [`xhtml_tree`](super::xhtml_tree); these number notes
+//! across the book and resolve the links between chapters.
+
+use std::collections::HashMap;
+
+/// Characters commonly used for "back to text" links in notes
+pub(crate) const BACKLINK_CHARS: &[char] = &['↩', '↑', '⤴', '\u{fe0e}'];
+
+/// Book-wide footnote numbering, keyed by resolved `path#id` target.
+///
+/// References and notes may live in different chapters, so both sides look
+/// up the same target and get the same label.
+#[derive(Debug, Default)]
+pub(crate) struct NoteLabels(HashMap<String, usize>);
+
+impl NoteLabels {
+    /// Label for a note target, numbering new targets in order of appearance
+    pub(crate) fn label(&mut self, target: &str) -> usize {
+        let next = self.0.len() + 1;
+        *self.0.entry(target.to_string()).or_insert(next)
+    }
+}
+
+/// True if an `epub:type` or `role` attribute value marks the given note kind
+pub(crate) fn is_note_type(epub_type: Option<&str>, role: Option<&str>, kind: &str) -> bool {
+    let role_kind = format!("doc-{kind}");
+    epub_type.is_some_and(|v| v.split_whitespace().any(|t| t == kind))
+        || role.is_some_and(|v| v.split_whitespace().any(|t| t == role_kind))
+}
+
+/// Resolve a note link against the chapter it appears in, as `path#id`
+pub(crate) fn resolve_href(chapter_path: &str, href: &str) -> String {
+    let (file, fragment) = href.split_once('#').unwrap_or((href, ""));
+    if file.is_empty() {
+        return format!("{chapter_path}#{fragment}");
+    }
+
+    let mut parts: Vec<&str> = chapter_path.split('/').collect();
+    parts.pop(); // chapter file name
+    for part in file.split('/') {
+        match part {
+            ".." => {
+                parts.pop();
+            }
+            "." | "" => {}
+            part => parts.push(part),
+        }
+    }
+    format!("{}#{fragment}", parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_labels_are_shared_across_chapters() {
+        let mut labels = NoteLabels::default();
+        assert_eq!(labels.label("OEBPS/notes.xhtml#n2"), 1);
+        assert_eq!(labels.label("OEBPS/notes.xhtml#n1"), 2);
+        assert_eq!(labels.label("OEBPS/notes.xhtml#n2"), 1);
+    }
+
+    #[test]
+    fn test_is_note_type() {
+        assert!(is_note_type(Some("footnote"), None, "footnote"));
+        assert!(is_note_type(None, Some("doc-noteref x"), "noteref"));
+        assert!(!is_note_type(Some("footnotes"), Some("note"), "footnote"));
+    }
+
+    #[test]
+    fn test_resolve_href() {
+        assert_eq!(resolve_href("a/ch1.xhtml", "#x"), "a/ch1.xhtml#x");
+        assert_eq!(resolve_href("a/b/ch1.xhtml", "../n.xhtml#x"), "a/n.xhtml#x");
+        assert_eq!(resolve_href("ch1.xhtml", "n.xhtml#x"), "n.xhtml#x");
+    }
+}
@@ -0,0 +1,1011 @@
+//! EPUB chapters read into the same document tree as Markdown
+//!
+//! Chapter XHTML is parsed leniently into elements and converted to the
+//! markdown-it nodes the Markdown walker already understands: `<em>` becomes
+//! emphasis, `<h2>` a heading, `<li>` a list item and so on. Tokens then get
+//! exactly the styles, blocks and timing of the equivalent Markdown.
+
+use std::cell::RefCell;
+
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::states::RawKind;
+use html5ever::tokenizer::{
+    BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use markdown_it::parser::core::Root;
+use markdown_it::parser::inline::Text;
+use markdown_it::plugins::cmark::block::{
+    blockquote::Blockquote,
+    fence::CodeFence,
+    heading::ATXHeading,
+    list::{BulletList, ListItem, OrderedList},
+    paragraph::Paragraph,
+};
+use markdown_it::plugins::cmark::inline::{
+    backticks::CodeInline,
+    emphasis::{Em, Strong},
+    image::Image,
+    link::Link,
+};
+use markdown_it::plugins::extra::strikethrough::Strikethrough;
+use markdown_it::plugins::extra::tables::{Table, TableCell, TableRow};
+use markdown_it::plugins::html::html_inline::HtmlInline;
+use markdown_it::Node;
+
+use crate::parser::footnote::{FootnoteDefinition, FootnoteReference};
+use crate::parser::xhtml::{is_note_type, resolve_href, NoteLabels, BACKLINK_CHARS};
+
+/// Elements that start a block of their own
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+/// Elements that never contain content to read
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "head",
+    "script",
+    "style",
+    "svg",
+    "math",
+    "title",
+    // Error report left in a chapter saved from a browser that failed to parse it
+    "parsererror",
+];
+
+/// Elements without a closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "wbr",
+];
+
+/// A parsed XHTML element or run of text
+#[derive(Debug, Clone, PartialEq)]
+enum XNode {
+    Text(String),
+    Element(Element),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Element {
+    /// Lowercase tag name
+    name: String,
+    /// Attribute names, prefix included (`epub:type`), and decoded values
+    attrs: Vec<(String, String)>,
+    children: Vec<XNode>,
+    /// Whether the element or anything inside it is a block, set as
+    /// children are added
+    block: bool,
+}
+
+impl Element {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            attrs: Vec::new(),
+            children: Vec::new(),
+            block: BLOCK_ELEMENTS.contains(&name),
+        }
+    }
+
+    fn push(&mut self, child: Self) {
+        self.block |= child.block;
+        self.children.push(XNode::Element(child));
+    }
+
+    fn attr(&self, name: &str) -> Option<String> {
+        self.attr_str(name).map(str::to_string)
+    }
+
+    fn attr_str(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(attr, _)| attr == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    const fn is_block(&self) -> bool {
+        self.block
+    }
+
+    /// True if `epub:type` or `role` marks the element as `kind`
+    fn has_type(&self, kind: &str) -> bool {
+        is_note_type(self.attr_str("epub:type"), self.attr_str("role"), kind)
+    }
+
+    fn is_note(&self) -> bool {
+        self.has_type("footnote") || self.has_type("endnote") || self.has_type("rearnote")
+    }
+
+    /// Text of the element and its descendants
+    fn text(&self) -> String {
+        let mut out = String::new();
+        for child in &self.children {
+            match child {
+                XNode::Text(text) => out.push_str(text),
+                XNode::Element(el) => out.push_str(&el.text()),
+            }
+        }
+        out
+    }
+}
+
+impl XNode {
+    fn is_block(&self) -> bool {
+        matches!(self, Self::Element(el) if el.is_block())
+    }
+}
+
+/// Parse XHTML leniently: unknown end tags are ignored and unclosed
+/// elements end with their parent.
+///
+/// html5ever tokenizes, decoding entities and attributes, but elements are
+/// assembled here: its HTML tree builder ignores XHTML's `/>`, so an empty
+/// `<a id="p1"/>` would wrap, and keep reopening around, the rest of the
+/// chapter.
+fn parse(xhtml: &str) -> Vec<XNode> {
+    let tokenizer = Tokenizer::new(TreeBuilder::default(), TokenizerOpts::default());
+    let input = BufferQueue::default();
+    input.push_back(StrTendril::from_slice(xhtml));
+    // Only scripts pause the tokenizer, and the builder never asks to run one
+    let _ = tokenizer.feed(&input);
+    tokenizer.end();
+
+    let mut stack = tokenizer.sink.stack.take();
+    while stack.len() > 1 {
+        close_element(&mut stack);
+    }
+    stack.pop().map(|root| root.children).unwrap_or_default()
+}
+
+/// Builds elements from html5ever tokens
+struct TreeBuilder {
+    /// Open elements; the bottom entry collects top-level nodes
+    stack: RefCell<Vec<Element>>,
+}
+
+impl Default for TreeBuilder {
+    fn default() -> Self {
+        Self {
+            stack: RefCell::new(vec![Element::new("")]),
+        }
+    }
+}
+
+impl TokenSink for TreeBuilder {
+    type Handle = ();
+
+    fn process_token(&self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        let mut stack = self.stack.borrow_mut();
+        match token {
+            Token::CharacterTokens(text) => push_text(&mut stack, &text),
+            Token::TagToken(tag) if tag.kind == TagKind::StartTag => {
+                let mut element = Element::new(&tag.name);
+                element.attrs = tag
+                    .attrs
+                    .iter()
+                    .map(|attr| (attr.name.local.to_string(), attr.value.to_string()))
+                    .collect();
+                if tag.self_closing || VOID_ELEMENTS.contains(&element.name.as_str()) {
+                    if let Some(parent) = stack.last_mut() {
+                        parent.push(element);
+                    }
+                } else {
+                    // Text of these is read verbatim up to the closing tag
+                    let raw = match element.name.as_str() {
+                        "script" => Some(RawKind::ScriptData),
+                        "style" => Some(RawKind::Rawtext),
+                        "title" | "textarea" => Some(RawKind::Rcdata),
+                        _ => None,
+                    };
+                    stack.push(element);
+                    if let Some(raw) = raw {
+                        return TokenSinkResult::RawData(raw);
+                    }
+                }
+            }
+            Token::TagToken(tag) => {
+                if let Some(depth) = stack.iter().skip(1).rposition(|el| *el.name == *tag.name) {
+                    while stack.len() > depth + 1 {
+                        close_element(&mut stack);
+                    }
+                }
+            }
+            _ => {}
+        }
+        TokenSinkResult::Continue
+    }
+
+    /// CDATA sections are text in XHTML, not comments as in HTML
+    fn adjusted_current_node_present_but_not_in_html_namespace(&self) -> bool {
+        true
+    }
+}
+
+/// Add text to the open element, joined to text just before it
+fn push_text(stack: &mut [Element], text: &str) {
+    let Some(parent) = stack.last_mut() else {
+        return;
+    };
+    if let Some(XNode::Text(last)) = parent.children.last_mut() {
+        last.push_str(text);
+    } else if !text.is_empty() {
+        parent.children.push(XNode::Text(text.to_string()));
+    }
+}
+
+fn close_element(stack: &mut Vec<Element>) {
+    if let Some(element) = stack.pop() {
+        if let Some(parent) = stack.last_mut() {
+            parent.push(element);
+        }
+    }
+}
+
+/// Root node for a book assembled from chapters
+pub(crate) fn root() -> Node {
+    Node::new(Root::new(String::new()))
+}
+
+/// Link target at the current position, found by [`find_anchor`](crate::links::find_anchor)
+pub(crate) fn anchor(id: &str) -> Node {
+    Node::new(HtmlInline {
+        content: format!("<a id=\"{}\">", id.replace('"', "&quot;")),
+    })
+}
+
+/// The `path#id` target an [`anchor`] node marks
+pub(crate) fn anchor_target(node: &Node) -> Option<String> {
+    let content = &node.cast::<HtmlInline>()?.content;
+    let id = content.strip_prefix("<a id=\"")?.strip_suffix("\">")?;
+    Some(id.replace("&quot;", "\""))
+}
+
+/// `Algorithm` of each `EncryptionMethod` in an EPUB's
+/// `META-INF/encryption.xml`, `None` where it is missing
+pub(crate) fn encryption_methods(xml: &str) -> Vec<Option<String>> {
+    fn collect(nodes: &[XNode], out: &mut Vec<Option<String>>) {
+        for node in nodes {
+            if let XNode::Element(el) = node {
+                // Tag names keep their namespace prefix, such as `enc:`
+                if el.name.rsplit(':').next() == Some("encryptionmethod") {
+                    out.push(el.attr("algorithm"));
+                }
+                collect(&el.children, out);
+            }
+        }
+    }
+    let mut methods = Vec::new();
+    collect(&parse(xml), &mut methods);
+    methods
+}
+
+/// Heading with plain text
+pub(crate) fn heading(level: u8, title: &str) -> Node {
+    let mut node = Node::new(ATXHeading { level });
+    node.children.push(text(title));
+    node
+}
+
+/// True if chapter nodes open with a heading reading `title`, ignoring
+/// anchors, case and spacing
+pub(crate) fn opens_with_heading(nodes: &[Node], title: &str) -> bool {
+    let words = |s: &str| {
+        s.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+    nodes
+        .iter()
+        .find(|n| !n.is::<HtmlInline>())
+        .is_some_and(|n| n.is::<ATXHeading>() && words(&n.collect_text()) == words(title))
+}
+
+fn text(content: &str) -> Node {
+    Node::new(Text {
+        content: content.to_string(),
+    })
+}
+
+/// Convert a chapter's XHTML into document nodes.
+///
+/// `chapter_path` is the chapter's path inside the book: element ids become
+/// anchors `path#id`, and note links are resolved against it. `labels`
+/// numbers notes across the book.
+pub(crate) fn chapter_nodes(xhtml: &str, chapter_path: &str, labels: &mut NoteLabels) -> Vec<Node> {
+    let nodes = parse(xhtml);
+    // Only the body is read when there is one; its id marks the chapter start
+    let body = find_element(&nodes, "body");
+    let mut converter = Converter {
+        chapter_path,
+        labels,
+    };
+    let mut out: Vec<Node> = body
+        .and_then(|b| converter.id_anchor(b))
+        .into_iter()
+        .collect();
+    out.extend(converter.blocks(body.map_or(nodes.as_slice(), |b| b.children.as_slice())));
+    out
+}
+
+/// True if a chapter's body has any text or images
+pub(crate) fn has_content(xhtml: &str) -> bool {
+    fn any_content(nodes: &[XNode]) -> bool {
+        nodes.iter().any(|node| match node {
+            XNode::Text(text) => !text.trim().is_empty(),
+            XNode::Element(el) => {
+                el.name == "img"
+                    || (!SKIPPED_ELEMENTS.contains(&el.name.as_str()) && any_content(&el.children))
+            }
+        })
+    }
+    let nodes = parse(xhtml);
+    let body = find_element(&nodes, "body").map_or(nodes.as_slice(), |b| b.children.as_slice());
+    any_content(body)
+}
+
+fn find_element<'a>(nodes: &'a [XNode], name: &str) -> Option<&'a Element> {
+    nodes.iter().find_map(|node| match node {
+        XNode::Element(el) if el.name == name => Some(el),
+        XNode::Element(el) => find_element(&el.children, name),
+        XNode::Text(_) => None,
+    })
+}
+
+/// An entry of a book's table of contents, flattened in reading order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TocEntry {
+    pub(crate) label: String,
+    /// Book path the entry points to, with the fragment if any
+    pub(crate) target: String,
+    pub(crate) level: u8,
+}
+
+/// Table of contents of an EPUB 3 navigation document at `nav_path`
+pub(crate) fn nav_entries(xhtml: &str, nav_path: &str) -> Vec<TocEntry> {
+    let nodes = parse(xhtml);
+    let mut navs = Vec::new();
+    find_elements(&nodes, "nav", &mut navs);
+    let Some(nav) = navs
+        .iter()
+        .find(|nav| nav.has_type("toc"))
+        .or_else(|| navs.first())
+    else {
+        return Vec::new();
+    };
+
+    let mut entries = Vec::new();
+    for child in &nav.children {
+        if let XNode::Element(list) = child {
+            if list.name == "ol" || list.name == "ul" {
+                nav_list(list, nav_path, 1, &mut entries);
+            }
+        }
+    }
+    entries
+}
+
+fn nav_list(list: &Element, nav_path: &str, level: u8, entries: &mut Vec<TocEntry>) {
+    for item in &list.children {
+        let XNode::Element(item) = item else {
+            continue;
+        };
+        if item.name != "li" {
+            continue;
+        }
+        for child in &item.children {
+            let XNode::Element(child) = child else {
+                continue;
+            };
+            match child.name.as_str() {
+                // Unlinked headings only group their children
+                "a" => {
+                    let label = child
+                        .text()
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    if let Some(href) = child.attr("href").filter(|_| !label.is_empty()) {
+                        entries.push(TocEntry {
+                            label,
+                            target: toc_target(nav_path, &href),
+                            level,
+                        });
+                    }
+                }
+                "ol" | "ul" => nav_list(child, nav_path, level.saturating_add(1), entries),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Normalized `path#fragment` for a link in the file at `base`, without a
+/// trailing `#` when there is no fragment
+pub(crate) fn toc_target(base: &str, href: &str) -> String {
+    resolve_href(base, href).trim_end_matches('#').to_string()
+}
+
+fn find_elements<'a>(nodes: &'a [XNode], name: &str, out: &mut Vec<&'a Element>) {
+    for node in nodes {
+        if let XNode::Element(el) = node {
+            if el.name == name {
+                out.push(el);
+            } else {
+                find_elements(&el.children, name, out);
+            }
+        }
+    }
+}
+
+struct Converter<'a> {
+    chapter_path: &'a str,
+    labels: &'a mut NoteLabels,
+}
+
+impl Converter<'_> {
+    /// Block nodes for mixed content, wrapping runs of inline content in
+    /// paragraphs
+    fn blocks(&mut self, nodes: &[XNode]) -> Vec<Node> {
+        let mut out = Vec::new();
+        let mut run: Vec<&XNode> = Vec::new();
+        for node in nodes {
+            match node {
+                XNode::Element(el) if el.is_block() || el.is_note() => {
+                    self.flush_paragraph(&mut run, &mut out);
+                    out.extend(self.block(el));
+                }
+                _ => run.push(node),
+            }
+        }
+        self.flush_paragraph(&mut run, &mut out);
+        out
+    }
+
+    fn flush_paragraph(&mut self, run: &mut Vec<&XNode>, out: &mut Vec<Node>) {
+        let mut inline = Vec::new();
+        for node in run.drain(..) {
+            self.inline(node, &mut inline);
+        }
+        let has_content = inline.iter().any(|n| {
+            !n.is::<HtmlInline>()
+                && n.cast::<Text>()
+                    .is_none_or(|t| !t.content.trim().is_empty())
+        });
+        if has_content {
+            let mut paragraph = Node::new(Paragraph);
+            paragraph.children = inline;
+            out.push(paragraph);
+        } else {
+            // Anchors without text still mark their place
+            out.extend(inline.into_iter().filter(|n| n.is::<HtmlInline>()));
+        }
+    }
+
+    /// Anchor for an element's id, if it has one
+    fn id_anchor(&self, el: &Element) -> Option<Node> {
+        el.attr("id")
+            .or_else(|| (el.name == "a").then(|| el.attr("name")).flatten())
+            .map(|id| anchor(&format!("{}#{id}", self.chapter_path)))
+    }
+
+    fn block(&mut self, el: &Element) -> Vec<Node> {
+        if SKIPPED_ELEMENTS.contains(&el.name.as_str()) {
+            return Vec::new();
+        }
+        if el.is_note() {
+            return self.note(el).into_iter().collect();
+        }
+
+        let mut out: Vec<Node> = self.id_anchor(el).into_iter().collect();
+        match el.name.as_str() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = el.name[1..].parse().unwrap_or(1);
+                let mut node = Node::new(ATXHeading { level });
+                node.children = self.inline_children(el);
+                out.push(node);
+            }
+            "p" | "dt" | "summary" if !el.children.iter().any(XNode::is_block) => {
+                let mut node = Node::new(Paragraph);
+                node.children = self.inline_children(el);
+                if !node.collect_text().trim().is_empty() || has_figure(&node) {
+                    out.push(node);
+                }
+            }
+            "blockquote" => {
+                let mut node = Node::new(Blockquote);
+                node.children = self.blocks(&el.children);
+                out.push(node);
+            }
+            "ul" | "ol" => out.push(self.list(el)),
+            "li" => out.push(self.list_item(el)),
+            "table" => out.extend(self.table(el)),
+            "pre" => out.push(code_fence(el)),
+            "figcaption" => {
+                let caption = el.text().split_whitespace().collect::<Vec<_>>().join(" ");
+                if !caption.is_empty() {
+                    out.push(figure(&caption, "", None));
+                }
+            }
+            "hr" => {}
+            _ => out.extend(self.blocks(&el.children)),
+        }
+        out
+    }
+
+    fn list(&mut self, el: &Element) -> Node {
+        let mut node = if el.name == "ol" {
+            let start = el.attr("start").and_then(|s| s.parse().ok()).unwrap_or(1);
+            Node::new(OrderedList { start, marker: '.' })
+        } else {
+            Node::new(BulletList { marker: '-' })
+        };
+        for child in &el.children {
+            match child {
+                XNode::Element(item) if item.name == "li" => {
+                    node.children.extend(self.id_anchor(item));
+                    node.children.push(self.list_item(item));
+                }
+                XNode::Element(other) => node.children.extend(self.block(other)),
+                XNode::Text(_) => {}
+            }
+        }
+        node
+    }
+
+    /// List item; items with only inline content are tight, as in Markdown
+    fn list_item(&mut self, el: &Element) -> Node {
+        let mut node = Node::new(ListItem);
+        node.children = if el.children.iter().any(XNode::is_block) {
+            self.blocks(&el.children)
+        } else {
+            self.inline_children(el)
+        };
+        node
+    }
+
+    /// Table rows from `<thead>`, `<tbody>`, `<tfoot>` and direct `<tr>`,
+    /// with the caption read first
+    fn table(&mut self, el: &Element) -> Vec<Node> {
+        let mut out = Vec::new();
+        let mut table = Node::new(Table {
+            alignments: Vec::new(),
+        });
+        let mut rows: Vec<&Element> = Vec::new();
+        for child in &el.children {
+            let XNode::Element(child) = child else {
+                continue;
+            };
+            match child.name.as_str() {
+                "caption" => out.extend(self.blocks(&child.children)),
+                "tr" => rows.push(child),
+                "thead" | "tbody" | "tfoot" => {
+                    rows.extend(child.children.iter().filter_map(|row| match row {
+                        XNode::Element(row) if row.name == "tr" => Some(row),
+                        _ => None,
+                    }))
+                }
+                _ => {}
+            }
+        }
+        for row in rows {
+            let mut row_node = Node::new(TableRow);
+            for cell in &row.children {
+                if let XNode::Element(cell) = cell {
+                    if cell.name == "td" || cell.name == "th" {
+                        let mut cell_node = Node::new(TableCell);
+                        cell_node.children = self.inline_children(cell);
+                        row_node.children.push(cell_node);
+                    }
+                }
+            }
+            if !row_node.children.is_empty() {
+                table.children.push(row_node);
+            }
+        }
+        if !table.children.is_empty() {
+            out.push(table);
+        }
+        out
+    }
+
+    /// Footnote or endnote body, read on demand instead of inline
+    fn note(&mut self, el: &Element) -> Option<Node> {
+        let id = el.attr("id")?;
+        let label = self.labels.label(&format!("{}#{id}", self.chapter_path));
+        let mut node = Node::new(FootnoteDefinition {
+            label: label.to_string(),
+        });
+        node.children = self.blocks(&el.children);
+        (!node.collect_text().trim().is_empty()).then_some(node)
+    }
+
+    fn inline_children(&mut self, el: &Element) -> Vec<Node> {
+        let mut out = Vec::new();
+        for child in &el.children {
+            self.inline(child, &mut out);
+        }
+        out
+    }
+
+    /// Append inline nodes for `node`, merging adjacent text so words split
+    /// across `<span>`s stay whole
+    fn inline(&mut self, node: &XNode, out: &mut Vec<Node>) {
+        let el = match node {
+            XNode::Text(content) => {
+                let content = content.replace(BACKLINK_CHARS, "");
+                push_inline_text(out, &content);
+                return;
+            }
+            XNode::Element(el) => el,
+        };
+        if SKIPPED_ELEMENTS.contains(&el.name.as_str()) {
+            return;
+        }
+        if el.is_note() {
+            out.extend(self.note(el));
+            return;
+        }
+        out.extend(self.id_anchor(el));
+
+        let styled = match el.name.as_str() {
+            "a" if el.has_type("noteref") => {
+                if let Some(href) = el.attr("href") {
+                    let label = self.labels.label(&resolve_href(self.chapter_path, &href));
+                    out.push(Node::new(FootnoteReference {
+                        label: label.to_string(),
+                    }));
+                }
+                return;
+            }
+            // "Back to text" links at the end of notes aren't read
+            "a" if is_backlink(&el.text()) => return,
+            "a" => match el.attr("href") {
+                Some(url) => Node::new(Link {
+                    url,
+                    title: el.attr("title"),
+                }),
+                None => {
+                    self.inline_transparent(el, out);
+                    return;
+                }
+            },
+            "em" | "i" | "cite" | "dfn" | "var" => Node::new(Em { marker: '*' }),
+            "strong" | "b" => Node::new(Strong { marker: '*' }),
+            "s" | "del" | "strike" => Node::new(Strikethrough { marker: '~' }),
+            "code" | "kbd" | "samp" | "tt" => {
+                let mut code = Node::new(CodeInline {
+                    marker: '`',
+                    marker_len: 1,
+                });
+                code.children.push(text(el.text().trim()));
+                out.push(code);
+                return;
+            }
+            "img" => {
+                let alt = el.attr("alt").unwrap_or_default();
+                let src = el.attr("src").unwrap_or_default();
+                out.push(figure(&alt, &src, el.attr("title")));
+                return;
+            }
+            "br" => {
+                push_inline_text(out, " ");
+                return;
+            }
+            _ => {
+                // Blocks inside table cells and similar read as separate words
+                let is_block = el.is_block();
+                if is_block {
+                    push_inline_text(out, " ");
+                }
+                self.inline_transparent(el, out);
+                if is_block {
+                    push_inline_text(out, " ");
+                }
+                return;
+            }
+        };
+        let mut styled = styled;
+        styled.children = self.inline_children(el);
+        out.push(styled);
+    }
+
+    fn inline_transparent(&mut self, el: &Element, out: &mut Vec<Node>) {
+        for child in &el.children {
+            self.inline(child, out);
+        }
+    }
+}
+
+fn is_backlink(text: &str) -> bool {
+    let text = text.trim();
+    !text.is_empty() && text.chars().all(|c| BACKLINK_CHARS.contains(&c))
+}
+
+fn push_inline_text(out: &mut Vec<Node>, content: &str) {
+    if content.is_empty() {
+        return;
+    }
+    if let Some(last) = out.last_mut().and_then(|n| n.cast_mut::<Text>()) {
+        last.content.push_str(content);
+    } else {
+        out.push(text(content));
+    }
+}
+
+/// Image read as its alt text (children) and title
+fn figure(alt: &str, url: &str, title: Option<String>) -> Node {
+    let mut node = Node::new(Image {
+        url: url.to_string(),
+        title: title.filter(|t| !t.trim().is_empty()),
+    });
+    if !alt.is_empty() {
+        node.children.push(text(alt));
+    }
+    node
+}
+
+fn has_figure(node: &Node) -> bool {
+    node.children.iter().any(|n| n.is::<Image>())
+}
+
+/// Preformatted text as a code block, with the language from a
+/// `language-*` class on `<pre>` or its `<code>`
+fn code_fence(el: &Element) -> Node {
+    let language = std::iter::once(el)
+        .chain(el.children.iter().filter_map(|c| match c {
+            XNode::Element(code) if code.name == "code" => Some(code),
+            _ => None,
+        }))
+        .filter_map(|e| e.attr("class"))
+        .flat_map(|class| {
+            class
+                .split_whitespace()
+                .filter_map(|c| {
+                    c.strip_prefix("language-")
+                        .or_else(|| c.strip_prefix("lang-"))
+                })
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .next();
+    Node::new(CodeFence {
+        info: language.unwrap_or_default(),
+        marker: '`',
+        marker_len: 3,
+        content: el.text().trim_start_matches('\n').to_string(),
+        lang_prefix: "language-",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{DocumentParser, MarkdownParser, ParsedDocument};
+    use crate::types::{BlockContext, DocumentMetadata, TokenStyle};
+
+    fn read(xhtml: &str) -> ParsedDocument {
+        let mut root = root();
+        root.children.extend(chapter_nodes(
+            xhtml,
+            "OEBPS/ch1.xhtml",
+            &mut NoteLabels::default(),
+        ));
+        MarkdownParser::new().parse_tree(&root, DocumentMetadata::default())
+    }
+
+    /// Words, styles and blocks, for comparing with the equivalent Markdown
+    fn summary(doc: &ParsedDocument) -> Vec<(String, TokenStyle, BlockContext)> {
+        doc.tokens
+            .iter()
+            .map(|t| (t.word.clone(), t.style.clone(), t.block.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_equivalent_markdown() {
+        let xhtml = "<html><head><title>Ignored</title></head><body>\
+                     <h2>The <em>Start</em></h2>\
+                     <p>Some <i>very</i> <strong>bold <em>and</em></strong> <code>x = 1</code> \
+                     <a href=\"https://example.com\">link</a> <del>gone</del>.</p>\
+                     <blockquote><p>Quoted words.</p></blockquote>\
+                     <ul><li>One</li><li>Two <b>items</b></li></ul>\
+                     <ol start=\"3\"><li>Third</li></ol>\
+                     </body></html>";
+        let markdown = "## The *Start*\n\n\
+                        Some *very* **bold *and*** `x = 1` [link](https://example.com) ~~gone~~.\n\n\
+                        > Quoted words.\n\n\
+                        - One\n- Two **items**\n\n\
+                        3. Third\n";
+        let expected = MarkdownParser::new().parse_str(markdown).unwrap();
+        let doc = read(xhtml);
+        assert_eq!(summary(&doc), summary(&expected));
+        assert_eq!(doc.sections.len(), 1);
+        assert_eq!(doc.sections[0].title, "The Start");
+        assert_eq!(doc.sections[0].level, 2);
+    }
+
+    #[test]
+    fn test_tables_and_code_blocks() {
+        let xhtml = "<table><thead><tr><th>Name</th><th>Age</th></tr></thead>\
+                     <tbody><tr><td>Ann</td><td><p>42</p></td></tr></tbody></table>\
+                     <pre><code class=\"language-rust\">fn main() {}\n</code></pre>";
+        let doc = read(xhtml);
+        let expected = MarkdownParser::new()
+            .parse_str("| Name | Age |\n|---|---|\n| Ann | 42 |\n")
+            .unwrap();
+        assert_eq!(summary(&doc)[..4], summary(&expected)[..]);
+        assert_eq!(doc.code_blocks.len(), 1);
+        assert_eq!(doc.code_blocks[0].language.as_deref(), Some("rust"));
+        assert_eq!(doc.code_blocks[0].content, "fn main() {}");
+    }
+
+    #[test]
+    fn test_words_split_across_spans_stay_whole() {
+        let doc = read("<p><span>Un</span>broken&nbsp;text<br/>next</p>");
+        let words: Vec<&str> = doc.tokens.iter().map(|t| t.word.as_str()).collect();
+        assert_eq!(words, vec!["Unbroken", "text", "next"]);
+    }
+
+    #[test]
+    fn test_notes_figures_and_anchors() {
+        let xhtml = "<p id=\"start\">A claim<a epub:type=\"noteref\" href=\"#fn1\">1</a> stands.</p>\
+                     <figure><img src=\"a.png\" alt=\"Chart\"/><figcaption>Sales</figcaption></figure>\
+                     <aside epub:type=\"footnote\" id=\"fn1\"><p><a href=\"#start\">↩</a> Cited source.</p></aside>";
+        let doc = read(xhtml);
+        let words: Vec<&str> = doc.tokens.iter().map(|t| t.word.as_str()).collect();
+        assert_eq!(
+            words,
+            vec!["A", "claim", "[1]", "stands.", "Chart", "Sales"]
+        );
+        assert_eq!(doc.tokens[2].style, TokenStyle::FootnoteRef(0));
+        assert_eq!(doc.tokens[4].block, BlockContext::Figure);
+        assert_eq!(doc.footnotes.len(), 1);
+        assert_eq!(doc.footnotes[0].text(), "Cited source.");
+        assert_eq!(doc.anchors.get("OEBPS/ch1.xhtml#start"), Some(&0));
+
+        let doc = read("<html><body id=\"c1\"><p>Body</p></body></html>");
+        assert_eq!(doc.anchors.get("OEBPS/ch1.xhtml#c1"), Some(&0));
+    }
+
+    #[test]
+    fn test_parse_closes_unclosed_elements() {
+        let nodes = parse("<p>one<p>two</div>three");
+        assert_eq!(nodes.len(), 1);
+        let XNode::Element(p) = &nodes[0] else {
+            panic!("expected element");
+        };
+        assert_eq!(p.name, "p");
+        assert_eq!(p.text(), "onetwothree");
+    }
+
+    #[test]
+    fn test_parse_skips_comments_and_keeps_cdata() {
+        let nodes =
+            parse("<?xml version=\"1.0\"?><!DOCTYPE html><p>a<!-- b -->c<![CDATA[<d>]]></p>");
+        let XNode::Element(p) = &nodes[0] else {
+            panic!("expected element");
+        };
+        assert_eq!(p.text(), "ac<d>");
+    }
+
+    #[test]
+    fn test_parse_self_closing_tags_and_quoted_angle_brackets() {
+        let nodes = parse("<p><a id=\"p1\"/>text <img alt=\"a > b\"/>rest</p>");
+        let XNode::Element(p) = &nodes[0] else {
+            panic!("expected element");
+        };
+        let names: Vec<&str> = p
+            .children
+            .iter()
+            .map(|child| match child {
+                XNode::Element(el) => el.name.as_str(),
+                XNode::Text(text) => text.as_str(),
+            })
+            .collect();
+        assert_eq!(names, vec!["a", "text ", "img", "rest"]);
+        let XNode::Element(img) = &p.children[2] else {
+            panic!("expected element");
+        };
+        assert_eq!(img.attr("alt").as_deref(), Some("a > b"));
+    }
+
+    #[test]
+    fn test_parse_keeps_bare_less_than() {
+        let nodes = parse("<p>a < b</p>");
+        let XNode::Element(p) = &nodes[0] else {
+            panic!("expected element");
+        };
+        assert_eq!(p.text(), "a < b");
+    }
+
+    #[test]
+    fn test_nav_entries_are_nested() {
+        let nav = "<html><body>\
+                   <nav epub:type=\"landmarks\"><ol><li><a href=\"cover.xhtml\">Cover</a></li></ol></nav>\
+                   <nav epub:type=\"toc\"><h1>Contents</h1><ol>\
+                   <li><a href=\"ch1.xhtml\">One</a><ol>\
+                   <li><a href=\"ch1.xhtml#s1\">One <em>A</em></a>\
+                   <ol><li><a href=\"../Text/ch1.xhtml#s1a\">Deep</a></li></ol></li></ol></li>\
+                   <li><span>Part</span><ol><li><a href=\"ch2.xhtml\">Two</a></li></ol></li>\
+                   </ol></nav></body></html>";
+        let entry = |label: &str, target: &str, level| TocEntry {
+            label: label.to_string(),
+            target: target.to_string(),
+            level,
+        };
+        assert_eq!(
+            nav_entries(nav, "OEBPS/Text/nav.xhtml"),
+            vec![
+                entry("One", "OEBPS/Text/ch1.xhtml", 1),
+                entry("One A", "OEBPS/Text/ch1.xhtml#s1", 2),
+                entry("Deep", "OEBPS/Text/ch1.xhtml#s1a", 3),
+                entry("Two", "OEBPS/Text/ch2.xhtml", 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_has_content() {
+        assert!(has_content("<html><body><p>Hi</p></body></html>"));
+        assert!(has_content(
+            "<body><div><img src=\"cover.jpg\"/></div></body>"
+        ));
+        assert!(!has_content(
+            "<html><head><title>Cover</title></head><body> <div>&#160;</div>\n</body></html>"
+        ));
+        assert!(!has_content("<body><style>p {}</style></body>"));
+    }
+
+    #[test]
+    fn test_parse_decodes_entities() {
+        let nodes = parse(
+            "<p title=\"Tom &amp; Jerry\">Tom &amp; Jerry&#8217;s &#x2014; &bogus; & more&nbsp;\
+             caf&eacute; &mdash; &hellip;&rsquo; &frac12;&times;&euro;</p>",
+        );
+        let XNode::Element(p) = &nodes[0] else {
+            panic!("expected element");
+        };
+        assert_eq!(p.attr("title").as_deref(), Some("Tom & Jerry"));
+        assert_eq!(
+            p.text(),
+            "Tom & Jerry’s — &bogus; & more\u{a0}café — …’ ½×€"
+        );
+    }
+}
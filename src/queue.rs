@@ -0,0 +1,88 @@
+//! Reading queue: several documents read back to back
+//!
+//! Documents in the queue are only parsed when they are opened, so entries
+//! learn their title and length the first time they are read.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::types::ReadingProgress;
+
+/// A document in the reading queue and how far it has been read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueEntry {
+    pub path: PathBuf,
+    /// Document title, known once the document has been opened
+    pub title: Option<String>,
+    /// Token to resume reading from
+    pub position: usize,
+    /// Number of tokens, known once the document has been opened
+    pub token_count: Option<usize>,
+    /// Why the document couldn't be opened; such entries are skipped
+    pub error: Option<String>,
+}
+
+impl QueueEntry {
+    #[must_use]
+    pub const fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            title: None,
+            position: 0,
+            token_count: None,
+            error: None,
+        }
+    }
+
+    /// Title if known, otherwise the file name
+    #[must_use]
+    pub fn label(&self) -> String {
+        self.title.clone().unwrap_or_else(|| {
+            self.path
+                .file_name()
+                .unwrap_or(self.path.as_os_str())
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+
+    /// How far the document has been read, once it has been opened
+    #[must_use]
+    pub fn reading(&self) -> Option<ReadingProgress> {
+        self.token_count.map(|token_count| ReadingProgress {
+            position: self.position,
+            token_count,
+        })
+    }
+
+    /// Fraction read (0.0-1.0); unopened documents haven't been started
+    #[must_use]
+    pub fn progress(&self) -> f64 {
+        self.reading().map_or(0.0, ReadingProgress::fraction)
+    }
+
+    /// True once the last word has been reached
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.reading().is_some_and(ReadingProgress::is_finished)
+    }
+}
+
+/// Read a queue file: one path per line, relative to the queue file.
+/// Blank lines and lines starting with `#` are ignored.
+pub fn read_queue_file(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let content = std::fs::read_to_string(path)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    Ok(parse_queue(&content, base))
+}
+
+/// Paths listed in queue file content, resolved against `base`
+#[must_use]
+pub fn parse_queue(content: &str, base: &Path) -> Vec<PathBuf> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| base.join(line))
+        .collect()
+}
@@ -1,4 +1,5 @@
-use crate::types::{TimingHint, Token};
+use crate::orp::calculate_orp;
+use crate::types::{TimedToken, TimingHint, Token};
 
 /// Calculate display duration for a token at given WPM.
 /// Returns duration in milliseconds.
@@ -27,6 +28,23 @@ pub fn calculate_duration(token: &Token, wpm: u16) -> u64 {
     }
 }
 
+/// Tokens with their display time at `wpm` and their focus letter
+#[must_use]
+pub fn timed_tokens(tokens: Vec<Token>, wpm: u16) -> Vec<TimedToken> {
+    tokens
+        .into_iter()
+        .map(|token| {
+            let duration = calculate_duration(&token, wpm);
+            let orp = calculate_orp(&token.word);
+            TimedToken {
+                token,
+                duration_ms: duration,
+                orp_position: orp,
+            }
+        })
+        .collect()
+}
+
 /// Generate timing hints based on word characteristics.
 #[must_use]
 pub fn generate_timing_hint(
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BlockHint {
     Heading(u8),
@@ -37,16 +39,25 @@ pub enum TokenStyle {
     BoldItalic,
     Code,
     Link(String),
+    /// Image alt text, title or caption
+    Image,
+    Strikethrough,
+    /// Reference to a footnote, by index into the document's footnotes
+    FootnoteRef(usize),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BlockContext {
     Paragraph,
-    ListItem(usize),  // depth
-    Quote(usize),     // depth
-    Callout(String),  // type
-    Heading(u8),      // level 1-6
-    TableCell(usize), // table cell with row number (0-indexed)
+    ListItem(usize),       // depth
+    TaskItem(usize, bool), // depth, checked
+    Quote(usize),          // depth
+    Callout(String),       // type
+    CalloutTitle(String),  // callout type
+    Heading(u8),           // level 1-6
+    TableCell(usize),      // table cell with row number (0-indexed)
+    CodeBlock,             // fenced or indented code
+    Figure,                // image alt text, title or caption
 }
 
 impl BlockContext {
@@ -62,9 +73,13 @@ impl BlockContext {
             BlockContext::Heading(6) => "######",
             BlockContext::Heading(_) => "#",
             BlockContext::ListItem(_) => "",
+            BlockContext::TaskItem(_, true) => "[x]",
+            BlockContext::TaskItem(_, false) => "[ ]",
             BlockContext::Quote(_) => ">",
             BlockContext::TableCell(_) => "|",
-            BlockContext::Callout(_) => "[!]",
+            BlockContext::Callout(_) | BlockContext::CalloutTitle(_) => "[!]",
+            BlockContext::CodeBlock => "```",
+            BlockContext::Figure => "img",
             BlockContext::Paragraph => "",
         }
     }
@@ -107,6 +122,207 @@ pub struct Section {
     pub token_end: usize,
 }
 
+/// An outline entry in a part of the document that hasn't loaded yet, such
+/// as a later EPUB chapter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingSection {
+    pub title: String,
+    pub level: u8,
+    /// Index of the part containing the section, in loading order
+    pub part: usize,
+}
+
+/// One file of a document combined from several files, such as a book
+/// directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub title: String,
+    pub token_start: usize,
+    pub token_end: usize,
+}
+
+/// How far a saved document has been read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadingProgress {
+    /// Token to resume reading from
+    pub position: usize,
+    /// Number of tokens in the document
+    pub token_count: usize,
+}
+
+impl ReadingProgress {
+    /// Fraction read (0.0-1.0), reaching 1.0 at the last word
+    #[must_use]
+    pub fn fraction(self) -> f64 {
+        if self.token_count > 1 {
+            self.position as f64 / (self.token_count - 1) as f64
+        } else {
+            1.0
+        }
+    }
+
+    /// True once the last word has been reached
+    #[must_use]
+    pub const fn is_finished(self) -> bool {
+        self.position + 1 >= self.token_count
+    }
+}
+
+/// A code block kept out of the word stream, viewable in an overlay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    /// Language from the fence info string, if any
+    pub language: Option<String>,
+    pub content: String,
+    pub token_start: usize,
+    pub token_end: usize,
+}
+
+impl CodeBlock {
+    #[must_use]
+    pub fn line_count(&self) -> usize {
+        self.content.lines().count()
+    }
+
+    /// Placeholder word shown in place of the block, e.g. "[code: rust, 24 lines]"
+    #[must_use]
+    pub fn placeholder(&self) -> String {
+        let lines = self.line_count();
+        let unit = if lines == 1 { "line" } else { "lines" };
+        match &self.language {
+            Some(lang) => format!("[code: {lang}, {lines} {unit}]"),
+            None => format!("[code: {lines} {unit}]"),
+        }
+    }
+}
+
+/// Encoding of a text input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1, every byte the code point of the same value
+    Latin1,
+    Windows1252,
+}
+
+impl Encoding {
+    /// Canonical label, as accepted by `--encoding`
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+            Self::Latin1 => "iso-8859-1",
+            Self::Windows1252 => "windows-1252",
+        }
+    }
+
+    /// Byte order mark that starts text in this encoding, if it has one
+    pub(crate) const fn bom(self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => b"\xEF\xBB\xBF",
+            Self::Utf16Le => b"\xFF\xFE",
+            Self::Utf16Be => b"\xFE\xFF",
+            Self::Latin1 | Self::Windows1252 => b"",
+        }
+    }
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(label: &str) -> Result<Self, Self::Err> {
+        match label.to_ascii_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Ok(Self::Utf8),
+            "utf-16le" | "utf16le" => Ok(Self::Utf16Le),
+            "utf-16be" | "utf16be" => Ok(Self::Utf16Be),
+            "iso-8859-1" | "latin1" | "latin-1" => Ok(Self::Latin1),
+            "windows-1252" | "cp1252" => Ok(Self::Windows1252),
+            _ => Err(format!(
+                "unknown encoding '{label}' (expected utf-8, utf-16le, utf-16be, latin1 or windows-1252)"
+            )),
+        }
+    }
+}
+
+/// Document-level metadata from front matter or EPUB package data
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentMetadata {
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    /// BCP 47 language tag, e.g. "en" or "fr-CA"
+    pub language: Option<String>,
+    /// File the document was read from
+    pub source_path: Option<PathBuf>,
+    pub word_count: usize,
+    /// ISBN, UUID, DOI or similar identifiers
+    pub identifiers: Vec<String>,
+    pub tags: Vec<String>,
+    /// Character encoding the text was decoded from, for text inputs
+    pub encoding: Option<Encoding>,
+}
+
+impl DocumentMetadata {
+    /// Stable key for storing per-document state such as reading position.
+    ///
+    /// Prefers an identifier, so a book keeps its key when the file moves,
+    /// then the source path, then the title. The path is used as it is, so
+    /// callers canonicalize it when setting it.
+    #[must_use]
+    pub fn persistence_key(&self) -> String {
+        if let Some(id) = self.identifiers.first() {
+            format!("id:{id}")
+        } else if let Some(path) = &self.source_path {
+            format!("path:{}", path.display())
+        } else if let Some(title) = &self.title {
+            format!("title:{title}")
+        } else {
+            "untitled".to_string()
+        }
+    }
+
+    /// Title and authors for display, e.g. "Dune by Frank Herbert"
+    #[must_use]
+    pub fn byline(&self) -> Option<String> {
+        let title = self.title.as_deref()?;
+        if self.authors.is_empty() {
+            Some(title.to_string())
+        } else {
+            Some(format!("{title} by {}", self.authors.join(", ")))
+        }
+    }
+}
+
+/// A footnote body, kept out of the main word stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct Footnote {
+    pub label: String,
+    pub tokens: Vec<Token>,
+}
+
+impl Footnote {
+    /// The note as plain text
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.tokens
+            .iter()
+            .map(|t| t.word.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,158 @@
+//! Large block-glyph rendering for the focus word
+//!
+//! Glyphs are drawn from an embedded 3x5 pixel font. Each pair of pixel rows
+//! is packed into one terminal row using half-block characters, so a word
+//! occupies `BIG_TEXT_HEIGHT` rows and `CELL_WIDTH` columns per character.
+
+/// Width of a glyph in terminal columns
+pub const GLYPH_WIDTH: usize = 3;
+
+/// Columns per character including the gap to the next glyph
+pub const CELL_WIDTH: usize = GLYPH_WIDTH + 1;
+
+/// Height of a rendered glyph in terminal rows
+pub const BIG_TEXT_HEIGHT: u16 = 3;
+
+/// Pixel rows per glyph in the embedded font
+const PIXEL_ROWS: usize = 5;
+
+/// Look up the bitmap for a character (3 bits per row, MSB is the left pixel)
+fn bitmap(c: char) -> Option<[u8; PIXEL_ROWS]> {
+    let rows = match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '?' => [0b110, 0b001, 0b010, 0b000, 0b010],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '\'' | '‘' | '’' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '"' | '“' | '”' => [0b101, 0b101, 0b000, 0b000, 0b000],
+        '-' | '–' | '—' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '[' => [0b011, 0b010, 0b010, 0b010, 0b011],
+        ']' => [0b110, 0b010, 0b010, 0b010, 0b110],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '&' => [0b010, 0b101, 0b010, 0b101, 0b011],
+        '*' => [0b000, 0b101, 0b010, 0b101, 0b000],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        ' ' => [0b000; PIXEL_ROWS],
+        _ => return None,
+    };
+    Some(rows)
+}
+
+/// Pixel value at (row, col), treating rows past the bitmap as empty
+fn pixel(rows: &[u8; PIXEL_ROWS], row: usize, col: usize) -> bool {
+    rows.get(row)
+        .is_some_and(|bits| bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0)
+}
+
+/// Render a character as `BIG_TEXT_HEIGHT` rows of `GLYPH_WIDTH` columns.
+///
+/// Characters missing from the font fall back to the plain character on the
+/// middle row so nothing is silently dropped.
+#[must_use]
+pub fn glyph_rows(c: char) -> [String; BIG_TEXT_HEIGHT as usize] {
+    let Some(rows) = bitmap(c) else {
+        return [
+            " ".repeat(GLYPH_WIDTH),
+            format!(" {c} "),
+            " ".repeat(GLYPH_WIDTH),
+        ];
+    };
+
+    std::array::from_fn(|line| {
+        (0..GLYPH_WIDTH)
+            .map(|col| {
+                let top = pixel(&rows, line * 2, col);
+                let bottom = pixel(&rows, line * 2 + 1, col);
+                match (top, bottom) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                }
+            })
+            .collect()
+    })
+}
+
+/// Column offset of the centre of the glyph at `char_index` within a word
+#[must_use]
+pub const fn glyph_center(char_index: usize) -> usize {
+    char_index * CELL_WIDTH + GLYPH_WIDTH / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyph_rows_have_fixed_width() {
+        for c in "The quick, brown fox! 0123456789".chars() {
+            for row in glyph_rows(c) {
+                assert_eq!(row.chars().count(), GLYPH_WIDTH, "glyph for {c:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_lowercase_uses_uppercase_glyph() {
+        assert_eq!(glyph_rows('a'), glyph_rows('A'));
+    }
+
+    #[test]
+    fn test_half_block_packing() {
+        // 'I' is a full top bar, a stem, then a full bottom bar
+        assert_eq!(glyph_rows('I'), ["▀█▀", " █ ", "▀▀▀"]);
+    }
+
+    #[test]
+    fn test_unknown_char_falls_back_to_plain() {
+        assert_eq!(glyph_rows('é'), ["   ", " é ", "   "]);
+    }
+
+    #[test]
+    fn test_glyph_center() {
+        assert_eq!(glyph_center(0), 1);
+        assert_eq!(glyph_center(2), 9);
+    }
+}
@@ -0,0 +1,63 @@
+use crate::types::CodeBlock;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// Render a code block overlay, starting at line `scroll`
+pub fn render(frame: &mut Frame, area: Rect, block: &CodeBlock, scroll: usize) {
+    // Leave a margin around the overlay
+    let width = area.width.saturating_sub(8).max(20).min(area.width);
+    let height = area.height.saturating_sub(4).max(5).min(area.height);
+    let x = area.x + (area.width.saturating_sub(width)) / 2;
+    let y = area.y + (area.height.saturating_sub(height)) / 2;
+
+    let code_area = Rect {
+        x,
+        y,
+        width,
+        height,
+    };
+
+    // Clear background
+    frame.render_widget(Clear, code_area);
+
+    let line_count = block.line_count();
+    let number_width = line_count.to_string().len();
+    let number_style = Style::default().fg(Color::DarkGray);
+    let code_style = Style::default().fg(Color::Rgb(200, 200, 200));
+
+    let lines: Vec<Line> = block
+        .content
+        .lines()
+        .enumerate()
+        .skip(scroll)
+        .map(|(i, text)| {
+            Line::from(vec![
+                Span::styled(format!("{:>number_width$} ", i + 1), number_style),
+                Span::styled(text.to_string(), code_style),
+            ])
+        })
+        .collect();
+
+    let title = match &block.language {
+        Some(lang) => format!(" {lang} "),
+        None => " code ".to_string(),
+    };
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(title)
+            .title_bottom(Line::from(Span::styled(
+                " j/k scroll  Esc close ",
+                Style::default().fg(Color::DarkGray),
+            )))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(paragraph, code_area);
+}
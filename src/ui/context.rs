@@ -52,15 +52,26 @@ pub fn render_after(frame: &mut Frame, app: &App, area: Rect, gutter_area: Optio
 
 /// A line with its tokens and their global indices
 #[derive(Clone)]
-struct DocLine<'a> {
-    tokens: Vec<(usize, &'a TimedToken)>, // (global_index, token)
-    is_blank: bool,                       // True for separator lines between blocks
+pub(crate) struct DocLine<'a> {
+    pub(crate) tokens: Vec<(usize, &'a TimedToken)>, // (global_index, token)
+    pub(crate) is_blank: bool,                       // True for separator lines between blocks
 }
 
 /// Compute document lines from tokens around current position
 fn compute_document_lines(app: &App, width: usize, max_line_chars: usize) -> Vec<DocLine<'_>> {
+    compute_lines_around(app, app.position(), width, max_line_chars, false)
+}
+
+/// Compute document lines from tokens around an arbitrary position. With
+/// `split_paragraphs`, as in the page view, each paragraph starts a line.
+pub(crate) fn compute_lines_around(
+    app: &App,
+    pos: usize,
+    width: usize,
+    max_line_chars: usize,
+    split_paragraphs: bool,
+) -> Vec<DocLine<'_>> {
     let tokens = app.tokens();
-    let pos = app.position();
 
     // Look back enough tokens to fill context, but not from the beginning
     // This prevents O(n) growth as position increases
@@ -82,10 +93,17 @@ fn compute_document_lines(app: &App, width: usize, max_line_chars: usize) -> Vec
         let was_in_table = last_table_row.is_some();
 
         // Detect block transitions
-        let is_new_list_item = matches!(&token.token.block, BlockContext::ListItem(_))
+        let is_new_list_item = matches!(
+            &token.token.block,
+            BlockContext::ListItem(_) | BlockContext::TaskItem(..)
+        ) && token.token.timing_hint.is_block_start;
+        // Consecutive paragraphs share a block context
+        let is_new_paragraph = split_paragraphs
+            && token.token.block == BlockContext::Paragraph
             && token.token.timing_hint.is_block_start;
 
         let block_changed = is_new_list_item
+            || is_new_paragraph
             || last_block.is_some_and(|b| {
                 if is_table_cell && was_in_table {
                     current_table_row != last_table_row
@@ -97,8 +115,12 @@ fn compute_document_lines(app: &App, width: usize, max_line_chars: usize) -> Vec
         let table_transition = was_in_table != is_table_cell;
         let word_width = token.token.word.chars().count() + 1;
         let would_overflow = current_width + word_width > max_chars;
+        // Code read line by line keeps one source line per display line
+        let is_code_line = matches!(&token.token.block, BlockContext::CodeBlock);
 
-        if (block_changed || table_transition || would_overflow) && !current_line.is_empty() {
+        if (block_changed || table_transition || would_overflow || is_code_line)
+            && !current_line.is_empty()
+        {
             lines.push(DocLine {
                 tokens: current_line,
                 is_blank: false,
@@ -132,7 +154,7 @@ fn compute_document_lines(app: &App, width: usize, max_line_chars: usize) -> Vec
 }
 
 /// Find which line and word index contains the given global position
-fn find_position_in_lines(lines: &[DocLine], pos: usize) -> (usize, usize) {
+pub(crate) fn find_position_in_lines(lines: &[DocLine], pos: usize) -> (usize, usize) {
     for (line_idx, line) in lines.iter().enumerate() {
         for (word_idx, (global_idx, _)) in line.tokens.iter().enumerate() {
             if *global_idx == pos {
@@ -153,7 +175,9 @@ const fn table_row(block: &BlockContext) -> Option<usize> {
 
 /// Compute column widths for table cells in a set of lines
 /// Returns a map from (row, column) to max width needed for that column
-fn compute_table_column_widths(lines: &[DocLine]) -> std::collections::HashMap<usize, usize> {
+pub(crate) fn compute_table_column_widths(
+    lines: &[DocLine],
+) -> std::collections::HashMap<usize, usize> {
     let mut column_widths: std::collections::HashMap<usize, usize> =
         std::collections::HashMap::new();
 
@@ -231,9 +255,13 @@ fn calculate_line_width(line: &DocLine) -> usize {
 const fn block_prefix(block: &BlockContext) -> &'static str {
     match block {
         BlockContext::ListItem(_) => "- ",
+        BlockContext::TaskItem(_, true) => "[x] ",
+        BlockContext::TaskItem(_, false) => "[ ] ",
         BlockContext::Quote(_) | BlockContext::TableCell(_) => "| ",
         BlockContext::Heading(_) | BlockContext::Paragraph => "",
-        BlockContext::Callout(_) => "[i] ",
+        BlockContext::Callout(_) | BlockContext::CalloutTitle(_) => "[i] ",
+        BlockContext::CodeBlock => "  ",
+        BlockContext::Figure => "[img] ",
     }
 }
 
@@ -243,10 +271,25 @@ fn line_prefix(token: &TimedToken) -> &'static str {
         // Only show - for first line of list item, indent continuation lines
         BlockContext::ListItem(_) if token.token.timing_hint.is_block_start => "- ",
         BlockContext::ListItem(_) => "  ", // Align with text after "- "
+        BlockContext::TaskItem(..) if !token.token.timing_hint.is_block_start => "    ",
         other => block_prefix(other),
     }
 }
 
+/// Range of lines shown above the RSVP word, bottom-aligned in `height` rows
+fn before_window(lines: &[DocLine], current_line_idx: usize, height: usize) -> (usize, usize) {
+    // Include current line (it will show words before current_pos)
+    let end_line = (current_line_idx + 1).min(lines.len());
+    let start_line = end_line.saturating_sub(height);
+    (start_line, end_line)
+}
+
+/// Range of lines shown below the RSVP word, starting at the current line
+fn after_window(lines: &[DocLine], current_line_idx: usize, height: usize) -> (usize, usize) {
+    let end_line = (current_line_idx + height).min(lines.len());
+    (current_line_idx.min(end_line), end_line)
+}
+
 /// Render lines before the current line (above context)
 fn render_lines_before(
     frame: &mut Frame,
@@ -261,15 +304,11 @@ fn render_lines_before(
         return;
     }
 
-    let num_lines = area.height as usize;
-    // Include current line (it will show words before current_pos)
-    let end_line = (current_line_idx + 1).min(lines.len());
-    let start_line = end_line.saturating_sub(num_lines);
-    let lines_to_show: Vec<_> = lines[start_line..end_line].iter().collect();
+    let (start_line, end_line) = before_window(lines, current_line_idx, area.height as usize);
+    let lines_to_show = &lines[start_line..end_line];
 
     // Compute table column widths for visible lines
-    let lines_vec: Vec<_> = lines_to_show.iter().map(|l| (*l).clone()).collect();
-    let column_widths = compute_table_column_widths(&lines_vec);
+    let column_widths = compute_table_column_widths(lines_to_show);
 
     // Render from top to bottom, with fading (farther = dimmer)
     for (i, line) in lines_to_show.iter().enumerate() {
@@ -310,9 +349,8 @@ fn render_lines_after(
         return;
     }
 
-    let num_lines = area.height as usize;
-    let end_line = (current_line_idx + num_lines).min(lines.len());
-    let lines_to_show = &lines[current_line_idx..end_line];
+    let (start_line, end_line) = after_window(lines, current_line_idx, area.height as usize);
+    let lines_to_show = &lines[start_line..end_line];
 
     // Compute table column widths for visible lines
     let column_widths = compute_table_column_widths(lines_to_show);
@@ -340,6 +378,69 @@ fn render_lines_after(
     }
 }
 
+/// Find the token under a mouse click in the context above the RSVP word
+#[must_use]
+pub fn token_at_before(app: &App, area: Rect, column: u16, row: u16) -> Option<usize> {
+    token_at(app, area, column, row, ContextType::Before)
+}
+
+/// Find the token under a mouse click in the context below the RSVP word
+#[must_use]
+pub fn token_at_after(app: &App, area: Rect, column: u16, row: u16) -> Option<usize> {
+    token_at(app, area, column, row, ContextType::After)
+}
+
+/// Map a screen cell back to the visible word rendered there
+fn token_at(
+    app: &App,
+    area: Rect,
+    column: u16,
+    row: u16,
+    context_type: ContextType,
+) -> Option<usize> {
+    if column < area.x || row < area.y || column >= area.x + area.width {
+        return None;
+    }
+    let row_offset = (row - area.y) as usize;
+    let height = area.height as usize;
+    if row_offset >= height {
+        return None;
+    }
+
+    let lines = compute_document_lines(app, area.width as usize, app.context_width());
+    let (line_idx, _) = find_position_in_lines(&lines, app.position());
+
+    let (start_line, end_line) = match context_type {
+        ContextType::Before => before_window(&lines, line_idx, height),
+        ContextType::After | ContextType::Page(_) => after_window(&lines, line_idx, height),
+    };
+    let lines_to_show = &lines[start_line..end_line];
+
+    // Before-context is bottom-aligned, after-context is top-aligned
+    let first_row = match context_type {
+        ContextType::Before => height - lines_to_show.len(),
+        ContextType::After | ContextType::Page(_) => 0,
+    };
+    let line = lines_to_show.get(row_offset.checked_sub(first_row)?)?;
+
+    let column_widths = compute_table_column_widths(lines_to_show);
+    let (_, words) = build_line_spans(
+        line,
+        area.width,
+        Style::default(),
+        app.position(),
+        context_type,
+        false,
+        &column_widths,
+    );
+
+    let col = (column - area.x) as usize;
+    words
+        .into_iter()
+        .find(|word| col >= word.start && col < word.end)
+        .map(|word| word.index)
+}
+
 /// Mode for rendering words - either show text or blank spaces
 #[derive(Clone, Copy, PartialEq)]
 enum WordMode {
@@ -347,10 +448,17 @@ enum WordMode {
     Blank,
 }
 
+/// Column range occupied by a visible word on a rendered line
+pub(crate) struct WordExtent {
+    pub(crate) index: usize,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
 /// Render a single line at the given position
 /// Words are shown or blanked based on their position relative to current_pos
 #[allow(clippy::too_many_arguments)]
-fn render_line(
+pub(crate) fn render_line(
     frame: &mut Frame,
     line: &DocLine,
     x: u16,
@@ -378,7 +486,6 @@ fn render_line(
     let style = Style::default().fg(gray);
 
     let first_token = &line.tokens[0].1;
-    let prefix = line_prefix(first_token);
 
     // Render gutter hint if enabled
     if let Some(gutter) = gutter_area {
@@ -397,11 +504,49 @@ fn render_line(
         }
     }
 
+    let (spans, _) = build_line_spans(
+        line,
+        width,
+        style,
+        current_pos,
+        context_type,
+        styling_enabled,
+        column_widths,
+    );
+
+    let line_area = Rect {
+        x,
+        y,
+        width,
+        height: 1,
+    };
+    frame.render_widget(Paragraph::new(Line::from(spans)), line_area);
+}
+
+/// Build the styled spans for a line, along with the columns of each visible word
+pub(crate) fn build_line_spans<'a>(
+    line: &DocLine<'a>,
+    width: u16,
+    style: Style,
+    current_pos: usize,
+    context_type: ContextType,
+    styling_enabled: bool,
+    column_widths: &std::collections::HashMap<usize, usize>,
+) -> (Vec<Span<'a>>, Vec<WordExtent>) {
+    let mut words = Vec::new();
+    if line.is_blank || line.tokens.is_empty() {
+        return (Vec::new(), words);
+    }
+
+    let first_token = &line.tokens[0].1;
+    let prefix = line_prefix(first_token);
+
     // Calculate padding - only center headings, left-align others
     let content_width = calculate_line_width(line);
     let should_center = matches!(&first_token.token.block, BlockContext::Heading(_));
     let padding_size = calculate_padding(content_width, width as usize, should_center);
     let padding = " ".repeat(padding_size);
+    let mut col = padding_size + prefix.chars().count();
     let mut spans = vec![Span::raw(padding), Span::styled(prefix, style)];
 
     // Add words - visible or blank depending on position
@@ -415,10 +560,11 @@ fn render_line(
 
         // When starting a new cell, add padding for the previous cell
         if is_new_cell {
-            if let Some(col) = current_col {
-                let target_width = column_widths.get(&col).copied().unwrap_or(0);
+            if let Some(c) = current_col {
+                let target_width = column_widths.get(&c).copied().unwrap_or(0);
                 if cell_content_width < target_width {
                     let pad = " ".repeat(target_width - cell_content_width);
+                    col += pad.len();
                     spans.push(Span::styled(pad, style));
                 }
             }
@@ -428,6 +574,7 @@ fn render_line(
 
         // Add cell separator between cells in same row
         if is_new_cell && prev_table_row.is_some() && j > 0 {
+            col += 3;
             spans.push(Span::styled(" | ", style));
         }
 
@@ -449,6 +596,7 @@ fn render_line(
                     WordMode::Blank
                 }
             }
+            ContextType::Page(_) => WordMode::Visible,
         };
 
         let word_len = token.token.word.chars().count();
@@ -458,6 +606,15 @@ fn render_line(
             WordMode::Blank => " ".repeat(word_len + 1),
         };
 
+        if mode == WordMode::Visible {
+            words.push(WordExtent {
+                index: *global_idx,
+                start: col,
+                end: col + word_len,
+            });
+        }
+        col += word_len + 1;
+
         // Track cell content width
         if current_col.is_some() {
             cell_content_width += word_len + 1; // word + space
@@ -473,19 +630,38 @@ fn render_line(
             }
             if matches!(
                 &token.token.style,
-                TokenStyle::Italic | TokenStyle::BoldItalic
+                TokenStyle::Italic | TokenStyle::BoldItalic | TokenStyle::Image
             ) {
                 word_style = word_style.add_modifier(Modifier::ITALIC);
             }
+            match &token.token.style {
+                TokenStyle::Strikethrough => {
+                    word_style = word_style.add_modifier(Modifier::CROSSED_OUT);
+                }
+                TokenStyle::FootnoteRef(_) => word_style = word_style.fg(Color::Cyan),
+                _ => {}
+            }
         }
 
-        spans.push(Span::styled(display_text, word_style));
+        // Page view: highlight the cursor word and mark the reading position,
+        // leaving the space after it unstyled
+        if let ContextType::Page(cursor) = context_type {
+            if *global_idx == cursor {
+                word_style = word_style.fg(Color::Black).bg(Color::Yellow);
+            } else if *global_idx == current_pos {
+                word_style = word_style.add_modifier(Modifier::UNDERLINED);
+            }
+            spans.push(Span::styled(token.token.word.clone(), word_style));
+            spans.push(Span::styled(" ", style));
+        } else {
+            spans.push(Span::styled(display_text, word_style));
+        }
         prev_table_row = current_row;
     }
 
     // Add padding for the last cell
-    if let Some(col) = current_col {
-        let target_width = column_widths.get(&col).copied().unwrap_or(0);
+    if let Some(c) = current_col {
+        let target_width = column_widths.get(&c).copied().unwrap_or(0);
         if cell_content_width < target_width {
             let pad = " ".repeat(target_width - cell_content_width);
             spans.push(Span::styled(pad, style));
@@ -497,19 +673,15 @@ fn render_line(
         spans.push(Span::styled("|", style));
     }
 
-    let line_area = Rect {
-        x,
-        y,
-        width,
-        height: 1,
-    };
-    frame.render_widget(Paragraph::new(Line::from(spans)), line_area);
+    (spans, words)
 }
 
 #[derive(Clone, Copy)]
-enum ContextType {
+pub(crate) enum ContextType {
     Before,
     After,
+    /// Full page view with the cursor on the given token
+    Page(usize),
 }
 
 #[cfg(test)]
@@ -0,0 +1,76 @@
+use crate::orp::calculate_orp;
+use crate::types::Footnote;
+use crate::ui::rsvp::build_word;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+/// Maximum width of the footnote popup
+const MAX_WIDTH: u16 = 60;
+
+/// Rows above the note text: padding, the RSVP word, padding
+const WORD_ROWS: u16 = 3;
+
+/// Render a footnote popup centered in `area`, RSVP-reading word `word`
+/// above the full note text
+pub fn render(frame: &mut Frame, area: Rect, footnote: &Footnote, word: usize) {
+    let width = area
+        .width
+        .saturating_sub(8)
+        .clamp(20, MAX_WIDTH)
+        .min(area.width);
+    let text = footnote.text();
+
+    // Rough wrapped height: text width inside the borders, plus borders
+    let inner_width = usize::from(width.saturating_sub(2)).max(1);
+    let text_lines = u16::try_from(text.chars().count().div_ceil(inner_width)).unwrap_or(u16::MAX);
+    let height = text_lines
+        .max(1)
+        .saturating_add(WORD_ROWS + 2)
+        .min(area.height);
+
+    let popup_area = Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    };
+
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(format!(" note {} ", footnote.label))
+        .title_bottom(Line::from(Span::styled(
+            " Space pause  Esc back ",
+            Style::default().fg(Color::DarkGray),
+        )))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    if let Some(token) = footnote.tokens.get(word) {
+        let orp = calculate_orp(&token.word);
+        let (word_lines, _) = build_word(&token.word, orp, Style::default(), inner.width.into());
+        let word_area = Rect {
+            y: inner.y + 1,
+            height: 1.min(inner.height.saturating_sub(1)),
+            ..inner
+        };
+        frame.render_widget(Paragraph::new(word_lines), word_area);
+    }
+
+    let text_area = Rect {
+        y: inner.y + WORD_ROWS.min(inner.height),
+        height: inner.height.saturating_sub(WORD_ROWS),
+        ..inner
+    };
+    let paragraph = Paragraph::new(text)
+        .style(Style::default().fg(Color::DarkGray))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(paragraph, text_area);
+}
@@ -9,7 +9,7 @@ use ratatui::{
 pub fn render(frame: &mut Frame, area: Rect) {
     // Center the help box
     let width = 50.min(area.width.saturating_sub(4));
-    let height = 16.min(area.height.saturating_sub(4));
+    let height = 27.min(area.height.saturating_sub(4));
     let x = (area.width.saturating_sub(width)) / 2;
     let y = (area.height.saturating_sub(height)) / 2;
 
@@ -33,9 +33,20 @@ pub fn render(frame: &mut Frame, area: Rect) {
         Line::from("l/Right   Skip sentence"),
         Line::from("o         Toggle outline"),
         Line::from("c         Toggle context"),
+        Line::from("b         Toggle big text"),
+        Line::from("p         Page view (Enter resumes)"),
+        Line::from("v         View code block"),
+        Line::from("f         Read footnote"),
+        Line::from("Enter     Follow link"),
+        Line::from("Backspace Back from link"),
+        Line::from("n / N     Next/previous document"),
+        Line::from("u         Queue of documents"),
         Line::from("q         Quit"),
         Line::from("?         Toggle help"),
         Line::from(""),
+        Line::from("Mouse     Click bar to seek, wheel for"),
+        Line::from("          speed, click words/sections"),
+        Line::from(""),
         Line::from(Span::styled(
             "Press ? to close",
             Style::default().fg(Color::DarkGray),
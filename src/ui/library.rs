@@ -0,0 +1,107 @@
+use crate::app::App;
+use crate::library::{format_duration, format_last_read, now};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+/// Lines above the list: filter prompt and a blank line
+const HEADER_HEIGHT: u16 = 2;
+
+pub fn render(frame: &mut Frame, app: &App, area: Rect) {
+    if area.height <= HEADER_HEIGHT {
+        return;
+    }
+
+    // Filter prompt, or a notice such as a document that failed to open
+    let prompt = match app.notice() {
+        Some(notice) => Line::from(Span::styled(
+            notice.to_string(),
+            Style::default().fg(Color::Red),
+        )),
+        None => {
+            let mut spans = vec![Span::styled("Library  ", Style::default().fg(Color::Cyan))];
+            if app.is_filtering_library() {
+                spans.push(Span::raw(format!("/{}", app.library_filter())));
+                spans.push(Span::styled("▏", Style::default().fg(Color::DarkGray)));
+            } else if app.library_filter().is_empty() {
+                spans.push(Span::styled(
+                    "/ filter  Enter open  ? help  q quit",
+                    Style::default().fg(Color::DarkGray),
+                ));
+            } else {
+                spans.push(Span::raw(format!("/{}", app.library_filter())));
+            }
+            Line::from(spans)
+        }
+    };
+    frame.render_widget(Paragraph::new(prompt), Rect { height: 1, ..area });
+
+    let list_area = Rect {
+        y: area.y + HEADER_HEIGHT,
+        height: area.height - HEADER_HEIGHT,
+        ..area
+    };
+    let matches = app.filtered_library();
+    if matches.is_empty() {
+        let message = if app.library().is_empty() {
+            "No documents read yet. Open one with: rsvp-term <file>"
+        } else {
+            "No matches"
+        };
+        let para = Paragraph::new(Span::styled(message, Style::default().fg(Color::DarkGray)));
+        frame.render_widget(para, list_area);
+        return;
+    }
+
+    // Keep the selection in view
+    let height = list_area.height as usize;
+    let selected = app.library_selection();
+    let first = selected
+        .saturating_sub(height / 2)
+        .min(matches.len().saturating_sub(height));
+
+    let width = list_area.width as usize;
+    let today = now();
+    let lines: Vec<Line> = matches
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(row, &index)| {
+            let entry = &app.library()[index];
+            let pct = (entry.progress() * 100.0).round() as u16;
+            let remaining = if entry.is_finished() {
+                "finished".to_string()
+            } else {
+                format!("{} left", format_duration(entry.time_remaining(app.wpm())))
+            };
+            let details = format!(
+                "{pct:>3}%  {remaining:>14}  {:>12}",
+                format_last_read(entry.last_read, today)
+            );
+
+            let room = width.saturating_sub(details.chars().count() + 2);
+            let title: String = entry.title.chars().take(room).collect();
+            let gap = width.saturating_sub(title.chars().count() + details.chars().count());
+
+            let style = if row == selected {
+                Style::default()
+                    .fg(Color::Rgb(200, 200, 200))
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Rgb(130, 130, 130))
+            };
+            Line::from(vec![
+                Span::styled(title, style),
+                Span::raw(" ".repeat(gap)),
+                Span::styled(details, Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines), list_area);
+}
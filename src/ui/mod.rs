@@ -1,11 +1,18 @@
+pub mod bigtext;
+pub mod code;
 pub mod common;
 pub mod context;
+pub mod footnote;
 pub mod help;
+pub mod library;
 pub mod outline;
+pub mod page;
+pub mod queue;
 pub mod rsvp;
 pub mod status;
 
-use crate::app::App;
+use crate::app::{App, Overlay, ViewMode};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::Frame;
 
 /// Width of the gutter column for hint chars
@@ -13,28 +20,82 @@ pub const GUTTER_WIDTH: u16 = 4;
 /// Padding between gutter and content
 pub const GUTTER_PADDING: u16 = 2;
 
-pub fn render(frame: &mut Frame, app: &App) {
-    use crate::app::ViewMode;
-    use ratatui::layout::{Constraint, Direction, Layout};
+/// Screen element under a mouse position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HitTarget {
+    /// Fraction of the document on the progress bar
+    Progress(f64),
+    /// Outline entry by section index
+    Section(usize),
+    /// Context word by token index
+    Token(usize),
+}
 
+/// Areas of the reading view
+struct ReadingLayout {
+    gutter: Option<Rect>,
+    before: Rect,
+    rsvp: Rect,
+    after: Rect,
+}
+
+/// Split the frame into main content and status bar
+fn main_layout(area: Rect) -> (Rect, Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(0),    // Main content
             Constraint::Length(2), // Status bar
         ])
-        .split(frame.area());
+        .split(area);
+    (chunks[0], chunks[1])
+}
+
+pub fn render(frame: &mut Frame, app: &App) {
+    // The library is shown before any document is open and needs no status bar
+    if app.view_mode() == ViewMode::Library {
+        library::render(frame, app, frame.area());
+        if app.show_help() {
+            help::render(frame, frame.area());
+        }
+        return;
+    }
+
+    let (main_area, status_area) = main_layout(frame.area());
 
     match app.view_mode() {
         ViewMode::Reading => {
-            render_reading_view(frame, app, chunks[0]);
+            render_reading_view(frame, app, main_area);
         }
         ViewMode::Outline => {
-            outline::render(frame, app, chunks[0]);
+            outline::render(frame, app, main_area);
+        }
+        ViewMode::Page => {
+            let (gutter, content) = split_gutter(app, main_area);
+            page::render(frame, app, content, gutter);
+        }
+        ViewMode::Queue => {
+            queue::render(frame, app, main_area);
         }
+        ViewMode::Library => {}
     }
 
-    status::render(frame, app, chunks[1]);
+    status::render(frame, app, status_area);
+
+    // Render popup overlay if active
+    match app.overlay() {
+        Some(Overlay::Code { index, scroll }) => {
+            if let Some(block) = app.code_blocks().get(index) {
+                code::render(frame, main_area, block, scroll);
+            }
+        }
+        Some(Overlay::Footnote { index, word, .. }) => {
+            if let Some(note) = app.footnotes().get(index) {
+                footnote::render(frame, main_area, note, word);
+            }
+        }
+        None => {}
+    }
 
     // Render help overlay if active
     if app.show_help() {
@@ -42,11 +103,51 @@ pub fn render(frame: &mut Frame, app: &App) {
     }
 }
 
-fn render_reading_view(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
-    use ratatui::layout::{Constraint, Direction, Layout};
+/// Find what is drawn at a screen position, for mouse handling
+#[must_use]
+pub fn hit_test(app: &App, area: Rect, column: u16, row: u16) -> Option<HitTarget> {
+    if !app.mouse_active() {
+        return None;
+    }
+    let (main_area, status_area) = main_layout(area);
+
+    if let Some(fraction) = status::seek_fraction(status_area, column, row) {
+        return Some(HitTarget::Progress(fraction));
+    }
+
+    match app.view_mode() {
+        ViewMode::Outline => outline::section_at(app, main_area, row).map(HitTarget::Section),
+        ViewMode::Reading if app.context_enabled() => {
+            let layout = reading_layout(app, main_area);
+            context::token_at_before(app, layout.before, column, row)
+                .or_else(|| context::token_at_after(app, layout.after, column, row))
+                .map(HitTarget::Token)
+        }
+        ViewMode::Reading | ViewMode::Queue | ViewMode::Library => None,
+        ViewMode::Page => {
+            let (_, content) = split_gutter(app, main_area);
+            page::token_at(app, content, column, row).map(HitTarget::Token)
+        }
+    }
+}
+
+/// Token the page cursor lands on after moving `delta` lines
+#[must_use]
+pub fn page_cursor_after_lines(app: &App, area: Rect, delta: isize) -> usize {
+    let (main_area, _) = main_layout(area);
+    let (_, content) = split_gutter(app, main_area);
+    page::cursor_after_lines(app, content.width, delta)
+}
+
+/// Number of document lines visible in the page view
+#[must_use]
+pub fn page_height(area: Rect) -> u16 {
+    main_layout(area).0.height
+}
 
-    // If hint_chars enabled, split horizontally first to create gutter area
-    let (gutter_area, content_area) = if app.hint_chars_enabled {
+/// If hint_chars enabled, split horizontally to create the gutter area
+fn split_gutter(app: &App, area: Rect) -> (Option<Rect>, Rect) {
+    if app.hint_chars_enabled {
         let horizontal = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
@@ -57,24 +158,39 @@ fn render_reading_view(frame: &mut Frame, app: &App, area: ratatui::layout::Rect
         (Some(horizontal[0]), horizontal[1])
     } else {
         (None, area)
-    };
+    }
+}
+
+fn reading_layout(app: &App, area: Rect) -> ReadingLayout {
+    let (gutter_area, content_area) = split_gutter(app, area);
 
     // Split content into: context above, RSVP line, context below
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Percentage(40), // Context above
-            Constraint::Length(3),      // RSVP line (with padding)
-            Constraint::Percentage(40), // Context below
+            Constraint::Percentage(40),                // Context above
+            Constraint::Length(rsvp::row_height(app)), // RSVP line (with padding)
+            Constraint::Percentage(40),                // Context below
         ])
         .split(content_area);
 
+    ReadingLayout {
+        gutter: gutter_area,
+        before: chunks[0],
+        rsvp: chunks[1],
+        after: chunks[2],
+    }
+}
+
+fn render_reading_view(frame: &mut Frame, app: &App, area: Rect) {
+    let layout = reading_layout(app, area);
+
     // Render context only when enabled
     if app.context_enabled() {
-        context::render_before(frame, app, chunks[0], gutter_area);
+        context::render_before(frame, app, layout.before, layout.gutter);
     }
-    rsvp::render(frame, app, chunks[1], gutter_area);
+    rsvp::render(frame, app, layout.rsvp, layout.gutter);
     if app.context_enabled() {
-        context::render_after(frame, app, chunks[2], gutter_area);
+        context::render_after(frame, app, layout.after, layout.gutter);
     }
 }
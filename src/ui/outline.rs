@@ -11,7 +11,7 @@ use ratatui::{
 };
 
 pub fn render(frame: &mut Frame, app: &App, area: Rect) {
-    let sections = app.sections();
+    let sections = app.outline_entries();
     if sections.is_empty() {
         return;
     }
@@ -27,9 +27,9 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     let center_y = area.height / 2;
 
     // Get selected section info for guide bars
-    let selected_section = &sections[selected];
-    let hint = "#".repeat(selected_section.level as usize);
-    let title_width = selected_section.title.chars().count();
+    let (selected_title, selected_level) = sections[selected];
+    let hint = "#".repeat(selected_level as usize);
+    let title_width = selected_title.chars().count();
     let title_padding = calculate_padding(title_width, area.width as usize, true);
     let tick_pos = title_padding + title_width / 2;
 
@@ -54,7 +54,7 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
         let style = Style::default()
             .fg(Color::Rgb(200, 200, 200))
             .add_modifier(Modifier::BOLD);
-        let text = format!("{}{}", " ".repeat(title_padding), selected_section.title);
+        let text = format!("{}{}", " ".repeat(title_padding), selected_title);
         let para = Paragraph::new(Line::from(Span::styled(text, style)));
         frame.render_widget(
             para,
@@ -88,10 +88,9 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     let mut above_idx = selected.saturating_sub(1);
     let mut distance = 1usize;
     while above_idx < sections.len() && above_y < area.height {
-        let section = &sections[above_idx];
         render_item(
             frame,
-            section,
+            sections[above_idx].0,
             area.x,
             area.y + above_y,
             area.width,
@@ -111,10 +110,9 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     let mut below_idx = selected + 1;
     let mut distance = 1usize;
     while below_idx < sections.len() && below_y < area.height {
-        let section = &sections[below_idx];
         render_item(
             frame,
-            section,
+            sections[below_idx].0,
             area.x,
             area.y + below_y,
             area.width,
@@ -127,14 +125,33 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
     }
 }
 
-fn render_item(
-    frame: &mut Frame,
-    section: &crate::types::Section,
-    x: u16,
-    y: u16,
-    width: u16,
-    distance: usize,
-) {
+/// Find the outline entry drawn on a given screen row
+#[must_use]
+pub fn section_at(app: &App, area: Rect, row: u16) -> Option<usize> {
+    let sections = app.outline_entries();
+    if sections.is_empty() || area.height < 3 || row < area.y || row >= area.y + area.height {
+        return None;
+    }
+
+    let selected = app.outline_selection();
+    let center_y = area.height / 2;
+    let offset = row - area.y;
+
+    // The selected item spans its guide bars
+    if offset + 1 >= center_y && offset <= center_y + 1 {
+        return Some(selected);
+    }
+
+    let index = if offset < center_y {
+        let distance = (center_y - 1 - offset) as usize;
+        selected.checked_sub(distance)?
+    } else {
+        selected + (offset - center_y - 1) as usize
+    };
+    (index < sections.len()).then_some(index)
+}
+
+fn render_item(frame: &mut Frame, title: &str, x: u16, y: u16, width: u16, distance: usize) {
     let gray = match distance {
         1 => Color::Rgb(150, 150, 150),
         2 => Color::Rgb(110, 110, 110),
@@ -143,9 +160,9 @@ fn render_item(
     };
     let style = Style::default().fg(gray);
 
-    let content_width = section.title.chars().count();
+    let content_width = title.chars().count();
     let padding = calculate_padding(content_width, width as usize, true);
-    let text = format!("{}{}", " ".repeat(padding), section.title);
+    let text = format!("{}{}", " ".repeat(padding), title);
 
     let para = Paragraph::new(Line::from(Span::styled(text, style)));
     frame.render_widget(
@@ -0,0 +1,110 @@
+//! Traditional scrolling page view of the document
+//!
+//! Lays the document out with the same line computation as the context
+//! display, with every word visible and the cursor word highlighted.
+
+use crate::app::App;
+use crate::ui::context::{
+    build_line_spans, compute_lines_around, compute_table_column_widths, find_position_in_lines,
+    render_line, ContextType, DocLine,
+};
+use ratatui::{layout::Rect, style::Style, Frame};
+
+/// Lines visible in the page, keeping the cursor line vertically centered
+fn visible_window(lines: &[DocLine], cursor_line: usize, height: usize) -> (usize, usize) {
+    let start = cursor_line.saturating_sub(height / 2);
+    let end = (start + height).min(lines.len());
+    (start, end)
+}
+
+pub fn render(frame: &mut Frame, app: &App, area: Rect, gutter_area: Option<Rect>) {
+    if area.height == 0 {
+        return;
+    }
+
+    let cursor = app.page_cursor();
+    let lines = compute_lines_around(app, cursor, area.width as usize, app.context_width(), true);
+    let (cursor_line, _) = find_position_in_lines(&lines, cursor);
+    let (start, end) = visible_window(&lines, cursor_line, area.height as usize);
+    let lines_to_show = &lines[start..end];
+    let column_widths = compute_table_column_widths(lines_to_show);
+
+    for (i, line) in lines_to_show.iter().enumerate() {
+        render_line(
+            frame,
+            line,
+            area.x,
+            area.y + i as u16,
+            area.width,
+            0,
+            app.position(),
+            ContextType::Page(cursor),
+            app.styling_enabled,
+            gutter_area,
+            &column_widths,
+        );
+    }
+}
+
+/// Token index reached by moving the cursor `delta` lines up (negative) or down.
+///
+/// Blank separator lines are skipped, and the cursor keeps its word offset
+/// within the line where possible.
+#[must_use]
+pub fn cursor_after_lines(app: &App, width: u16, delta: isize) -> usize {
+    let cursor = app.page_cursor();
+    let lines = compute_lines_around(app, cursor, width as usize, app.context_width(), true);
+    if lines.is_empty() {
+        return cursor;
+    }
+
+    let (line_idx, word_idx) = find_position_in_lines(&lines, cursor);
+    let mut target = line_idx.saturating_add_signed(delta).min(lines.len() - 1);
+
+    // Step past blank separators in the direction of travel
+    while lines[target].is_blank {
+        target = match (delta < 0, target) {
+            (true, 0) => break,
+            (true, t) => t - 1,
+            (false, t) if t + 1 < lines.len() => t + 1,
+            (false, _) => break,
+        };
+    }
+
+    let line = &lines[target];
+    line.tokens
+        .get(word_idx.min(line.tokens.len().saturating_sub(1)))
+        .map_or(cursor, |(index, _)| *index)
+}
+
+/// Find the token under a mouse click in the page view
+#[must_use]
+pub fn token_at(app: &App, area: Rect, column: u16, row: u16) -> Option<usize> {
+    if column < area.x || row < area.y || column >= area.x + area.width {
+        return None;
+    }
+
+    let cursor = app.page_cursor();
+    let lines = compute_lines_around(app, cursor, area.width as usize, app.context_width(), true);
+    let (cursor_line, _) = find_position_in_lines(&lines, cursor);
+    let (start, end) = visible_window(&lines, cursor_line, area.height as usize);
+    let lines_to_show = &lines[start..end];
+    let line = lines_to_show.get((row - area.y) as usize)?;
+
+    let column_widths = compute_table_column_widths(lines_to_show);
+    let (_, words) = build_line_spans(
+        line,
+        area.width,
+        Style::default(),
+        app.position(),
+        ContextType::Page(cursor),
+        false,
+        &column_widths,
+    );
+
+    let col = (column - area.x) as usize;
+    words
+        .into_iter()
+        .find(|word| col >= word.start && col < word.end)
+        .map(|word| word.index)
+}
@@ -0,0 +1,68 @@
+use crate::app::App;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+/// Width of the percentage column
+const PROGRESS_WIDTH: usize = 12;
+
+pub fn render(frame: &mut Frame, app: &App, area: Rect) {
+    let queue = app.queue();
+    if queue.is_empty() || area.height == 0 {
+        return;
+    }
+
+    // Keep the selection in view, centered once the list is longer than the area
+    let height = area.height as usize;
+    let selected = app.queue_selection();
+    let first = selected
+        .saturating_sub(height / 2)
+        .min(queue.len().saturating_sub(height));
+
+    let width = area.width as usize;
+    let lines: Vec<Line> = queue
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(height)
+        .map(|(index, entry)| {
+            let marker = if index == app.queue_index() {
+                "▶ "
+            } else {
+                "  "
+            };
+            let status = match &entry.error {
+                Some(_) => "unreadable".to_string(),
+                None => {
+                    let pct = (app.queue_progress(index) * 100.0).round() as u16;
+                    format!("{pct:>3}%")
+                }
+            };
+            let label = format!("{marker}{}. {}", index + 1, entry.label());
+            let room = width.saturating_sub(PROGRESS_WIDTH);
+            let label: String = label.chars().take(room).collect();
+            let gap = width.saturating_sub(label.chars().count() + status.chars().count());
+
+            let style = if index == selected {
+                Style::default()
+                    .fg(Color::Rgb(200, 200, 200))
+                    .add_modifier(Modifier::BOLD)
+            } else if entry.error.is_some() {
+                Style::default().fg(Color::Rgb(80, 80, 80))
+            } else {
+                Style::default().fg(Color::Rgb(130, 130, 130))
+            };
+            Line::from(vec![
+                Span::styled(label, style),
+                Span::raw(" ".repeat(gap)),
+                Span::styled(status, Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines), area);
+}
@@ -1,5 +1,6 @@
 use crate::app::App;
 use crate::types::TokenStyle;
+use crate::ui::bigtext::{glyph_center, glyph_rows, BIG_TEXT_HEIGHT, CELL_WIDTH, GLYPH_WIDTH};
 use crate::ui::common::{fade_char_left, BRIGHTNESS_SOLID_END, FADE_TOTAL, GUIDE_COLOR};
 use crate::ui::GUTTER_WIDTH;
 use ratatui::{
@@ -10,6 +11,19 @@ use ratatui::{
     Frame,
 };
 
+/// Gutter marker shown on footnote references
+const FOOTNOTE_HINT: &str = "^";
+
+/// Height of the RSVP row: the word plus a guide line above and below
+#[must_use]
+pub const fn row_height(app: &App) -> u16 {
+    if app.big_text_enabled {
+        BIG_TEXT_HEIGHT + 2
+    } else {
+        3
+    }
+}
+
 pub fn render(frame: &mut Frame, app: &App, area: Rect, gutter_area: Option<Rect>) {
     let Some(token) = app.current_token() else {
         return;
@@ -28,15 +42,24 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect, gutter_area: Option<Rect
                 .add_modifier(Modifier::ITALIC),
             TokenStyle::Code => Style::default().bg(Color::Rgb(60, 60, 60)),
             TokenStyle::Link(_) => Style::default().add_modifier(Modifier::UNDERLINED),
+            TokenStyle::Strikethrough => Style::default().add_modifier(Modifier::CROSSED_OUT),
+            TokenStyle::FootnoteRef(_) => Style::default().fg(Color::Cyan),
+            TokenStyle::Image => Style::default()
+                .add_modifier(Modifier::ITALIC)
+                .bg(Color::Rgb(40, 50, 70)),
             TokenStyle::Normal => Style::default(),
         }
     } else {
         Style::default()
     };
 
-    // Calculate ORP center position
-    let center = area.width as usize / 2;
-    let left_padding = center.saturating_sub(orp_pos);
+    // Build the word rows and the column of the ORP character within the area
+    let (word_lines, tick_pos) = if app.big_text_enabled {
+        build_big_word(word, orp_pos, base_style, area.width as usize)
+    } else {
+        build_word(word, orp_pos, base_style, area.width as usize)
+    };
+    let word_height = word_lines.len() as u16;
 
     // Build guide line with tick mark at ORP position
     let guide_style = Style::default().fg(GUIDE_COLOR);
@@ -44,13 +67,13 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect, gutter_area: Option<Rect
     // Build guide lines - with fade effect if hint_chars enabled
     let (top_line, bottom_line) = if app.hint_chars_enabled {
         // Build faded guide lines
-        let top_spans = build_faded_guide_line(area.width as usize, left_padding + orp_pos, '┬');
-        let bottom_spans = build_faded_guide_line(area.width as usize, left_padding + orp_pos, '┴');
+        let top_spans = build_faded_guide_line(area.width as usize, tick_pos, '┬');
+        let bottom_spans = build_faded_guide_line(area.width as usize, tick_pos, '┴');
         (top_spans, bottom_spans)
     } else {
         // Simple guide lines
-        let top = build_guide_line(area.width as usize, left_padding + orp_pos, '┬');
-        let bottom = build_guide_line(area.width as usize, left_padding + orp_pos, '┴');
+        let top = build_guide_line(area.width as usize, tick_pos, '┬');
+        let bottom = build_guide_line(area.width as usize, tick_pos, '┴');
         (
             vec![Span::styled(top, guide_style)],
             vec![Span::styled(bottom, guide_style)],
@@ -59,51 +82,36 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect, gutter_area: Option<Rect
     let top_para = Paragraph::new(Line::from(top_line));
     let bottom_para = Paragraph::new(Line::from(bottom_line));
 
-    // Build styled word with ORP highlight
-    let chars: Vec<char> = word.chars().collect();
-    let mut spans = Vec::with_capacity(chars.len() + 1);
-
-    spans.push(Span::raw(" ".repeat(left_padding)));
-
-    for (i, c) in chars.iter().enumerate() {
-        let char_style = if i == orp_pos {
-            base_style.fg(Color::Red).add_modifier(Modifier::BOLD)
-        } else {
-            base_style.fg(Color::White)
-        };
-        spans.push(Span::styled(c.to_string(), char_style));
-    }
-
-    let word_para = Paragraph::new(Line::from(spans));
-
-    // Center vertically - need 3 lines: guide, word, guide
-    let vertical_center = area.height / 2;
+    // Center vertically - guide, word rows, guide
+    let word_top = area.height.saturating_sub(word_height) / 2;
+    let word_bottom = word_top + word_height;
+    let vertical_center = word_top + word_height / 2;
 
     // Top guide line
-    if vertical_center > 0 {
+    if word_top > 0 {
         let top_area = Rect {
             x: area.x,
-            y: area.y + vertical_center - 1,
+            y: area.y + word_top - 1,
             width: area.width,
             height: 1,
         };
         frame.render_widget(top_para, top_area);
     }
 
-    // Word line
+    // Word lines
     let word_area = Rect {
         x: area.x,
-        y: area.y + vertical_center,
+        y: area.y + word_top,
         width: area.width,
-        height: 1,
+        height: word_height.min(area.height.saturating_sub(word_top)),
     };
-    frame.render_widget(word_para, word_area);
+    frame.render_widget(Paragraph::new(word_lines), word_area);
 
     // Bottom guide line
-    if vertical_center + 1 < area.height {
+    if word_bottom < area.height {
         let bottom_area = Rect {
             x: area.x,
-            y: area.y + vertical_center + 1,
+            y: area.y + word_bottom,
             width: area.width,
             height: 1,
         };
@@ -114,14 +122,17 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect, gutter_area: Option<Rect
     if let Some(gutter) = gutter_area {
         let gutter_style = Style::default().fg(GUIDE_COLOR);
 
-        // Current block hint at word line
-        let block_hint = token.token.block.hint_chars();
+        // Current block hint at word line; footnote references get a marker
+        let block_hint = match token.token.style {
+            TokenStyle::FootnoteRef(_) => FOOTNOTE_HINT,
+            _ => token.token.block.hint_chars(),
+        };
         if !block_hint.is_empty() {
             let hint_text = format!("{:>width$}", block_hint, width = GUTTER_WIDTH as usize);
             let hint_para = Paragraph::new(Line::from(Span::styled(hint_text, gutter_style)));
             let hint_area = Rect {
                 x: gutter.x,
-                y: gutter.y + vertical_center,
+                y: area.y + vertical_center,
                 width: GUTTER_WIDTH,
                 height: 1,
             };
@@ -135,12 +146,12 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect, gutter_area: Option<Rect
                 let hint_text = format!("{:>width$}", parent_hint, width = GUTTER_WIDTH as usize);
 
                 // Top guide line gutter
-                if vertical_center > 0 {
+                if word_top > 0 {
                     let top_hint_para =
                         Paragraph::new(Line::from(Span::styled(&hint_text, gutter_style)));
                     let top_hint_area = Rect {
                         x: gutter.x,
-                        y: gutter.y + vertical_center - 1,
+                        y: area.y + word_top - 1,
                         width: GUTTER_WIDTH,
                         height: 1,
                     };
@@ -148,12 +159,12 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect, gutter_area: Option<Rect
                 }
 
                 // Bottom guide line gutter
-                if vertical_center + 1 < area.height {
+                if word_bottom < area.height {
                     let bottom_hint_para =
                         Paragraph::new(Line::from(Span::styled(&hint_text, gutter_style)));
                     let bottom_hint_area = Rect {
                         x: gutter.x,
-                        y: gutter.y + vertical_center + 1,
+                        y: area.y + word_bottom,
                         width: GUTTER_WIDTH,
                         height: 1,
                     };
@@ -164,6 +175,71 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect, gutter_area: Option<Rect
     }
 }
 
+/// Style for a character of the focus word, highlighting the ORP
+fn char_style(base_style: Style, is_orp: bool) -> Style {
+    if is_orp {
+        base_style.fg(Color::Red).add_modifier(Modifier::BOLD)
+    } else {
+        base_style.fg(Color::White)
+    }
+}
+
+/// Build the single-row word with ORP highlight.
+/// Returns the line and the column of the ORP character.
+pub(crate) fn build_word<'a>(
+    word: &str,
+    orp_pos: usize,
+    base_style: Style,
+    width: usize,
+) -> (Vec<Line<'a>>, usize) {
+    // Calculate ORP center position
+    let center = width / 2;
+    let left_padding = center.saturating_sub(orp_pos);
+
+    let chars: Vec<char> = word.chars().collect();
+    let mut spans = Vec::with_capacity(chars.len() + 1);
+
+    spans.push(Span::raw(" ".repeat(left_padding)));
+
+    for (i, c) in chars.iter().enumerate() {
+        spans.push(Span::styled(
+            c.to_string(),
+            char_style(base_style, i == orp_pos),
+        ));
+    }
+
+    (vec![Line::from(spans)], left_padding + orp_pos)
+}
+
+/// Build the word as block glyphs with the ORP glyph centered under the ticks.
+/// Returns the glyph rows and the column of the ORP glyph's center.
+fn build_big_word<'a>(
+    word: &str,
+    orp_pos: usize,
+    base_style: Style,
+    width: usize,
+) -> (Vec<Line<'a>>, usize) {
+    let center = width / 2;
+    let left_padding = center.saturating_sub(glyph_center(orp_pos));
+
+    let mut lines: Vec<Vec<Span>> = (0..BIG_TEXT_HEIGHT)
+        .map(|_| vec![Span::raw(" ".repeat(left_padding))])
+        .collect();
+
+    for (i, c) in word.chars().enumerate() {
+        let style = char_style(base_style, i == orp_pos);
+        for (line, row) in lines.iter_mut().zip(glyph_rows(c)) {
+            line.push(Span::styled(row, style));
+            line.push(Span::raw(" ".repeat(CELL_WIDTH - GLYPH_WIDTH)));
+        }
+    }
+
+    (
+        lines.into_iter().map(Line::from).collect(),
+        left_padding + glyph_center(orp_pos),
+    )
+}
+
 /// Build a guide line with a tick mark at the specified position
 fn build_guide_line(width: usize, tick_pos: usize, tick_char: char) -> String {
     let mut line = String::with_capacity(width);
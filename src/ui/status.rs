@@ -1,31 +1,90 @@
 use crate::app::App;
+use crate::types::Encoding;
 use ratatui::{
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span},
     widgets::{Gauge, Paragraph},
     Frame,
 };
+use std::rc::Rc;
 
-pub fn render(frame: &mut Frame, app: &App, area: Rect) {
-    use ratatui::layout::{Constraint, Direction, Layout};
-
-    let chunks = Layout::default()
+fn layout(area: Rect) -> Rc<[Rect]> {
+    Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1), // Section title + progress %
             Constraint::Length(1), // Progress bar + WPM + state
         ])
-        .split(area);
+        .split(area)
+}
+
+/// Fraction of the document under a mouse position on the progress bar
+#[must_use]
+pub fn seek_fraction(area: Rect, column: u16, row: u16) -> Option<f64> {
+    let gauge = layout(area)[1];
+    let on_gauge = row == gauge.y && column >= gauge.x && column < gauge.x + gauge.width;
+    on_gauge.then(|| f64::from(column - gauge.x) / f64::from(gauge.width))
+}
+
+pub fn render(frame: &mut Frame, app: &App, area: Rect) {
+    let chunks = layout(area);
 
-    // Top line: section title and percentage
-    let section_title = app.current_section_title().unwrap_or("Document");
+    // Top line: section title and percentage, then the document byline
+    let byline = app.metadata().byline();
+    let section_title = app.current_section_title();
+    let title = section_title
+        .map(str::to_string)
+        .or_else(|| byline.clone())
+        .unwrap_or_else(|| "Document".to_string());
     let progress_pct = (app.progress() * 100.0).round() as u16;
-    let top_line = Line::from(vec![
+    let mut spans = vec![
         Span::raw("> "),
-        Span::styled(section_title, Style::default().fg(Color::Cyan)),
+        Span::styled(title, Style::default().fg(Color::Cyan)),
         Span::raw(format!(" {progress_pct:>3}%")),
-    ]);
+    ];
+    if app.is_loading() {
+        spans.push(Span::styled(
+            format!("  ~{} words (loading)", app.word_count()),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if let (Some(_), Some(byline)) = (section_title, byline) {
+        spans.push(Span::styled(
+            format!("  · {byline}"),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if let Some(notice) = app.recent_notice() {
+        spans.push(Span::styled(
+            format!("  [{notice}]"),
+            Style::default().fg(Color::Green),
+        ));
+    }
+    if let Some(encoding) = app.metadata().encoding.filter(|&e| e != Encoding::Utf8) {
+        spans.push(Span::styled(
+            format!("  [{encoding}]"),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    match app.warnings().len() {
+        0 => {}
+        1 => spans.push(Span::styled(
+            "  [1 warning]",
+            Style::default().fg(Color::Yellow),
+        )),
+        n => spans.push(Span::styled(
+            format!("  [{n} warnings]"),
+            Style::default().fg(Color::Yellow),
+        )),
+    }
+    if app.queue().len() > 1 {
+        spans.push(Span::styled(
+            format!("  [{}/{}]", app.queue_index() + 1, app.queue().len()),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    let top_line = Line::from(spans);
     frame.render_widget(Paragraph::new(top_line), chunks[0]);
 
     // Bottom line: progress bar, WPM, pause state
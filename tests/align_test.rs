@@ -0,0 +1,43 @@
+use rsvp_term::align::map_position;
+
+fn words(text: &str) -> Vec<&str> {
+    text.split_whitespace().collect()
+}
+
+#[test]
+fn test_map_position_unchanged_document() {
+    let doc = words("the quick brown fox jumps over the lazy dog");
+    for position in 0..doc.len() {
+        assert_eq!(map_position(&doc, position, &doc), position);
+    }
+}
+
+#[test]
+fn test_map_position_after_insert_before() {
+    let old = words("one two three four five six seven eight");
+    let new = words("a new opening paragraph here one two three four five six seven eight");
+    // "five" moves from 4 to 9
+    assert_eq!(map_position(&old, 4, &new), 9);
+}
+
+#[test]
+fn test_map_position_when_current_word_was_edited() {
+    let old = words("alpha beta gamma delta epsilon zeta eta theta");
+    let new = words("alpha beta gamma DELTA epsilon zeta eta theta");
+    assert_eq!(map_position(&old, 3, &new), 3);
+}
+
+#[test]
+fn test_map_position_repeated_phrases_prefer_nearby() {
+    let old = words("x y z a b c x y z a b c x y z");
+    let new = words("x y z a b c x y z a b c x y z");
+    assert_eq!(map_position(&old, 7, &new), 7);
+}
+
+#[test]
+fn test_map_position_falls_back_to_relative_place() {
+    let old = words("a b c d e f g h i j");
+    let new = words("k l m n o");
+    assert_eq!(map_position(&old, 6, &new), 3);
+    assert_eq!(map_position(&old, 6, &Vec::<&str>::new()), 0);
+}
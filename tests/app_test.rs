@@ -1,5 +1,27 @@
-use rsvp_term::app::{App, ViewMode};
-use rsvp_term::types::{BlockContext, TimedToken, TimingHint, Token, TokenStyle};
+use std::collections::HashMap;
+
+use rsvp_term::app::{App, HistoryEntry, Overlay, ViewMode, FOOTNOTE_LOOKBACK, LINK_LOOKBACK};
+use rsvp_term::parser::{Location, ParseWarning, ParsedDocument};
+use rsvp_term::types::{
+    BlockContext, CodeBlock, DocumentMetadata, Footnote, Section, TimedToken, TimingHint, Token,
+    TokenStyle,
+};
+
+/// A part to append, from zero-based positions
+fn part(
+    tokens: Vec<TimedToken>,
+    sections: Vec<Section>,
+    footnotes: Vec<Footnote>,
+    anchors: HashMap<String, usize>,
+) -> ParsedDocument {
+    ParsedDocument {
+        tokens: tokens.into_iter().map(|t| t.token).collect(),
+        sections,
+        footnotes,
+        anchors,
+        ..ParsedDocument::default()
+    }
+}
 
 fn make_timed_token(word: &str) -> TimedToken {
     TimedToken {
@@ -88,3 +110,648 @@ fn test_app_view_mode_toggle() {
     app.toggle_outline();
     assert_eq!(app.view_mode(), ViewMode::Reading);
 }
+
+#[test]
+fn test_app_big_text_toggle() {
+    let tokens = vec![make_timed_token("hello")];
+    let mut app = App::new(tokens, vec![]);
+
+    assert!(!app.big_text_enabled);
+    app.toggle_big_text();
+    assert!(app.big_text_enabled);
+}
+
+#[test]
+fn test_app_seek_and_jump() {
+    let tokens = (0..10).map(|_| make_timed_token("word")).collect();
+    let mut app = App::new(tokens, vec![]);
+
+    app.seek(0.5);
+    assert_eq!(app.position(), 5);
+    app.seek(1.0);
+    assert_eq!(app.position(), 9);
+    app.jump_to(3);
+    assert_eq!(app.position(), 3);
+    app.jump_to(100);
+    assert_eq!(app.position(), 9);
+}
+
+#[test]
+fn test_app_pauses_at_code_block_and_opens_overlay() {
+    let mut code = make_timed_token("[code: rust, 2 lines]");
+    code.token.block = BlockContext::CodeBlock;
+    code.token.timing_hint.is_block_start = true;
+    let tokens = vec![make_timed_token("before"), code, make_timed_token("after")];
+    let mut app = App::new(tokens, vec![]);
+    app.set_code_blocks(vec![CodeBlock {
+        language: Some("rust".to_string()),
+        content: "fn main() {}\nmain();".to_string(),
+        token_start: 1,
+        token_end: 2,
+    }]);
+
+    app.open_code_overlay();
+    assert_eq!(app.overlay(), None, "no code block under the first word");
+
+    app.advance();
+    assert!(app.is_paused());
+    assert_eq!(app.current_code_block(), Some(0));
+
+    app.open_code_overlay();
+    assert_eq!(
+        app.overlay(),
+        Some(Overlay::Code {
+            index: 0,
+            scroll: 0
+        })
+    );
+    app.scroll_overlay_down();
+    app.scroll_overlay_down();
+    assert_eq!(
+        app.overlay(),
+        Some(Overlay::Code {
+            index: 0,
+            scroll: 1
+        })
+    );
+    app.close_overlay();
+    assert_eq!(app.overlay(), None);
+}
+
+#[test]
+fn test_app_code_pause_can_be_disabled() {
+    let mut code = make_timed_token("[code: 1 line]");
+    code.token.block = BlockContext::CodeBlock;
+    code.token.timing_hint.is_block_start = true;
+    let mut app = App::new(vec![make_timed_token("before"), code], vec![]);
+    app.pause_on_code = false;
+
+    app.advance();
+    assert!(!app.is_paused());
+}
+
+#[test]
+fn test_app_pause_on_images() {
+    let figure = |word: &str, start: bool| {
+        let mut token = make_timed_token(word);
+        token.token.style = TokenStyle::Image;
+        token.token.block = BlockContext::Figure;
+        token.token.timing_hint.is_block_start = start;
+        token
+    };
+    let tokens = vec![
+        make_timed_token("see"),
+        figure("A", true),
+        figure("cat", false),
+    ];
+
+    let mut app = App::new(tokens.clone(), vec![]);
+    app.advance();
+    assert!(!app.is_paused(), "images don't pause by default");
+
+    let mut app = App::new(tokens, vec![]);
+    app.pause_on_images = true;
+    app.advance();
+    assert!(app.is_paused());
+
+    app.toggle_pause();
+    app.advance();
+    assert!(!app.is_paused(), "only the first figure word pauses");
+}
+
+#[test]
+fn test_app_footnote_overlay() {
+    let mut reference = make_timed_token("[1]");
+    reference.token.style = TokenStyle::FootnoteRef(0);
+    let mut tokens = vec![make_timed_token("claim"), reference];
+    tokens.extend((0..10).map(|_| make_timed_token("word")));
+    let mut app = App::new(tokens, vec![]);
+    app.set_footnotes(vec![Footnote {
+        label: "1".to_string(),
+        tokens: vec![
+            make_timed_token("cited").token,
+            make_timed_token("source").token,
+        ],
+    }]);
+
+    app.open_footnote_overlay();
+    assert_eq!(app.overlay(), None, "reference not reached yet");
+
+    app.advance();
+    app.advance();
+    assert_eq!(app.current_footnote(), Some(0));
+    app.open_footnote_overlay();
+    assert_eq!(
+        app.overlay(),
+        Some(Overlay::Footnote {
+            index: 0,
+            word: 0,
+            resume: true
+        })
+    );
+    assert!(!app.is_paused(), "the note is read word by word");
+    app.close_overlay();
+    assert_eq!(app.overlay(), None);
+    assert!(!app.is_paused(), "closing resumes the document");
+    assert_eq!(app.position(), 2);
+
+    for _ in 0..FOOTNOTE_LOOKBACK {
+        app.advance();
+    }
+    assert_eq!(app.current_footnote(), None, "reference too far back");
+}
+
+#[test]
+fn test_app_footnote_is_read_then_returns() {
+    let mut reference = make_timed_token("[1]");
+    reference.token.style = TokenStyle::FootnoteRef(0);
+    let tokens = vec![
+        make_timed_token("claim"),
+        reference,
+        make_timed_token("more"),
+    ];
+    let mut app = App::new(tokens, vec![]);
+    app.set_footnotes(vec![Footnote {
+        label: "1".to_string(),
+        tokens: vec![
+            make_timed_token("cited").token,
+            make_timed_token("source").token,
+        ],
+    }]);
+    app.advance();
+
+    app.open_footnote_overlay();
+    assert!(app.is_playing(), "the note plays while open");
+    assert_eq!(app.active_token().map(|t| t.word.as_str()), Some("cited"));
+
+    app.tick();
+    assert_eq!(app.active_token().map(|t| t.word.as_str()), Some("source"));
+    assert_eq!(app.position(), 1, "document position is untouched");
+
+    app.tick();
+    assert_eq!(app.overlay(), None);
+    assert_eq!(app.active_token().map(|t| t.word.as_str()), Some("[1]"));
+    assert!(!app.is_paused(), "reading resumes where it was");
+
+    // Opened while paused, the document stays paused after the note
+    app.toggle_pause();
+    app.open_footnote_overlay();
+    assert!(!app.is_paused());
+    app.close_overlay();
+    assert!(app.is_paused());
+}
+
+#[test]
+fn test_current_link_looks_back() {
+    let mut tokens: Vec<TimedToken> = (0..20)
+        .map(|i| make_timed_token(&format!("w{i}")))
+        .collect();
+    tokens[2].token.style = TokenStyle::Link("other.md".to_string());
+    let mut app = App::new(tokens, vec![]);
+
+    assert_eq!(app.current_link(), None);
+    app.jump_to(2 + LINK_LOOKBACK);
+    assert_eq!(app.current_link(), Some("other.md"));
+    app.jump_to(3 + LINK_LOOKBACK);
+    assert_eq!(app.current_link(), None);
+}
+
+#[test]
+fn test_follow_anchor_and_back() {
+    let tokens: Vec<TimedToken> = (0..10)
+        .map(|i| make_timed_token(&format!("w{i}")))
+        .collect();
+    let mut app = App::new(tokens, vec![]);
+    app.set_anchors([("usage".to_string(), 7)].into_iter().collect());
+    app.jump_to(3);
+
+    assert!(!app.follow_anchor("", Some("missing")));
+    assert!(app.history().is_empty());
+
+    assert!(app.follow_anchor("", Some("Usage")));
+    assert_eq!(app.position(), 7);
+    assert_eq!(
+        app.pop_history(),
+        Some(HistoryEntry {
+            path: None,
+            position: 3
+        })
+    );
+    assert_eq!(app.pop_history(), None);
+}
+
+#[test]
+fn test_replace_document_keeps_settings_and_history() {
+    let mut app = App::new(
+        vec![make_timed_token("old"), make_timed_token("doc")],
+        vec![],
+    );
+    app.set_wpm(450);
+    app.set_metadata(DocumentMetadata {
+        source_path: Some("old.md".into()),
+        ..Default::default()
+    });
+    app.jump_to(1);
+    app.push_history();
+    app.toggle_outline();
+
+    app.replace_document(vec![make_timed_token("new")], vec![]);
+
+    assert_eq!(app.position(), 0);
+    assert_eq!(app.view_mode(), ViewMode::Reading);
+    assert_eq!(app.wpm(), 450);
+    assert_eq!(app.metadata(), &DocumentMetadata::default());
+    assert_eq!(app.history()[0].path.as_deref(), Some("old.md".as_ref()));
+    assert_eq!(app.current_token().unwrap().token.word, "new");
+}
+
+#[test]
+fn test_current_file_in_book() {
+    use rsvp_term::types::SourceFile;
+
+    let tokens: Vec<TimedToken> = (0..6).map(|i| make_timed_token(&format!("w{i}"))).collect();
+    let mut app = App::new(tokens, vec![]);
+    assert!(app.current_file().is_none());
+
+    let file = |name: &str, token_start, token_end| SourceFile {
+        path: name.into(),
+        title: name.to_string(),
+        token_start,
+        token_end,
+    };
+    app.set_files(vec![file("a.md", 0, 3), file("b.md", 3, 6)]);
+    assert_eq!(app.current_file().unwrap().title, "a.md");
+    app.jump_to(4);
+    assert_eq!(app.current_file().unwrap().title, "b.md");
+}
+
+/// Show `words` as queue entry `index`, as the binary does after
+/// parsing it
+fn open_queued(app: &mut App, index: usize, words: &[&str]) {
+    let path = app.queue()[index].path.clone();
+    app.replace_document(words.iter().map(|w| make_timed_token(w)).collect(), vec![]);
+    app.set_metadata(DocumentMetadata {
+        source_path: Some(path),
+        ..Default::default()
+    });
+    app.enter_queue_entry(index);
+}
+
+#[test]
+fn test_queue_advances_at_end_of_document() {
+    let mut app = App::new(vec![], vec![]);
+    app.set_queue(vec!["a.md".into(), "b.md".into()]);
+    open_queued(&mut app, 0, &["one", "two"]);
+
+    app.advance();
+    assert_eq!(app.take_pending_document(), None);
+    app.advance();
+    assert_eq!(app.take_pending_document(), Some(1));
+
+    open_queued(&mut app, 1, &["three"]);
+    assert_eq!(app.queue_index(), 1);
+    assert_eq!(app.queue()[0].token_count, Some(2));
+    assert!(app.queue()[0].is_finished());
+
+    // Nothing follows the last document
+    app.advance();
+    assert_eq!(app.take_pending_document(), None);
+}
+
+#[test]
+fn test_queue_resumes_where_document_was_left() {
+    let mut app = App::new(vec![], vec![]);
+    app.set_queue(vec!["a.md".into(), "b.md".into()]);
+    open_queued(&mut app, 0, &["w0", "w1", "w2", "w3"]);
+    app.jump_to(2);
+
+    app.next_document();
+    assert_eq!(app.take_pending_document(), Some(1));
+    open_queued(&mut app, 1, &["x0", "x1"]);
+    assert_eq!(app.position(), 0);
+    assert!((app.queue_progress(0) - 2.0 / 3.0).abs() < f64::EPSILON);
+
+    app.previous_document();
+    assert_eq!(app.take_pending_document(), Some(0));
+    open_queued(&mut app, 0, &["w0", "w1", "w2", "w3"]);
+    assert_eq!(app.position(), 2);
+}
+
+#[test]
+fn test_queue_skips_unreadable_documents() {
+    let mut app = App::new(vec![], vec![]);
+    app.set_queue(vec!["a.md".into(), "broken.epub".into(), "c.md".into()]);
+    open_queued(&mut app, 0, &["only"]);
+    app.set_queue_error(1, "not a zip archive".to_string());
+
+    app.advance();
+    assert_eq!(app.take_pending_document(), Some(2));
+}
+
+#[test]
+fn test_queue_moves_past_document_that_fails_to_open() {
+    let mut app = App::new(vec![], vec![]);
+    app.set_queue(vec!["a.md".into(), "gone.md".into(), "c.md".into()]);
+    open_queued(&mut app, 0, &["only"]);
+
+    app.advance();
+    assert_eq!(app.take_pending_document(), Some(1));
+    app.skip_queue_entry(1, "file not found".to_string());
+    assert_eq!(app.queue()[1].error.as_deref(), Some("file not found"));
+    assert_eq!(app.notice(), Some("Skipped gone.md: file not found"));
+    assert_eq!(app.take_pending_document(), Some(2));
+}
+
+#[test]
+fn test_queue_view_selects_document() {
+    let mut app = App::new(vec![], vec![]);
+    app.set_queue(vec!["a.md".into(), "b.md".into(), "c.md".into()]);
+    open_queued(&mut app, 0, &["word"]);
+
+    app.toggle_queue();
+    assert_eq!(app.view_mode(), ViewMode::Queue);
+    app.queue_down();
+    app.queue_down();
+    app.queue_down();
+    assert_eq!(app.queue_selection(), 2);
+    app.open_queue_selection();
+    assert_eq!(app.view_mode(), ViewMode::Reading);
+    assert_eq!(app.take_pending_document(), Some(2));
+}
+
+#[test]
+fn test_reading_position_is_recorded_and_resumed() {
+    let words = ["w0", "w1", "w2", "w3"];
+    let tokens = || {
+        words
+            .iter()
+            .map(|w| make_timed_token(w))
+            .collect::<Vec<_>>()
+    };
+    let metadata = DocumentMetadata {
+        title: Some("Draft".to_string()),
+        source_path: Some("draft.md".into()),
+        ..Default::default()
+    };
+
+    let mut app = App::new(tokens(), vec![]);
+    app.set_metadata(metadata.clone());
+    app.jump_to(2);
+    app.record_reading();
+    let saved = app.library().to_vec();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0].title, "Draft");
+    assert_eq!(saved[0].position, 2);
+    assert_eq!(saved[0].token_count, 4);
+
+    // A later session picks up where this one stopped
+    let mut app = App::new(tokens(), vec![]);
+    app.set_library(saved);
+    app.set_metadata(metadata);
+    app.resume_saved_position();
+    assert_eq!(app.position(), 2);
+}
+
+#[test]
+fn test_finished_document_starts_over() {
+    let mut app = App::new(vec![make_timed_token("a"), make_timed_token("b")], vec![]);
+    app.set_metadata(DocumentMetadata {
+        source_path: Some("done.md".into()),
+        ..Default::default()
+    });
+    app.jump_to(1);
+    app.record_reading();
+    app.jump_to(0);
+    app.resume_saved_position();
+    assert_eq!(app.position(), 0);
+}
+
+#[test]
+fn test_library_filter_and_selection() {
+    use rsvp_term::library::LibraryEntry;
+
+    let entry = |title: &str| LibraryEntry {
+        key: format!("title:{title}"),
+        path: format!("/docs/{title}.md").into(),
+        title: title.to_string(),
+        position: 0,
+        token_count: 10,
+        last_read: 0,
+    };
+    let mut app = App::new(vec![], vec![]);
+    app.set_library(vec![entry("Cache design"), entry("Release notes")]);
+    app.open_library();
+    assert_eq!(app.view_mode(), ViewMode::Library);
+    assert_eq!(app.filtered_library(), vec![0, 1]);
+
+    app.library_down();
+    assert_eq!(app.selected_library_entry().unwrap().title, "Release notes");
+
+    assert!(!app.is_filtering_library(), "letters are commands until /");
+    app.start_library_filter();
+    assert!(app.is_filtering_library());
+    for c in "relnot".chars() {
+        app.library_input(c);
+    }
+    assert_eq!(app.filtered_library(), vec![1]);
+    assert_eq!(app.library_selection(), 0);
+    assert_eq!(app.selected_library_entry().unwrap().title, "Release notes");
+
+    app.library_backspace();
+    assert_eq!(app.library_filter(), "relno");
+
+    app.stop_library_filter();
+    assert!(!app.is_filtering_library());
+    assert_eq!(app.library_filter(), "relno", "the filter stays applied");
+    app.open_library();
+    assert_eq!(app.library_filter(), "");
+}
+
+#[test]
+fn test_reload_document_keeps_place_and_view() {
+    let tokens = |text: &str| {
+        text.split_whitespace()
+            .map(make_timed_token)
+            .collect::<Vec<_>>()
+    };
+    let mut app = App::new(tokens("one two three four five six"), vec![]);
+    app.set_wpm(500);
+    app.jump_to(3);
+    app.toggle_page();
+    app.push_history();
+
+    app.reload_document(tokens("intro text one two three four five six"), vec![]);
+
+    assert_eq!(app.current_token().unwrap().token.word, "four");
+    assert_eq!(app.page_cursor(), 5);
+    assert_eq!(app.view_mode(), ViewMode::Page);
+    assert_eq!(app.wpm(), 500);
+    assert_eq!(app.history().len(), 1);
+}
+
+#[test]
+fn test_notice_is_recent_after_set() {
+    let mut app = App::new(vec![], vec![]);
+    assert_eq!(app.recent_notice(), None);
+    app.set_notice("reloaded".to_string());
+    assert_eq!(app.notice(), Some("reloaded"));
+    assert_eq!(app.recent_notice(), Some("reloaded"));
+}
+
+#[test]
+fn test_warnings_from_loaded_parts_accumulate() {
+    let skipped = |index| ParseWarning::new(Location::chapter(index, "Notes"), "skipped chapter");
+    let mut app = App::new(vec![], vec![]);
+    app.set_warnings(vec![skipped(1)]);
+    app.add_warnings(vec![]);
+    assert_eq!(app.notice(), None);
+
+    app.add_warnings(vec![skipped(4)]);
+    assert_eq!(app.warnings().len(), 2);
+    assert_eq!(
+        app.recent_notice(),
+        Some("chapter 5 \"Notes\": skipped chapter")
+    );
+}
+
+#[test]
+fn test_append_document_extends_sections_and_footnotes() {
+    let section = |title: &str, token_start, token_end| Section {
+        title: title.to_string(),
+        level: 1,
+        token_start,
+        token_end,
+    };
+    let mut app = App::new(vec![], vec![]);
+    app.append_document(part(
+        vec![make_timed_token("Intro"), make_timed_token("text")],
+        vec![section("Intro", 0, 2)],
+        vec![Footnote {
+            label: "1".to_string(),
+            tokens: vec![],
+        }],
+        HashMap::new(),
+    ));
+    app.jump_to(1);
+
+    let mut note_ref = make_timed_token("ref");
+    note_ref.token.style = TokenStyle::FootnoteRef(0);
+    app.append_document(part(
+        vec![make_timed_token("More"), note_ref],
+        vec![section("More", 0, 2)],
+        vec![Footnote {
+            label: "2".to_string(),
+            tokens: vec![],
+        }],
+        HashMap::from([("more".to_string(), 0)]),
+    ));
+
+    assert_eq!(app.position(), 1);
+    assert_eq!(app.anchors().get("more"), Some(&2));
+    assert_eq!(app.tokens().len(), 4);
+    assert_eq!(app.sections()[0].token_end, 2);
+    assert_eq!(app.sections()[1].token_start, 2);
+    assert_eq!(app.sections()[1].token_end, 4);
+    assert_eq!(app.tokens()[3].token.style, TokenStyle::FootnoteRef(1));
+    assert_eq!(app.metadata().word_count, 4);
+
+    // Playback waits at the end until more arrives
+    app.jump_to(3);
+    app.advance();
+    assert_eq!(app.position(), 3);
+}
+
+#[test]
+fn test_append_document_merges_footnotes_by_label() {
+    let mut note_ref = make_timed_token("ref");
+    note_ref.token.style = TokenStyle::FootnoteRef(0);
+    let mut app = App::new(vec![], vec![]);
+    app.append_document(part(
+        vec![note_ref.clone()],
+        vec![],
+        vec![Footnote {
+            label: "7".to_string(),
+            tokens: vec![],
+        }],
+        HashMap::new(),
+    ));
+
+    // The note arrives with a later part
+    app.append_document(part(
+        vec![make_timed_token("end")],
+        vec![],
+        vec![Footnote {
+            label: "7".to_string(),
+            tokens: vec![make_timed_token("Cited").token],
+        }],
+        HashMap::new(),
+    ));
+
+    assert_eq!(app.footnotes().len(), 1);
+    assert_eq!(app.footnotes()[0].text(), "Cited");
+    assert_eq!(app.tokens()[0].token.style, TokenStyle::FootnoteRef(0));
+}
+
+#[test]
+fn test_loading_document_estimates_and_awaits_jumps() {
+    use rsvp_term::types::PendingSection;
+
+    let mut app = App::new(
+        vec![make_timed_token("One"), make_timed_token("two")],
+        vec![],
+    );
+    app.set_queue(vec!["a.epub".into(), "b.epub".into()]);
+    app.set_loading(
+        10,
+        vec![PendingSection {
+            title: "Later".to_string(),
+            level: 2,
+            part: 3,
+        }],
+    );
+    assert!(app.is_loading());
+    assert_eq!(app.word_count(), 10);
+    assert_eq!(app.outline_entries(), vec![("Later", 2)]);
+
+    // Playback waits for the next part instead of moving to the next document
+    app.jump_to(1);
+    app.advance();
+    assert_eq!(app.position(), 1);
+    assert_eq!(app.take_pending_document(), None);
+
+    // Jumping to a section not loaded yet waits for its part
+    app.toggle_outline();
+    app.jump_to_section();
+    assert_eq!(app.awaited_part(), Some(3));
+    assert_eq!(app.view_mode(), ViewMode::Reading);
+    app.append_document(part(
+        vec![make_timed_token("Later"), make_timed_token("on")],
+        vec![Section {
+            title: "Later".to_string(),
+            level: 2,
+            token_start: 1,
+            token_end: 2,
+        }],
+        vec![],
+        HashMap::new(),
+    ));
+    assert_eq!(app.position(), 3);
+    assert_eq!(app.awaited_part(), None);
+
+    // So does a position past the loaded words
+    app.jump_to(6);
+    assert_eq!(app.position(), 3);
+    assert_eq!(app.awaited_position(), Some(6));
+    app.append_document(part(
+        (0..4).map(|_| make_timed_token("word")).collect(),
+        vec![],
+        vec![],
+        HashMap::new(),
+    ));
+    assert_eq!(app.position(), 6);
+    assert_eq!(app.awaited_position(), None);
+
+    app.finish_loading();
+    assert!(!app.is_loading());
+    assert_eq!(app.word_count(), 8);
+}
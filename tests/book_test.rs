@@ -0,0 +1,208 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::temp_dir;
+use rsvp_term::parser::{BookParser, DocumentParser, Encoding, MarkdownOptions, ParseError};
+use rsvp_term::types::TokenStyle;
+
+fn write(dir: &Path, file: &str, content: &str) {
+    let path = dir.join(file);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_mdbook_summary_order_and_nesting() {
+    let root = temp_dir("book", "mdbook");
+    write(
+        &root,
+        "book.toml",
+        "[book]\ntitle = \"Handbook\"\nauthors = [\"Ops\"]\n",
+    );
+    write(
+        &root,
+        "src/SUMMARY.md",
+        "# Summary\n\n[Intro](README.md)\n\n# Part\n\n- [Setup](setup.md)\n    - [Linux](setup/linux.md)\n- [Draft]()\n- [Usage](usage.md)\n",
+    );
+    write(&root, "src/README.md", "# Welcome\n\nHello");
+    write(
+        &root,
+        "src/setup.md",
+        "Install it\n\n## Requirements\n\nRust",
+    );
+    write(&root, "src/setup/linux.md", "Use apt");
+    write(&root, "src/usage.md", "Run it");
+
+    let doc = BookParser::new().parse_file(&root).unwrap();
+
+    let words: Vec<&str> = doc.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(
+        words,
+        vec![
+            "Welcome",
+            "Hello",
+            "Install",
+            "it",
+            "Requirements",
+            "Rust",
+            "Use",
+            "apt",
+            "Run",
+            "it"
+        ]
+    );
+    let outline: Vec<(&str, u8, usize)> = doc
+        .sections
+        .iter()
+        .map(|s| (s.title.as_str(), s.level, s.token_start))
+        .collect();
+    assert_eq!(
+        outline,
+        vec![
+            ("Intro", 1, 0),
+            ("Setup", 1, 2),
+            ("Requirements", 3, 4),
+            ("Linux", 2, 6),
+            ("Usage", 1, 8),
+        ]
+    );
+    assert_eq!(doc.sections[1].token_end, 4);
+
+    let files: Vec<(PathBuf, usize, usize)> = doc
+        .files
+        .iter()
+        .map(|f| (f.path.clone(), f.token_start, f.token_end))
+        .collect();
+    assert_eq!(files[1], (root.join("src/setup.md"), 2, 6));
+    assert_eq!(files.len(), 4);
+
+    assert_eq!(doc.metadata.title.as_deref(), Some("Handbook"));
+    assert_eq!(doc.metadata.authors, vec!["Ops"]);
+    assert_eq!(doc.metadata.word_count, 10);
+    assert_eq!(doc.metadata.source_path.as_deref(), Some(root.as_path()));
+
+    let setup = root.join("src/setup.md").to_string_lossy().into_owned();
+    assert_eq!(doc.anchors.get(&setup), Some(&2));
+    assert_eq!(doc.anchors.get(&format!("{setup}#requirements")), Some(&4));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_directory_read_in_natural_order() {
+    let root = temp_dir("book", "natural");
+    write(&root, "ch10.md", "ten");
+    write(&root, "ch2.md", "two");
+    write(&root, "ch1.md", "---\ntitle: First\n---\none");
+    write(&root, ".hidden/ch0.md", "hidden");
+    write(&root, "notes.txt", "ignored");
+
+    let doc = BookParser::new().parse_file(&root).unwrap();
+
+    let words: Vec<&str> = doc.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["one", "two", "ten"]);
+    let titles: Vec<&str> = doc.sections.iter().map(|s| s.title.as_str()).collect();
+    assert_eq!(titles, vec!["First", "ch2", "ch10"]);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_index_links_give_order() {
+    let root = temp_dir("book", "index");
+    write(
+        &root,
+        "index.md",
+        "Start with [the end](z.md) then [the start](a.md)",
+    );
+    write(&root, "a.md", "alpha");
+    write(&root, "z.md", "omega");
+
+    let doc = BookParser::new().parse_file(&root).unwrap();
+
+    let words: Vec<&str> = doc.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(
+        words,
+        vec!["Start", "with", "the", "end", "then", "the", "start", "omega", "alpha"]
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_footnotes_renumbered_across_files() {
+    let root = temp_dir("book", "footnotes");
+    write(&root, "SUMMARY.md", "- [One](one.md)\n- [Two](two.md)\n");
+    write(&root, "one.md", "A[^1]\n\n[^1]: First note");
+    write(&root, "two.md", "B[^1]\n\n[^1]: Second note");
+
+    // Passing SUMMARY.md reads its directory as a book
+    assert!(BookParser::is_book(&root.join("SUMMARY.md")));
+    let doc = BookParser::new()
+        .parse_file(&root.join("SUMMARY.md"))
+        .unwrap();
+
+    let refs: Vec<usize> = doc
+        .tokens
+        .iter()
+        .filter_map(|t| match &t.style {
+            TokenStyle::FootnoteRef(index) => Some(*index),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(refs, vec![0, 1]);
+    assert_eq!(doc.footnotes[1].text(), "Second note");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_summary_is_decoded_like_the_chapters() {
+    let root = temp_dir("book", "encoding");
+    std::fs::write(root.join("SUMMARY.md"), b"- [Caf\xe9](cafe.md)\n").unwrap();
+    std::fs::write(root.join("cafe.md"), b"Cr\xe8me").unwrap();
+
+    let doc = BookParser::new().parse_file(&root).unwrap();
+    let words: Vec<&str> = doc.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Crème"]);
+    assert_eq!(doc.sections[0].title, "Café");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_unreadable_chapters_are_skipped_with_a_warning() {
+    let root = temp_dir("book", "unreadable");
+    write(&root, "SUMMARY.md", "- [One](one.md)\n- [Two](two.md)\n");
+    write(&root, "one.md", "First");
+    std::fs::write(root.join("two.md"), b"Caf\xe9").unwrap();
+
+    let parser = BookParser::with_options(MarkdownOptions {
+        encoding: Some(Encoding::Utf8),
+        ..MarkdownOptions::default()
+    });
+    let doc = parser.parse_file(&root).unwrap();
+    let words: Vec<&str> = doc.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["First"]);
+    assert_eq!(doc.warnings.len(), 1);
+    assert_eq!(doc.warnings[0].location.chapter_index, Some(1));
+    assert_eq!(doc.warnings[0].location.chapter.as_deref(), Some("Two"));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_empty_directory_is_an_error() {
+    let root = temp_dir("book", "empty");
+    let error = BookParser::new().parse_file(&root).unwrap_err();
+    assert!(matches!(error, ParseError::EmptyDocument { .. }));
+    assert_eq!(error.exit_code(), 8);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_book_cannot_be_read_from_bytes() {
+    let error = BookParser::new().parse_bytes(b"# One").unwrap_err();
+    assert!(matches!(error, ParseError::UnsupportedFormat { .. }));
+}
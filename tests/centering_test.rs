@@ -2,6 +2,7 @@ use insta::assert_debug_snapshot;
 
 /// Test data for centering calculations
 #[derive(Debug)]
+#[allow(dead_code)]
 struct CenteringCase {
     content_width: usize,
     available_width: usize,
@@ -31,6 +32,7 @@ fn generate_visual(content: &str, available_width: usize) -> String {
 }
 
 #[test]
+#[allow(clippy::useless_vec)]
 fn test_centering_scenarios() {
     let cases = vec![
         CenteringCase {
@@ -77,6 +79,7 @@ fn test_centering_scenarios() {
 }
 
 #[test]
+#[allow(clippy::useless_vec)]
 fn test_visual_centering_examples() {
     let examples = vec![
         ("# Title", 80),
@@ -98,6 +101,7 @@ fn test_visual_centering_examples() {
 }
 
 #[test]
+#[allow(clippy::useless_vec)]
 fn test_narrow_terminal_centering() {
     // In narrow terminals, even short content might exceed threshold
     let cases = vec![
@@ -1,4 +1,14 @@
-use std::process::Command;
+mod common;
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use common::epub::{chapter, write_book};
+use common::temp_dir;
+use rsvp_term::library::{self, LibraryEntry};
+use rsvp_term::types::DocumentMetadata;
 
 #[test]
 fn test_no_hint_chars_flag_accepted() {
@@ -27,3 +37,182 @@ fn test_env_var_args_parsed() {
         .expect("Failed to run");
     assert!(output.status.success());
 }
+
+#[test]
+fn test_library_flag_conflicts_with_files() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rsvp-term"))
+        .args(["--library", "notes.md"])
+        .output()
+        .expect("Failed to run");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_queue_of_unreadable_files_reports_each() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rsvp-term"))
+        .args(["missing-one.md", "missing-two.md"])
+        .output()
+        .expect("Failed to run");
+    // Exit code for files that can't be read
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("missing-one.md"), "{stderr}");
+    assert!(stderr.contains("missing-two.md"), "{stderr}");
+}
+
+#[test]
+fn test_usage_errors_exit_with_two() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rsvp-term"))
+        .args(["--export-md", "one.epub", "two.epub"])
+        .output()
+        .expect("Failed to run");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_check_reports_problems_on_stderr() {
+    let dir = temp_dir("cli", "check");
+    let good = dir.join("good.md");
+    let bad = dir.join("bad.epub");
+    std::fs::write(&good, "Fine words.\n").unwrap();
+    std::fs::write(&bad, "not a zip").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rsvp-term"))
+        .arg("--check")
+        .args([&good, &bad])
+        .output()
+        .expect("Failed to run");
+    assert_eq!(output.status.code(), Some(5));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stdout.contains("good.md: 2 words"), "{stdout}");
+    assert!(!stdout.contains("bad.epub"), "{stdout}");
+    assert!(stderr.contains("bad.epub"), "{stderr}");
+}
+
+/// Run the binary in a pseudo-terminal through util-linux `script`, typing
+/// `keys` one by one after it has started. Returns true if it exited
+/// successfully.
+fn run_in_terminal(args: &[&str], state: &Path, keys: &[&str]) -> bool {
+    let command = format!(
+        "stty cols 80 rows 24; exec '{}' {}",
+        env!("CARGO_BIN_EXE_rsvp-term"),
+        args.join(" ")
+    );
+    let mut child = Command::new("script")
+        .args(["-qec", &command, "/dev/null"])
+        .env("XDG_STATE_HOME", state)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .expect("util-linux script is needed to run in a terminal");
+    let mut stdin = child.stdin.take().unwrap();
+    for key in keys {
+        std::thread::sleep(Duration::from_millis(400));
+        stdin.write_all(key.as_bytes()).unwrap();
+        stdin.flush().unwrap();
+    }
+    child.wait().unwrap().success()
+}
+
+/// A ten-word document with a saved position at word 5
+fn read_halfway(name: &str) -> (PathBuf, PathBuf) {
+    let dir = temp_dir("cli", name);
+    let doc = dir.join("doc.md");
+    std::fs::write(&doc, "w0 w1 w2 w3 w4 w5 w6 w7 w8 w9").unwrap();
+    let doc = doc.canonicalize().unwrap();
+    let key = DocumentMetadata {
+        source_path: Some(doc.clone()),
+        ..Default::default()
+    }
+    .persistence_key();
+    let entry = LibraryEntry {
+        key,
+        path: doc.clone(),
+        title: "doc.md".to_string(),
+        position: 5,
+        token_count: 10,
+        last_read: library::now(),
+    };
+    let state = dir.join("state");
+    library::save(&state.join("rsvp-term/library.tsv"), &[entry]).unwrap();
+    (doc, state)
+}
+
+fn saved_position(state: &Path) -> usize {
+    let entries = library::load(&state.join("rsvp-term/library.tsv")).unwrap();
+    entries[0].position
+}
+
+#[test]
+#[ignore = "drives the reader in a pseudo-terminal through util-linux script, with timed keys"]
+fn test_library_entry_resumes_at_saved_position() {
+    let (_, state) = read_halfway("resume");
+    assert!(run_in_terminal(
+        &["--library", "--wpm", "100"],
+        &state,
+        &["\r", "q"]
+    ));
+    let position = saved_position(&state);
+    assert!((5..8).contains(&position), "stopped at {position}");
+}
+
+#[test]
+#[ignore = "drives the reader in a pseudo-terminal through util-linux script, with timed keys"]
+fn test_file_argument_starts_from_the_beginning() {
+    let (doc, state) = read_halfway("start");
+    let doc = doc.to_string_lossy();
+    assert!(run_in_terminal(
+        &[doc.as_ref(), "--wpm", "100"],
+        &state,
+        &["q"]
+    ));
+    assert!(saved_position(&state) < 5);
+}
+
+#[test]
+#[ignore = "drives the reader in a pseudo-terminal through util-linux script, with timed keys"]
+fn test_no_library_leaves_library_untouched() {
+    let (doc, state) = read_halfway("opt-out");
+    let library = state.join("rsvp-term/library.tsv");
+    let before = std::fs::read_to_string(&library).unwrap();
+    let doc = doc.to_string_lossy();
+    assert!(run_in_terminal(
+        &[doc.as_ref(), "--no-library"],
+        &state,
+        &["q"]
+    ));
+    assert_eq!(std::fs::read_to_string(&library).unwrap(), before);
+}
+
+#[test]
+fn test_no_library_needs_a_file() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rsvp-term"))
+        .arg("--no-library")
+        .stdin(Stdio::null())
+        .output()
+        .expect("Failed to run");
+    // A usage error, like clap's own
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_strict_stops_at_a_malformed_later_chapter() {
+    let broken = "<parsererror><div>error on line 2 at column 5: Opening and ending tag mismatch\
+                  </div></parsererror><p>Partly <b>broken</p>";
+    let chapters = [
+        ("one.xhtml", chapter("<p>Fine.</p>")),
+        ("two.xhtml", chapter(broken)),
+    ];
+    let book = write_book("cli-strict", &chapters, &[], "");
+    let output = Command::new(env!("CARGO_BIN_EXE_rsvp-term"))
+        .arg(&book)
+        .args(["--strict", "--no-library"])
+        .output()
+        .expect("Failed to run");
+    // Exit code for malformed documents, before the reader starts
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("chapter 2"), "{stderr}");
+    assert!(stderr.contains("line 2, column 5"), "{stderr}");
+}
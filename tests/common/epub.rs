@@ -0,0 +1,114 @@
+//! EPUB books built in memory for tests
+
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Zip archive with the files stored uncompressed, as EPUB requires of
+/// the leading `mimetype`
+fn zip(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    for (name, content) in files {
+        writer.start_file(*name, options).unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+pub fn chapter(body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\">\
+         <head><title>Chapter</title></head><body>{body}</body></html>"
+    )
+}
+
+/// EPUB 2 book with an NCX table of contents and PNG images, written to
+/// `book.epub` in a temporary directory from [`temp_dir`](super::temp_dir)
+pub fn write_book(
+    name: &str,
+    chapters: &[(&str, String)],
+    images: &[(&str, &str)],
+    ncx_points: &str,
+) -> PathBuf {
+    write_book_with(name, chapters, images, ncx_points, &[])
+}
+
+/// Like [`write_book`], with `extra` files such as `META-INF/encryption.xml`
+/// added to the archive
+pub fn write_book_with(
+    name: &str,
+    chapters: &[(&str, String)],
+    images: &[(&str, &str)],
+    ncx_points: &str,
+    extra: &[(&str, &str)],
+) -> PathBuf {
+    let mut manifest: String = chapters
+        .iter()
+        .enumerate()
+        .map(|(i, (file, _))| {
+            format!("<item id=\"c{i}\" href=\"{file}\" media-type=\"application/xhtml+xml\"/>")
+        })
+        .collect();
+    for (i, (file, _)) in images.iter().enumerate() {
+        manifest += &format!("<item id=\"img{i}\" href=\"{file}\" media-type=\"image/png\"/>");
+    }
+    let spine: String = (0..chapters.len())
+        .map(|i| format!("<itemref idref=\"c{i}\"/>"))
+        .collect();
+    let opf = format!(
+        "<?xml version=\"1.0\"?>\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"2.0\" unique-identifier=\"id\">\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\
+         <dc:title>Test Book</dc:title><dc:creator>A. Writer</dc:creator>\
+         <dc:language>en</dc:language><dc:identifier id=\"id\">test-{name}</dc:identifier>\
+         </metadata>\
+         <manifest><item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>{manifest}</manifest>\
+         <spine toc=\"ncx\">{spine}</spine></package>"
+    );
+    let ncx = format!(
+        "<?xml version=\"1.0\"?>\
+         <ncx xmlns=\"http://www.daisy.org/z3986/2005/ncx/\" version=\"2005-1\">\
+         <docTitle><text>Test Book</text></docTitle><navMap>{ncx_points}</navMap></ncx>"
+    );
+    let container = "<?xml version=\"1.0\"?>\
+         <container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\
+         <rootfiles><rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>\
+         </rootfiles></container>";
+
+    let contents: Vec<(String, &str)> = chapters
+        .iter()
+        .map(|(file, content)| (format!("OEBPS/{file}"), content.as_str()))
+        .chain(
+            images
+                .iter()
+                .map(|(file, content)| (format!("OEBPS/{file}"), *content)),
+        )
+        .collect();
+    let mut files = vec![
+        ("mimetype", "application/epub+zip"),
+        ("META-INF/container.xml", container),
+        ("OEBPS/content.opf", opf.as_str()),
+        ("OEBPS/toc.ncx", ncx.as_str()),
+    ];
+    files.extend(
+        contents
+            .iter()
+            .map(|(path, content)| (path.as_str(), *content)),
+    );
+    files.extend_from_slice(extra);
+
+    let path = super::temp_dir("epub", name).join("book.epub");
+    std::fs::write(&path, zip(&files)).unwrap();
+    path
+}
+
+pub fn nav_point(id: &str, order: usize, label: &str, src: &str, children: &str) -> String {
+    format!(
+        "<navPoint id=\"{id}\" playOrder=\"{order}\"><navLabel><text>{label}</text></navLabel>\
+         <content src=\"{src}\"/>{children}</navPoint>"
+    )
+}
@@ -0,0 +1,15 @@
+//! Helpers shared by the integration tests
+
+// Only some tests build EPUBs
+#[allow(dead_code)]
+pub mod epub;
+
+use std::path::PathBuf;
+
+/// Empty directory unique to one test, e.g. `temp_dir("vault", "embed")`
+pub fn temp_dir(kind: &str, name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rsvp-{kind}-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
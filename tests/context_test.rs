@@ -131,3 +131,13 @@ fn test_list_items_should_be_separate_lines() {
 
     assert_eq!(new_line_triggers.len(), 3);
 }
+
+#[test]
+fn test_status_bar_shows_estimated_length_while_loading() {
+    let mut app = create_long_test_app();
+    assert!(!render_to_string(&app, 100, 24).contains("(loading)"));
+
+    app.set_loading(5000, vec![]);
+    let output = render_to_string(&app, 100, 24);
+    assert!(output.contains("~5000 words (loading)"), "{output}");
+}
@@ -0,0 +1,430 @@
+mod common;
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use common::epub::{chapter, nav_point, write_book, write_book_with};
+use common::temp_dir;
+use rsvp_term::parser::{
+    ChapterLoader, DocumentParser, EpubOptions, EpubParser, ExportOptions, ParseError,
+    ParsedDocument,
+};
+use rsvp_term::types::{BlockContext, TokenStyle};
+
+fn sample_book(name: &str) -> PathBuf {
+    let chapters = [
+        (
+            "one.xhtml",
+            chapter(
+                "<h1>One</h1><p>It was <em>very</em> dark\
+                 <a epub:type=\"noteref\" href=\"notes.xhtml#n1\">1</a>.</p>\
+                 <h2 id=\"deep\">Deeper</h2><p>Down here.</p>",
+            ),
+        ),
+        ("two.xhtml", chapter("<h1>Two</h1><p>Morning came.</p>")),
+        (
+            "notes.xhtml",
+            chapter("<aside epub:type=\"endnote\" id=\"n1\"><p>Very dark indeed.</p></aside>"),
+        ),
+    ];
+    let ncx = nav_point(
+        "p1",
+        1,
+        "One",
+        "one.xhtml",
+        &nav_point("p2", 2, "Deeper", "one.xhtml#deep", ""),
+    ) + &nav_point("p3", 3, "Two", "two.xhtml", "");
+    write_book(name, &chapters, &[], &ncx)
+}
+
+fn words(doc: &ParsedDocument) -> Vec<&str> {
+    doc.tokens.iter().map(|t| t.word.as_str()).collect()
+}
+
+#[test]
+fn test_chapter_titles_are_read_without_doubling_headings() {
+    let chapters = [
+        ("one.xhtml", chapter("<p>Untitled start.</p>")),
+        (
+            "two.xhtml",
+            chapter("<h1 id=\"top\">  the   END </h1><p>Done.</p>"),
+        ),
+    ];
+    let ncx = nav_point("p1", 1, "Opening", "one.xhtml", "")
+        + &nav_point("p2", 2, "The End", "two.xhtml#top", "");
+    let path = write_book("titles", &chapters, &[], &ncx);
+    let doc = EpubParser::new().parse_file(&path).unwrap();
+
+    assert_eq!(
+        words(&doc),
+        vec!["Opening", "Untitled", "start.", "the", "END", "Done."]
+    );
+    assert_eq!(doc.tokens[0].block, BlockContext::Heading(1));
+    let outline: Vec<(&str, usize)> = doc
+        .sections
+        .iter()
+        .map(|s| (s.title.as_str(), s.token_start))
+        .collect();
+    assert_eq!(outline, vec![("Opening", 0), ("The End", 3)]);
+}
+
+#[test]
+fn test_parse_file_reads_chapters_with_nested_toc() {
+    let path = sample_book("full");
+    let doc = EpubParser::new().parse_file(&path).unwrap();
+
+    assert_eq!(
+        words(&doc),
+        vec![
+            "One", "It", "was", "very", "dark", "[1]", ".", "Deeper", "Down", "here.", "Two",
+            "Morning", "came."
+        ]
+    );
+    assert_eq!(doc.tokens[3].style, TokenStyle::Italic);
+    assert_eq!(doc.metadata.title.as_deref(), Some("Test Book"));
+
+    let outline: Vec<(&str, u8, usize)> = doc
+        .sections
+        .iter()
+        .map(|s| (s.title.as_str(), s.level, s.token_start))
+        .collect();
+    assert_eq!(
+        outline,
+        vec![("One", 1, 0), ("Deeper", 2, 7), ("Two", 1, 10)]
+    );
+    assert_eq!(doc.footnotes.len(), 1);
+    assert_eq!(doc.footnotes[0].text(), "Very dark indeed.");
+}
+
+#[test]
+fn test_chapter_loader_loads_on_request() {
+    let path = sample_book("lazy");
+    let (first, mut loader) = ChapterLoader::open(&path, EpubOptions::default()).unwrap();
+
+    // Only the first chapter is parsed before reading starts
+    assert_eq!(words(&first).len(), 10);
+    assert_eq!(first.metadata.source_path.as_deref(), Some(path.as_path()));
+    assert_eq!(first.sections.len(), 2);
+    assert!(!loader.is_complete());
+    let pending: Vec<String> = loader
+        .pending_sections()
+        .into_iter()
+        .map(|s| s.title)
+        .collect();
+    assert_eq!(pending, vec!["Two"]);
+
+    // The length of the book is estimated from the archive's chapter sizes
+    let mut book = epub::doc::EpubDoc::new(&path).unwrap();
+    let mut size = |file: &str| {
+        book.get_resource_str_by_path(format!("OEBPS/{file}"))
+            .unwrap()
+            .len() as f64
+    };
+    let (one, rest) = (size("one.xhtml"), size("two.xhtml") + size("notes.xhtml"));
+    let expected = (10.0 * (one + rest) / one).round() as usize;
+    assert_eq!(loader.estimated_word_count(), expected);
+
+    let mut chapters = Vec::new();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !loader.is_complete() && Instant::now() < deadline {
+        loader.request_next();
+        match loader.try_next() {
+            Some(chapter) => chapters.push(chapter.unwrap()),
+            None => std::thread::sleep(Duration::from_millis(5)),
+        }
+    }
+
+    assert!(loader.is_complete());
+    assert!(loader.pending_sections().is_empty());
+    assert_eq!(loader.estimated_word_count(), 13);
+    assert_eq!(words(&chapters[0]), vec!["Two", "Morning", "came."]);
+    // The note keeps the label of its reference in the first chapter
+    assert_eq!(chapters[1].footnotes[0].label, first.footnotes[0].label);
+    assert_eq!(chapters[1].footnotes[0].text(), "Very dark indeed.");
+}
+
+#[test]
+fn test_parse_bytes_matches_parse_file() {
+    let path = sample_book("bytes");
+    let parser = EpubParser::new();
+    let from_file = parser.parse_file(&path).unwrap();
+    let from_bytes = parser.parse_bytes(&std::fs::read(&path).unwrap()).unwrap();
+
+    assert_eq!(words(&from_bytes), words(&from_file));
+    assert_eq!(from_bytes.sections.len(), 3);
+    assert_eq!(from_bytes.metadata.title.as_deref(), Some("Test Book"));
+    assert_eq!(from_bytes.metadata.source_path, None);
+    assert!(parser.parse_bytes(b"not a zip").is_err());
+}
+
+fn export_book(name: &str) -> PathBuf {
+    let chapters = [
+        ("text/cover.xhtml", chapter("<div>&#160;</div>")),
+        (
+            "text/one.xhtml",
+            chapter(
+                "<h1>One</h1><p>See <a href=\"two.xhtml#later\">what follows</a> \
+                 or <a href=\"https://example.com\">the site</a>.</p>\
+                 <p id=\"later\">Also <a href=\"#later\">here</a>.</p>\
+                 <p><img src=\"../images/map.png\" alt=\"A map\"/></p>",
+            ),
+        ),
+        (
+            "text/two.xhtml",
+            chapter("<h1>Chapter Two</h1><p id=\"later\">The end.</p>"),
+        ),
+    ];
+    let ncx = nav_point("p1", 1, "One", "text/one.xhtml", "")
+        + &nav_point("p2", 2, "Chapter Two", "text/two.xhtml", "");
+    write_book(name, &chapters, &[("images/map.png", "PNG")], &ncx)
+}
+
+#[test]
+fn test_export_chapters_with_front_matter_assets_and_links() {
+    let path = export_book("export");
+    let output = temp_dir("export", "chapters");
+    let options = ExportOptions {
+        output: Some(output.clone()),
+        single_file: false,
+    };
+    let summary = EpubParser::new().export_chapters(&path, &options).unwrap();
+
+    assert_eq!(summary.path, output);
+    assert_eq!((summary.chapters, summary.images), (2, 1));
+
+    let one = std::fs::read_to_string(output.join("01-One.md")).unwrap();
+    assert!(one.starts_with(
+        "---\ntitle: \"One\"\nbook: \"Test Book\"\nchapter: 1\n\
+         author: \"A. Writer\"\nlanguage: \"en\"\n---\n\n# One"
+    ));
+    assert!(one.contains("![A map](<assets/map.png>)"));
+    assert!(one.contains("](<02-Chapter%20Two.md#later>)"));
+    assert!(one.contains("](<https://example.com"));
+    assert_eq!(
+        std::fs::read_to_string(output.join("assets/map.png")).unwrap(),
+        "PNG"
+    );
+
+    let index = std::fs::read_to_string(output.join("index.md")).unwrap();
+    assert!(index
+        .ends_with("# Test Book\n\n- [One](01-One.md)\n- [Chapter Two](02-Chapter%20Two.md)\n"));
+    let two = std::fs::read_to_string(output.join("02-Chapter Two.md")).unwrap();
+    assert!(two.contains("<a id=\"later\">"));
+}
+
+#[test]
+fn test_export_single_file() {
+    let path = export_book("single");
+    let output = temp_dir("export", "single");
+    let options = ExportOptions {
+        output: Some(output.clone()),
+        single_file: true,
+    };
+    let summary = EpubParser::new().export_chapters(&path, &options).unwrap();
+
+    assert_eq!(summary.path, output.join("Test Book.md"));
+    let book = std::fs::read_to_string(&summary.path).unwrap();
+    assert!(book.starts_with("---\ntitle: \"Test Book\"\n"));
+    assert!(book.contains("- [One](#chapter-01)\n- [Chapter Two](#chapter-02)\n"));
+    assert!(book.contains("<a id=\"chapter-02\"></a>\n\n# Chapter Two"));
+    // The same id in two chapters gets an anchor for each
+    assert!(book.contains("](<#chapter-01-later>)"));
+    assert!(book.contains("](<#chapter-02-later>)"));
+    assert!(output.join("assets/map.png").exists());
+
+    // The export reads back as a document with its metadata and anchors
+    let doc = rsvp_term::parser::MarkdownParser::new()
+        .parse_file(&summary.path)
+        .unwrap();
+    assert_eq!(doc.metadata.title.as_deref(), Some("Test Book"));
+    assert!(doc.anchors.contains_key("chapter-02"));
+    assert!(doc.anchors.contains_key("chapter-01-later"));
+    assert!(doc.anchors["chapter-01-later"] < doc.anchors["chapter-02-later"]);
+}
+
+#[test]
+fn test_export_reads_chapters_like_the_reader() {
+    let chapters = [(
+        "one.xhtml",
+        chapter(
+            "<h1>One</h1><p>Before<!-- <a href=\"gone.xhtml\">hidden</a> --> \
+             <img src=\"map.png\" alt=\"a &gt; b &amp; c\" title=\"x > y\"/> after.</p>",
+        ),
+    )];
+    let ncx = nav_point("p1", 1, "One", "one.xhtml", "");
+    let path = write_book("export-markup", &chapters, &[("map.png", "PNG")], &ncx);
+    let output = temp_dir("export", "markup");
+    let options = ExportOptions {
+        output: Some(output.clone()),
+        single_file: false,
+    };
+    EpubParser::new().export_chapters(&path, &options).unwrap();
+
+    let one = std::fs::read_to_string(output.join("01-One.md")).unwrap();
+    assert!(one.ends_with("# One\n\nBefore ![a > b & c](<assets/map.png> \"x > y\") after.\n"));
+    assert!(!one.contains("hidden"));
+}
+
+/// Error report a browser leaves in a chapter it couldn't parse
+const PARSER_ERROR: &str = "<parsererror xmlns=\"http://www.w3.org/1999/xhtml\">\
+    <h3>This page contains the following errors:</h3>\
+    <div>error on line 3 at column 9: Opening and ending tag mismatch</div></parsererror>";
+
+fn broken_book(name: &str) -> PathBuf {
+    let chapters = [
+        ("one.xhtml", chapter("<h1>One</h1><p>Fine.</p>")),
+        (
+            "two.xhtml",
+            chapter(&format!(
+                "{PARSER_ERROR}<h1>Two</h1><p>Partly <b>broken</p>"
+            )),
+        ),
+        ("three.xhtml", chapter(PARSER_ERROR)),
+        ("four.xhtml", chapter("<h1>Four</h1><p>Also fine.</p>")),
+    ];
+    let ncx = nav_point("p1", 1, "One", "one.xhtml", "")
+        + &nav_point("p2", 2, "Two", "two.xhtml", "")
+        + &nav_point("p3", 3, "Three", "three.xhtml", "")
+        + &nav_point("p4", 4, "Four", "four.xhtml", "");
+    write_book(name, &chapters, &[], &ncx)
+}
+
+#[test]
+fn test_malformed_chapters_are_read_around() {
+    let path = broken_book("lenient");
+    let doc = EpubParser::new().parse_file(&path).unwrap();
+
+    assert_eq!(
+        words(&doc),
+        vec!["One", "Fine.", "Two", "Partly", "broken", "Four", "Also", "fine."]
+    );
+    let warnings: Vec<String> = doc.warnings.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        warnings,
+        vec![
+            "chapter 2 \"Two\": malformed XHTML; read what could be recovered",
+            "chapter 3 \"Three\": skipped malformed chapter",
+        ]
+    );
+
+    let strict = EpubParser::with_options(EpubOptions { strict: true });
+    let output = temp_dir("export", "strict").join("book");
+    let options = ExportOptions {
+        output: Some(output.clone()),
+        single_file: false,
+    };
+    let error = strict.export_chapters(&path, &options).unwrap_err();
+    assert!(matches!(error, ParseError::Malformed { .. }));
+    assert!(!output.exists());
+    let summary = EpubParser::new().export_chapters(&path, &options).unwrap();
+    assert_eq!(summary.chapters, 3);
+    assert_eq!(summary.warnings.len(), 2);
+    std::fs::remove_dir_all(output.parent().unwrap()).unwrap();
+
+    let error = strict.parse_file(&path).unwrap_err();
+    let ParseError::Malformed { location, .. } = &error else {
+        panic!("expected a malformed chapter, got {error:?}");
+    };
+    assert_eq!(location.file.as_deref(), Some(path.as_path()));
+    assert_eq!(location.chapter_index, Some(1));
+    assert_eq!(location.chapter.as_deref(), Some("Two"));
+    assert_eq!((location.line, location.column), (Some(3), Some(9)));
+    assert_eq!(error.exit_code(), 4);
+}
+
+#[test]
+fn test_chapter_loader_reports_malformed_chapters() {
+    let path = broken_book("lenient-lazy");
+    let (_, mut loader) = ChapterLoader::open(&path, EpubOptions::default()).unwrap();
+
+    let mut warnings = Vec::new();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !loader.is_complete() && Instant::now() < deadline {
+        loader.request_next();
+        match loader.try_next() {
+            Some(chapter) => warnings.extend(chapter.unwrap().warnings),
+            None => std::thread::sleep(Duration::from_millis(5)),
+        }
+    }
+    assert!(loader.is_complete());
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(loader.word_count(), 8);
+}
+
+#[test]
+fn test_non_epub_is_unsupported() {
+    let error = EpubParser::new().parse_bytes(b"# Not a zip").unwrap_err();
+    assert!(matches!(error, ParseError::UnsupportedFormat { .. }));
+    assert_eq!(error.exit_code(), 5);
+}
+
+/// `META-INF/encryption.xml` encrypting `uri` with `algorithm`
+fn encryption(algorithm: &str, uri: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\"?>\
+         <encryption xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\" \
+         xmlns:enc=\"http://www.w3.org/2001/04/xmlenc#\">\
+         <enc:EncryptedData><enc:EncryptionMethod Algorithm=\"{algorithm}\"/>\
+         <enc:CipherData><enc:CipherReference URI=\"{uri}\"/></enc:CipherData>\
+         </enc:EncryptedData></encryption>"
+    )
+}
+
+#[test]
+fn test_encrypted_text_is_drm_protected() {
+    let chapters = [("one.xhtml", chapter("<p>Ciphertext</p>"))];
+    let xml = encryption(
+        "http://www.w3.org/2001/04/xmlenc#aes128-cbc",
+        "OEBPS/one.xhtml",
+    );
+    let extra = [("META-INF/encryption.xml", xml.as_str())];
+    let path = write_book_with("drm", &chapters, &[], "", &extra);
+
+    let error = EpubParser::new().parse_file(&path).unwrap_err();
+    assert!(matches!(error, ParseError::DrmProtected { .. }));
+    assert_eq!(error.exit_code(), 7);
+    let opened = ChapterLoader::open(&path, EpubOptions::default());
+    assert!(matches!(opened, Err(ParseError::DrmProtected { .. })));
+}
+
+#[test]
+fn test_obfuscated_fonts_do_not_block_reading() {
+    let chapters = [("one.xhtml", chapter("<p>Readable text</p>"))];
+    let xml = encryption(
+        "http://www.idpf.org/2008/embedding",
+        "OEBPS/fonts/serif.otf",
+    );
+    let extra = [("META-INF/encryption.xml", xml.as_str())];
+    let path = write_book_with("fonts", &chapters, &[], "", &extra);
+
+    let doc = EpubParser::new().parse_file(&path).unwrap();
+    assert_eq!(words(&doc), vec!["Readable", "text"]);
+}
+
+#[test]
+fn test_image_only_books_are_reported() {
+    let pages = [
+        ("one.xhtml", chapter("<img src=\"p1.png\"/>")),
+        ("two.xhtml", chapter("<img src=\"p2.png\"/>")),
+    ];
+    let images = [("p1.png", "PNG"), ("p2.png", "PNG")];
+    let path = write_book("comic", &pages, &images, "");
+    let error = EpubParser::new().parse_file(&path).unwrap_err();
+    assert!(matches!(error, ParseError::UnsupportedFormat { .. }));
+    assert!(error.to_string().contains("only has images"));
+    assert!(ChapterLoader::open(&path, EpubOptions::default()).is_err());
+
+    // Described pages can still be read, with a warning
+    let pages = [(
+        "one.xhtml",
+        chapter("<img src=\"p1.png\" alt=\"A harbour\"/>"),
+    )];
+    let path = write_book("described-comic", &pages, &images, "");
+    let doc = EpubParser::new().parse_file(&path).unwrap();
+    assert_eq!(words(&doc), vec!["A", "harbour"]);
+    assert_eq!(doc.warnings.len(), 1);
+
+    let path = write_book("blank", &[("one.xhtml", chapter(""))], &[], "");
+    let error = EpubParser::new().parse_file(&path).unwrap_err();
+    assert!(matches!(error, ParseError::EmptyDocument { .. }));
+}
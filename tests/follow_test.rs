@@ -0,0 +1,54 @@
+mod common;
+
+use common::temp_dir;
+use rsvp_term::follow::{spawn, FollowSource};
+use rsvp_term::parser::encoding::StreamDecoder;
+use rsvp_term::parser::Encoding;
+use std::io::Write;
+use std::time::Duration;
+
+#[test]
+fn test_stream_decoder_joins_split_characters() {
+    let mut decoder = StreamDecoder::default();
+    let bytes = "café".as_bytes();
+    let (first, rest) = bytes.split_at(bytes.len() - 1);
+    assert_eq!(decoder.decode(first), "caf");
+    assert_eq!(decoder.decode(rest), "é");
+    assert_eq!(decoder.decode(b"\xff ok"), "\u{fffd} ok");
+}
+
+#[test]
+fn test_stream_decoder_honours_byte_order_marks_and_encodings() {
+    let mut decoder = StreamDecoder::default();
+    assert_eq!(decoder.decode(b"\xFF"), "");
+    assert_eq!(decoder.decode(b"\xFEh\x00\x3D\xD8"), "h");
+    assert_eq!(decoder.decode(b"\x00\xDE!\x00"), "😀!");
+
+    let mut decoder = StreamDecoder::new(Some(Encoding::Windows1252));
+    assert_eq!(decoder.decode(b"\x93Caf\xe9\x94"), "“Café”");
+
+    let mut decoder = StreamDecoder::new(Some(Encoding::Utf16Be));
+    assert_eq!(decoder.decode(b"\xFE\xFF\x00o\x00"), "o");
+    assert_eq!(decoder.decode(b"k"), "k");
+}
+
+#[test]
+fn test_follow_file_reads_appended_text() {
+    let dir = temp_dir("follow", "appended");
+    let path = dir.join("log.md");
+    std::fs::write(&path, "first\n\n").unwrap();
+
+    let input = spawn(FollowSource::File(path.clone()), None).unwrap();
+    let timeout = Duration::from_secs(5);
+    assert_eq!(input.recv_timeout(timeout).unwrap(), "first\n\n");
+
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    file.write_all(b"second\n").unwrap();
+    drop(file);
+    let received = input.recv_timeout(timeout).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(received, "second\n");
+}
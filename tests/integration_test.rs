@@ -46,3 +46,33 @@ This is a **bold** test with *italic* text.
     app.increase_wpm();
     assert_eq!(app.wpm(), 325);
 }
+
+#[test]
+fn test_status_shows_front_matter_title_without_sections() {
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let doc = MarkdownParser::new()
+        .parse_str("---\ntitle: Vault Note\n---\nJust some prose.")
+        .unwrap();
+    let timed: Vec<TimedToken> = doc
+        .tokens
+        .into_iter()
+        .map(|t| TimedToken {
+            duration_ms: calculate_duration(&t, 300),
+            orp_position: calculate_orp(&t.word),
+            token: t,
+        })
+        .collect();
+    let mut app = App::new(timed, doc.sections);
+    app.set_metadata(doc.metadata);
+
+    let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+    terminal
+        .draw(|frame| rsvp_term::ui::render(frame, &app))
+        .unwrap();
+    let buffer = terminal.backend().buffer();
+    let status: String = (0..60)
+        .map(|x| buffer.cell((x, 10)).unwrap().symbol().to_string())
+        .collect();
+    assert!(status.contains("Vault Note"), "status line: {status:?}");
+}
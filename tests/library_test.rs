@@ -0,0 +1,90 @@
+mod common;
+
+use common::temp_dir;
+use rsvp_term::library::{
+    format_duration, format_last_read, fuzzy_score, load, parse, save, serialize, LibraryEntry,
+};
+use std::time::Duration;
+
+fn entry(key: &str, title: &str, position: usize, last_read: u64) -> LibraryEntry {
+    LibraryEntry {
+        key: key.to_string(),
+        path: format!("/docs/{key}.md").into(),
+        title: title.to_string(),
+        position,
+        token_count: 601,
+        last_read,
+    }
+}
+
+#[test]
+fn test_library_round_trip_most_recent_first() {
+    let entries = vec![
+        entry("a", "Old RFC", 10, 100),
+        entry("b", "New design", 600, 300),
+    ];
+    let parsed = parse(&serialize(&entries));
+    assert_eq!(parsed, vec![entries[1].clone(), entries[0].clone()]);
+}
+
+#[test]
+fn test_library_skips_malformed_lines() {
+    let content = "# rsvp-term library v1\npath:/a.md\t/a.md\tA\t3\t10\t5\nbroken line\npath:/b.md\t/b.md\tB\tx\t10\t5\n";
+    let parsed = parse(content);
+    assert_eq!(parsed.len(), 1);
+    assert_eq!(parsed[0].title, "A");
+}
+
+#[test]
+fn test_library_tabs_in_title_dont_break_lines() {
+    let entries = vec![entry("a", "Tab\there", 0, 1)];
+    assert_eq!(parse(&serialize(&entries))[0].title, "Tab here");
+}
+
+#[test]
+fn test_library_save_and_load() {
+    let dir = temp_dir("library", "save");
+    let path = dir.join("state/library.tsv");
+    assert_eq!(load(&path).unwrap(), vec![]);
+
+    let entries = vec![entry("a", "Notes", 42, 7)];
+    save(&path, &entries).unwrap();
+    let loaded = load(&path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(loaded, entries);
+}
+
+#[test]
+fn test_library_entry_progress_and_time_remaining() {
+    let half = entry("a", "A", 300, 0);
+    assert!((half.progress() - 0.5).abs() < f64::EPSILON);
+    assert_eq!(half.time_remaining(300), Duration::from_secs(60));
+    assert!(!half.is_finished());
+    assert!(entry("b", "B", 600, 0).is_finished());
+}
+
+#[test]
+fn test_fuzzy_score() {
+    assert!(fuzzy_score("", "anything").is_some());
+    assert!(fuzzy_score("rfc", "RFC 9110: HTTP Semantics").is_some());
+    assert!(fuzzy_score("htsem", "RFC 9110: HTTP Semantics").is_some());
+    assert_eq!(fuzzy_score("xyz", "RFC 9110"), None);
+    // Matches at word starts beat scattered ones
+    assert!(fuzzy_score("api", "API guide") > fuzzy_score("api", "rapid iteration"));
+}
+
+#[test]
+fn test_format_last_read_and_duration() {
+    const DAY: u64 = 24 * 60 * 60;
+    let now = 1000 * DAY;
+    assert_eq!(format_last_read(now - 60, now), "today");
+    assert_eq!(format_last_read(now - DAY, now), "yesterday");
+    assert_eq!(format_last_read(now - 3 * DAY, now), "3 days ago");
+    assert_eq!(format_last_read(now - 21 * DAY, now), "3 weeks ago");
+    assert_eq!(format_last_read(now - 90 * DAY, now), "3 months ago");
+
+    assert_eq!(format_duration(Duration::from_secs(30)), "1 min");
+    assert_eq!(format_duration(Duration::from_secs(45 * 60)), "45 min");
+    assert_eq!(format_duration(Duration::from_secs(120 * 60)), "2 h");
+    assert_eq!(format_duration(Duration::from_secs(130 * 60)), "2 h 10 min");
+}
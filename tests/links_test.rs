@@ -0,0 +1,95 @@
+mod common;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use common::temp_dir;
+use rsvp_term::links::{find_anchor, resolve, slugify, LinkTarget};
+
+#[test]
+fn test_web_links_are_not_followed() {
+    let current = Path::new("notes/index.md");
+    assert_eq!(resolve("https://example.com/a.md", current, None), None);
+    assert_eq!(resolve("mailto:me@example.com", current, None), None);
+}
+
+#[test]
+fn test_fragment_links_stay_in_document() {
+    assert_eq!(
+        resolve("#Getting%20Started", Path::new("index.md"), None),
+        Some(LinkTarget::Internal {
+            file: String::new(),
+            fragment: Some("Getting Started".to_string()),
+        })
+    );
+    assert_eq!(resolve("#", Path::new("index.md"), None), None);
+}
+
+#[test]
+fn test_epub_hrefs_stay_in_book() {
+    assert_eq!(
+        resolve("../Text/ch2.xhtml#sec", Path::new("book.epub"), None),
+        Some(LinkTarget::Internal {
+            file: "../Text/ch2.xhtml".to_string(),
+            fragment: Some("sec".to_string()),
+        })
+    );
+}
+
+#[test]
+fn test_relative_markdown_links_and_notes() {
+    let dir = temp_dir("links", "relative");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("index.md"), "").unwrap();
+    std::fs::write(dir.join("sub/My Page.md"), "").unwrap();
+    std::fs::write(dir.join("image.png"), "").unwrap();
+    let current = dir.join("index.md");
+
+    assert_eq!(
+        resolve("sub/My%20Page.md#usage", &current, None),
+        Some(LinkTarget::Document {
+            path: dir.join("sub/My Page.md"),
+            fragment: Some("usage".to_string()),
+        })
+    );
+    // Wikilink targets are found anywhere in the vault
+    assert_eq!(
+        resolve("My Page", &current, None),
+        Some(LinkTarget::Document {
+            path: dir.join("sub/My Page.md"),
+            fragment: None,
+        })
+    );
+    assert_eq!(resolve("missing.md", &current, None), None);
+    assert_eq!(resolve("image.png", &current, None), None);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_find_anchor() {
+    let anchors: HashMap<String, usize> = [
+        ("getting-started", 4),
+        ("OEBPS/Text/ch2.xhtml", 10),
+        ("OEBPS/Text/ch2.xhtml#sec", 15),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect();
+
+    assert_eq!(find_anchor(&anchors, "", Some("Getting Started")), Some(4));
+    assert_eq!(find_anchor(&anchors, "", Some("missing")), None);
+    assert_eq!(find_anchor(&anchors, "ch2.xhtml", None), Some(10));
+    assert_eq!(
+        find_anchor(&anchors, "../Text/ch2.xhtml", Some("sec")),
+        Some(15)
+    );
+    assert_eq!(find_anchor(&anchors, "ch2.xhtml", Some("other")), Some(10));
+    assert_eq!(find_anchor(&anchors, "ch3.xhtml", None), None);
+}
+
+#[test]
+fn test_slugify() {
+    assert_eq!(slugify("Getting Started!"), "getting-started");
+    assert_eq!(slugify("API: v2_beta"), "api-v2_beta");
+}
@@ -0,0 +1,130 @@
+use ratatui::{backend::TestBackend, layout::Rect, Terminal};
+use rsvp_term::app::App;
+use rsvp_term::types::{BlockContext, Section, TimedToken, TimingHint, Token, TokenStyle};
+use rsvp_term::ui::{self, HitTarget};
+
+fn make_timed_token(word: &str) -> TimedToken {
+    TimedToken {
+        token: Token {
+            word: word.to_string(),
+            style: TokenStyle::Normal,
+            block: BlockContext::Paragraph,
+            parent_context: None,
+            timing_hint: TimingHint::default(),
+        },
+        duration_ms: 200,
+        orp_position: 1,
+    }
+}
+
+fn make_app(words: &str) -> App {
+    App::new(
+        words.split_whitespace().map(make_timed_token).collect(),
+        vec![],
+    )
+}
+
+/// Find the screen cell where `word` starts on the given row range
+fn find_word(
+    app: &App,
+    width: u16,
+    height: u16,
+    word: &str,
+    rows: std::ops::Range<u16>,
+) -> (u16, u16) {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|frame| ui::render(frame, app)).unwrap();
+    let buffer = terminal.backend().buffer();
+
+    for y in rows {
+        let line: String = (0..width)
+            .map(|x| buffer.cell((x, y)).unwrap().symbol().to_string())
+            .collect();
+        if let Some(col) = line.find(&format!(" {word} ")) {
+            return ((col + 1) as u16, y);
+        }
+    }
+    panic!("word {word:?} not rendered");
+}
+
+#[test]
+fn test_click_progress_bar_seeks() {
+    let app = make_app("one two three four");
+    let area = Rect::new(0, 0, 80, 24);
+
+    // Status bar occupies the last two rows; the gauge is the bottom row
+    assert_eq!(
+        ui::hit_test(&app, area, 40, 23),
+        Some(HitTarget::Progress(0.5))
+    );
+    assert!(!matches!(
+        ui::hit_test(&app, area, 40, 22),
+        Some(HitTarget::Progress(_))
+    ));
+}
+
+#[test]
+fn test_mouse_ignored_under_help_screen() {
+    let mut app = make_app("one two three four");
+    let area = Rect::new(0, 0, 80, 24);
+
+    app.toggle_help();
+    assert!(!app.mouse_active());
+    assert_eq!(ui::hit_test(&app, area, 40, 23), None);
+
+    app.toggle_help();
+    assert!(app.mouse_active());
+}
+
+#[test]
+fn test_click_context_word_targets_token() {
+    let mut app = make_app("alpha beta gamma delta epsilon zeta eta theta");
+    for _ in 0..3 {
+        app.advance();
+    }
+
+    // "beta" is before the current word, "zeta" after
+    let (x, y) = find_word(&app, 80, 24, "beta", 0..11);
+    assert_eq!(
+        ui::hit_test(&app, Rect::new(0, 0, 80, 24), x, y),
+        Some(HitTarget::Token(1))
+    );
+
+    let (x, y) = find_word(&app, 80, 24, "zeta", 12..22);
+    assert_eq!(
+        ui::hit_test(&app, Rect::new(0, 0, 80, 24), x + 2, y),
+        Some(HitTarget::Token(5))
+    );
+}
+
+#[test]
+fn test_click_outline_entry() {
+    let sections = (0..5)
+        .map(|i| Section {
+            title: format!("Section {i}"),
+            level: 1,
+            token_start: i * 2,
+            token_end: i * 2 + 2,
+        })
+        .collect();
+    let tokens = (0..10)
+        .map(|i| make_timed_token(&format!("w{i}")))
+        .collect();
+    let mut app = App::new(tokens, sections);
+    app.toggle_outline();
+    app.select_section(2);
+
+    // Main area is 22 rows high: selected item on row 11, neighbours 2 rows away
+    let area = Rect::new(0, 0, 80, 24);
+    assert_eq!(
+        ui::hit_test(&app, area, 10, 11),
+        Some(HitTarget::Section(2))
+    );
+    assert_eq!(ui::hit_test(&app, area, 10, 9), Some(HitTarget::Section(1)));
+    assert_eq!(ui::hit_test(&app, area, 10, 8), Some(HitTarget::Section(0)));
+    assert_eq!(
+        ui::hit_test(&app, area, 10, 13),
+        Some(HitTarget::Section(3))
+    );
+    assert_eq!(ui::hit_test(&app, area, 10, 15), None);
+}
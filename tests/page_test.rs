@@ -0,0 +1,99 @@
+use ratatui::{backend::TestBackend, layout::Rect, style::Color, Terminal};
+use rsvp_term::app::{App, ViewMode};
+use rsvp_term::parser::{DocumentParser, MarkdownParser};
+use rsvp_term::types::TimedToken;
+use rsvp_term::ui::{self, HitTarget};
+
+fn make_app(markdown: &str) -> App {
+    let doc = MarkdownParser::new().parse_str(markdown).unwrap();
+    let tokens = doc
+        .tokens
+        .into_iter()
+        .map(|token| TimedToken {
+            token,
+            duration_ms: 200,
+            orp_position: 0,
+        })
+        .collect();
+    App::new(tokens, doc.sections)
+}
+
+#[test]
+fn test_page_toggle_and_resume() {
+    let mut app = make_app("one two three four five");
+    app.advance();
+
+    app.toggle_page();
+    assert_eq!(app.view_mode(), ViewMode::Page);
+    assert_eq!(app.page_cursor(), 1);
+
+    app.page_cursor_next();
+    app.page_cursor_next();
+    assert_eq!(app.position(), 1, "moving the cursor must not move RSVP");
+
+    app.resume_from_page();
+    assert_eq!(app.view_mode(), ViewMode::Reading);
+    assert_eq!(app.position(), 3);
+}
+
+#[test]
+fn test_page_renders_all_words_with_cursor_highlight() {
+    let mut app = make_app("First paragraph here.\n\nSecond paragraph there.");
+    app.toggle_page();
+    app.set_page_cursor(4);
+
+    let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+    terminal.draw(|frame| ui::render(frame, &app)).unwrap();
+    let buffer = terminal.backend().buffer();
+
+    let text: String = (0..12)
+        .flat_map(|y| (0..60).map(move |x| (x, y)))
+        .map(|pos| buffer.cell(pos).unwrap().symbol().to_string())
+        .collect();
+    assert!(text.contains("First paragraph here."));
+    assert!(text.contains("Second paragraph there."));
+
+    // The cursor word ("paragraph" in the second block) is highlighted
+    let highlighted: String = (0..12)
+        .flat_map(|y| (0..60).map(move |x| (x, y)))
+        .map(|pos| buffer.cell(pos).unwrap())
+        .filter(|cell| cell.bg == Color::Yellow)
+        .map(|cell| cell.symbol().to_string())
+        .collect();
+    assert_eq!(highlighted, "paragraph");
+}
+
+#[test]
+fn test_page_cursor_moves_by_lines() {
+    let mut app = make_app("Alpha beta.\n\nGamma delta.\n\nEpsilon zeta.");
+    app.toggle_page();
+    app.set_page_cursor(1); // "beta."
+
+    let area = Rect::new(0, 0, 60, 20);
+    // Blank separator lines are skipped, word offset is kept
+    assert_eq!(ui::page_cursor_after_lines(&app, area, 1), 3);
+    app.set_page_cursor(3);
+    assert_eq!(ui::page_cursor_after_lines(&app, area, 1), 5);
+    assert_eq!(ui::page_cursor_after_lines(&app, area, -1), 1);
+    // Clamped at the document edges
+    assert_eq!(ui::page_cursor_after_lines(&app, area, 100), 5);
+}
+
+#[test]
+fn test_page_click_targets_word() {
+    let mut app = make_app("Alpha beta gamma");
+    app.toggle_page();
+
+    let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+    terminal.draw(|frame| ui::render(frame, &app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let row: String = (0..60)
+        .map(|x| buffer.cell((x, 0)).unwrap().symbol().to_string())
+        .collect();
+    let col = row.find("gamma").unwrap() as u16;
+
+    assert_eq!(
+        ui::hit_test(&app, Rect::new(0, 0, 60, 12), col, 0),
+        Some(HitTarget::Token(2))
+    );
+}
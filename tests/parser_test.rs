@@ -1,6 +1,9 @@
+mod common;
+
+use common::temp_dir;
 use insta::assert_debug_snapshot;
-use rsvp_term::parser::{DocumentParser, MarkdownParser};
-use rsvp_term::types::TokenStyle;
+use rsvp_term::parser::{DocumentParser, Encoding, MarkdownOptions, MarkdownParser, ParseError};
+use rsvp_term::types::{BlockContext, DocumentMetadata, TokenStyle};
 
 #[test]
 fn test_parse_simple_paragraph() {
@@ -12,6 +15,21 @@ fn test_parse_simple_paragraph() {
     assert_eq!(result.tokens[1].word, "world");
 }
 
+#[test]
+fn test_parse_reader_and_bytes() {
+    let parser = MarkdownParser::new();
+    let source = "---\ntitle: Notes\n---\n# Title\n\nHello world";
+    let from_reader = parser
+        .parse_reader(std::io::Cursor::new(source.as_bytes()))
+        .unwrap();
+    let from_bytes = parser.parse_bytes(source.as_bytes()).unwrap();
+
+    assert_eq!(from_reader.tokens.len(), 3);
+    assert_eq!(from_bytes.tokens.len(), 3);
+    assert_eq!(from_bytes.metadata.title.as_deref(), Some("Notes"));
+    assert!(parser.parse_bytes(&[0xff, 0xfe, 0x00]).is_err());
+}
+
 #[test]
 fn test_parse_heading() {
     let parser = MarkdownParser::new();
@@ -54,13 +72,84 @@ fn test_skip_code_block() {
 }
 
 #[test]
-fn test_skip_image() {
+fn test_code_block_placeholder() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str("Before\n\n```rust\nfn main() {\n    run();\n}\n```\n\nAfter")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Before", "[code: rust, 3 lines]", "After"]);
+    assert_eq!(result.tokens[1].block, BlockContext::CodeBlock);
+    assert!(result.tokens[2].timing_hint.is_block_start);
+
+    assert_eq!(result.code_blocks.len(), 1);
+    let block = &result.code_blocks[0];
+    assert_eq!(block.language.as_deref(), Some("rust"));
+    assert_eq!(block.content, "fn main() {\n    run();\n}");
+    assert_eq!((block.token_start, block.token_end), (1, 2));
+}
+
+#[test]
+fn test_indented_code_block_placeholder() {
     let parser = MarkdownParser::new();
-    let result = parser.parse_str("Before ![alt](image.png) After").unwrap();
+    let result = parser.parse_str("Text\n\n    let x = 1;\n").unwrap();
 
     let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
-    assert!(!words.contains(&"alt"));
+    assert_eq!(words, vec!["Text", "[code: 1 line]"]);
+    assert_eq!(result.code_blocks[0].language, None);
+}
+
+#[test]
+fn test_code_block_read_line_by_line() {
+    let parser = MarkdownParser::with_options(MarkdownOptions {
+        code_lines: true,
+        ..Default::default()
+    });
+    let result = parser
+        .parse_str("```sh\ncargo build\n\ncargo test\n```")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["cargo build", "cargo test"]);
+    assert!(result.tokens.iter().all(|t| t.style == TokenStyle::Code));
+    assert!(result.tokens[0].timing_hint.is_block_start);
+    assert!(!result.tokens[1].timing_hint.is_block_start);
+    assert_eq!(
+        (
+            result.code_blocks[0].token_start,
+            result.code_blocks[0].token_end
+        ),
+        (0, 2)
+    );
+}
+
+#[test]
+fn test_image_alt_text_read_as_figure() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str("Before ![A cat](image.png \"On a mat\") After")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Before", "A", "cat", "On", "a", "mat", "After"]);
     assert!(!words.contains(&"image.png"));
+
+    let figure = &result.tokens[1..6];
+    assert!(figure.iter().all(|t| t.style == TokenStyle::Image));
+    assert!(figure.iter().all(|t| t.block == BlockContext::Figure));
+    assert!(figure[0].timing_hint.is_block_start);
+    assert_eq!(BlockContext::Figure.hint_chars(), "img");
+}
+
+#[test]
+fn test_image_without_alt_text_has_marker() {
+    let parser = MarkdownParser::new();
+    let result = parser.parse_str("See ![](chart.png)").unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["See", "[image]"]);
+    assert_eq!(result.tokens[1].block, BlockContext::Figure);
 }
 
 // Snapshot tests for capturing full token/section structure
@@ -103,9 +192,10 @@ fn test_parse_callout_with_folder_emoji() {
     let parser = MarkdownParser::new();
     let result = parser.parse_str("> [!folder] File: example.txt").unwrap();
 
-    // Should parse as Callout with folder type
+    // The rest of the marker line is the callout title
     assert!(!result.tokens.is_empty());
-    if let rsvp_term::types::BlockContext::Callout(callout_type) = &result.tokens[0].block {
+    assert_eq!(result.tokens[0].word, "File:");
+    if let rsvp_term::types::BlockContext::CalloutTitle(callout_type) = &result.tokens[0].block {
         assert_eq!(callout_type, "folder");
     } else {
         panic!(
@@ -255,3 +345,590 @@ fn test_star_bullet_list_parsed_same_as_dash() {
     );
     assert_eq!(words, vec!["First", "Second", "Third"]);
 }
+
+#[test]
+fn test_strikethrough_style() {
+    let parser = MarkdownParser::new();
+    let result = parser.parse_str("Keep ~~drop this~~ done").unwrap();
+
+    let styles: Vec<&TokenStyle> = result.tokens.iter().map(|t| &t.style).collect();
+    assert_eq!(
+        styles,
+        vec![
+            &TokenStyle::Normal,
+            &TokenStyle::Strikethrough,
+            &TokenStyle::Strikethrough,
+            &TokenStyle::Normal
+        ]
+    );
+}
+
+#[test]
+fn test_bare_urls_are_linkified() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str("Visit https://example.com/docs today")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Visit", "https://example.com/docs", "today"]);
+    assert_eq!(
+        result.tokens[1].style,
+        TokenStyle::Link("https://example.com/docs".to_string())
+    );
+}
+
+#[test]
+fn test_escapes_and_entities_join_surrounding_text() {
+    let parser = MarkdownParser::new();
+    let result = parser.parse_str("snake\\_case AT&amp;T").unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["snake_case", "AT&T"]);
+}
+
+#[test]
+fn test_escapes_and_entities_split_like_plain_text() {
+    let parser = MarkdownParser::new();
+    let words = |source: &str| -> Vec<(String, TokenStyle)> {
+        parser
+            .parse_str(source)
+            .unwrap()
+            .tokens
+            .into_iter()
+            .map(|t| (t.word, t.style))
+            .collect()
+    };
+    let normal = |word: &str| (word.to_string(), TokenStyle::Normal);
+
+    // Escaped markup is read as its literal character, attached to its word
+    assert_eq!(
+        words("\\*not emphasis\\* \\# hash"),
+        vec![
+            normal("*not"),
+            normal("emphasis*"),
+            normal("#"),
+            normal("hash")
+        ]
+    );
+    assert_eq!(words("1\\. Not")[0], normal("1."));
+    // An entity on its own is a word; inside a word it joins it
+    assert_eq!(
+        words("a &amp; b"),
+        vec![normal("a"), normal("&"), normal("b")]
+    );
+    assert_eq!(
+        words("&copy;2024 Tom&#8217;s"),
+        vec![normal("©2024"), normal("Tom’s")]
+    );
+    // Joined text keeps the style around it
+    assert_eq!(
+        words("**snake\\_case** plain"),
+        vec![
+            ("snake_case".to_string(), TokenStyle::Bold),
+            normal("plain")
+        ]
+    );
+}
+
+#[test]
+fn test_task_list_items() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str("- [x] Done task\n- [ ] Open task\n- Plain item")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Done", "task", "Open", "task", "Plain", "item"]);
+    assert_eq!(result.tokens[0].block, BlockContext::TaskItem(1, true));
+    assert_eq!(result.tokens[2].block, BlockContext::TaskItem(1, false));
+    assert_eq!(result.tokens[4].block, BlockContext::ListItem(1));
+    assert_eq!(result.tokens[0].block.hint_chars(), "[x]");
+    assert_eq!(result.tokens[2].block.hint_chars(), "[ ]");
+}
+
+#[test]
+fn test_footnotes_kept_out_of_word_stream() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str("A claim[^1] and more.\n\n[^1]: The source\n    continues here.\n\nAfter.")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["A", "claim", "[1]", "and", "more.", "After."]);
+    assert_eq!(result.tokens[2].style, TokenStyle::FootnoteRef(0));
+
+    assert_eq!(result.footnotes.len(), 1);
+    assert_eq!(result.footnotes[0].label, "1");
+    assert_eq!(result.footnotes[0].text(), "The source continues here.");
+}
+
+#[test]
+fn test_undefined_footnote_reference_is_plain_text() {
+    let parser = MarkdownParser::new();
+    let result = parser.parse_str("Missing[^x] note").unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Missing[^x]", "note"]);
+    assert!(result.footnotes.is_empty());
+}
+
+#[test]
+fn test_yaml_front_matter_stripped() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str(
+            "---\ntitle: \"Reading Notes\"\nauthor: Ada Lovelace\ntags:\n  - books\n  - '#rsvp'\n---\nBody text",
+        )
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Body", "text"]);
+    assert!(
+        result.sections.is_empty(),
+        "no setext heading from the fence"
+    );
+    assert_eq!(result.metadata.title.as_deref(), Some("Reading Notes"));
+    assert_eq!(result.metadata.authors, vec!["Ada Lovelace"]);
+    assert_eq!(result.metadata.tags, vec!["books", "rsvp"]);
+}
+
+#[test]
+fn test_toml_front_matter_stripped() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str("+++\ntitle = 'Post'\ntags = [\"a\", \"b\"]\n+++\n\nHello")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Hello"]);
+    assert_eq!(result.metadata.title.as_deref(), Some("Post"));
+    assert_eq!(result.metadata.tags, vec!["a", "b"]);
+}
+
+#[test]
+fn test_yaml_block_scalars_in_front_matter() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str(
+            "---\ntitle: >-\n  A Long\n  Title\ndescription: >\n  A folded\n  summary\n\
+             notes: |\n  line one\n  line two\nauthor: Ada\n---\n\nBody",
+        )
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Body"]);
+    assert_eq!(result.metadata.title.as_deref(), Some("A Long Title"));
+    assert_eq!(result.metadata.authors, vec!["Ada"]);
+}
+
+#[test]
+fn test_thematic_break_is_not_front_matter() {
+    let parser = MarkdownParser::new();
+    let result = parser.parse_str("Intro\n\n---\n\nMore").unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Intro", "More"]);
+    assert_eq!(
+        result.metadata,
+        DocumentMetadata {
+            word_count: 2,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_opening_rule_with_later_rule_is_not_front_matter() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str("---\n\n# Part one\n\nOpening words: kept.\n\n---\n\nClosing words.")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(
+        words,
+        vec!["Part", "one", "Opening", "words:", "kept.", "Closing", "words."]
+    );
+    assert_eq!(result.metadata.title, None);
+}
+
+#[test]
+fn test_french_punctuation_stays_with_word() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str("---\nlang: fr\n---\n\nIl dit « bonjour » : quoi ?")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(
+        words,
+        vec!["Il", "dit", "«\u{a0}bonjour\u{a0}»\u{a0}:", "quoi\u{a0}?"]
+    );
+    assert_eq!(result.metadata.language.as_deref(), Some("fr"));
+}
+
+#[test]
+fn test_cjk_text_is_chunked() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str("---\nlang: ja\n---\n\n吾輩は猫である。名前はまだ無い。")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert!(words.len() > 1, "CJK runs are split into chunks: {words:?}");
+    assert!(words.iter().all(|w| w.chars().count() <= 5));
+    assert_eq!(words.concat(), "吾輩は猫である。名前はまだ無い。");
+}
+
+#[test]
+fn test_metadata_word_count_and_source_path() {
+    let parser = MarkdownParser::new();
+    let path = std::path::Path::new("tests/fixtures/simple.md");
+    let result = parser.parse_file(path).unwrap();
+
+    assert_eq!(result.metadata.word_count, result.tokens.len());
+    assert_eq!(result.metadata.source_path.as_deref(), Some(path));
+}
+
+#[test]
+fn test_callout_title_is_separate_from_body() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str("> [!warning] Mind the gap\n> Step carefully")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Mind", "the", "gap", "Step", "carefully"]);
+    let title = BlockContext::CalloutTitle("warning".to_string());
+    assert!(result.tokens[..3].iter().all(|t| t.block == title));
+    assert!(result.tokens[..3]
+        .iter()
+        .all(|t| t.style == TokenStyle::Bold));
+    assert_eq!(
+        result.tokens[3].block,
+        BlockContext::Callout("warning".to_string())
+    );
+    assert!(result.tokens[3].timing_hint.is_block_start);
+}
+
+#[test]
+fn test_callout_without_title_uses_type() {
+    let parser = MarkdownParser::new();
+    let result = parser.parse_str("> [!tip]\n> Body").unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Tip", "Body"]);
+}
+
+#[test]
+fn test_folded_callout_can_be_skipped() {
+    let source = "> [!faq]- Why?\n> Because.\n\nAfter";
+    let words = |options: MarkdownOptions| -> Vec<String> {
+        MarkdownParser::with_options(options)
+            .parse_str(source)
+            .unwrap()
+            .tokens
+            .into_iter()
+            .map(|t| t.word)
+            .collect()
+    };
+
+    assert_eq!(
+        words(MarkdownOptions::default()),
+        vec!["Why?", "Because.", "After"]
+    );
+    let skipped = words(MarkdownOptions {
+        skip_folded_callouts: true,
+        ..Default::default()
+    });
+    assert_eq!(skipped, vec!["Why?", "After"]);
+}
+
+#[test]
+fn test_wikilinks_read_as_links() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str("See [[Notes/Reading List]] and [[Home|the start]].")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(
+        words,
+        vec!["See", "Reading", "List", "and", "the", "start", "."]
+    );
+    assert_eq!(
+        result.tokens[1].style,
+        TokenStyle::Link("Notes/Reading List".to_string())
+    );
+    assert_eq!(result.tokens[5].style, TokenStyle::Link("Home".to_string()));
+}
+
+#[test]
+fn test_embeds_are_inlined_from_vault() {
+    let vault = temp_dir("vault", "embed");
+    std::fs::create_dir_all(vault.join("sub")).unwrap();
+    std::fs::write(
+        vault.join("sub/Quote.md"),
+        "---\ntitle: Quote\n---\n\nTo be or not\n\n## Later\n\nSkipped",
+    )
+    .unwrap();
+    std::fs::write(vault.join("main.md"), "Start\n\n![[Quote]]\n\nEnd").unwrap();
+
+    let parser = MarkdownParser::new();
+    let result = parser.parse_file(&vault.join("main.md")).unwrap();
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(
+        words,
+        vec!["Start", "To", "be", "or", "not", "Later", "Skipped", "End"]
+    );
+
+    std::fs::write(vault.join("main.md"), "Start ![[Quote#Later]]").unwrap();
+    let result = parser.parse_file(&vault.join("main.md")).unwrap();
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Start", "Later", "Skipped"]);
+
+    std::fs::remove_dir_all(&vault).unwrap();
+}
+
+#[test]
+fn test_embeds_are_decoded_like_the_note() {
+    let vault = temp_dir("vault", "embed-encoding");
+    std::fs::write(vault.join("Quote.md"), b"Caf\xe9 cr\xe8me").unwrap();
+    std::fs::write(vault.join("main.md"), "Start ![[Quote]]").unwrap();
+
+    let options = MarkdownOptions {
+        encoding: Some(Encoding::Latin1),
+        ..MarkdownOptions::default()
+    };
+    let result = MarkdownParser::with_options(options)
+        .parse_file(&vault.join("main.md"))
+        .unwrap();
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Start", "Café", "crème"]);
+
+    std::fs::remove_dir_all(&vault).unwrap();
+}
+
+#[test]
+fn test_embeds_outside_the_vault_are_not_resolved() {
+    let root = temp_dir("vault", "escape");
+    let vault = root.join("vault");
+    std::fs::create_dir_all(&vault).unwrap();
+    std::fs::write(root.join("secret.md"), "Private words").unwrap();
+    let absolute = root.join("secret").display().to_string();
+    std::fs::write(
+        vault.join("main.md"),
+        format!("Start ![[../secret]] ![[{absolute}]] End"),
+    )
+    .unwrap();
+
+    let parser = MarkdownParser::with_options(MarkdownOptions {
+        vault: Some(vault.clone()),
+        ..MarkdownOptions::default()
+    });
+    let result = parser.parse_file(&vault.join("main.md")).unwrap();
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert!(!words.contains(&"Private"), "{words:?}");
+    assert_eq!(words.first(), Some(&"Start"));
+    assert_eq!(words.last(), Some(&"End"));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_embed_cycles_and_attachments() {
+    let vault = temp_dir("vault", "cycle");
+    std::fs::write(vault.join("a.md"), "A ![[b]]").unwrap();
+    std::fs::write(vault.join("b.md"), "B ![[a]] ![[chart.png|Sales chart]]").unwrap();
+
+    let parser = MarkdownParser::new();
+    let result = parser.parse_file(&vault.join("a.md")).unwrap();
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["A", "B", "a", "Sales", "chart"]);
+    assert_eq!(result.tokens[2].style, TokenStyle::Link("a".to_string()));
+    assert_eq!(result.tokens[3].block, BlockContext::Figure);
+
+    std::fs::remove_dir_all(&vault).unwrap();
+}
+
+#[test]
+fn test_heading_and_html_anchors() {
+    let parser = MarkdownParser::new();
+    let result = parser
+        .parse_str("# Intro\n\nText\n\n## Intro\n\nMore <a id=\"here\"></a>words")
+        .unwrap();
+
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Intro", "Text", "Intro", "More", "words"]);
+    assert_eq!(result.anchors.get("intro"), Some(&0));
+    assert_eq!(result.anchors.get("intro-1"), Some(&2));
+    assert_eq!(result.anchors.get("here"), Some(&4));
+
+    // Only anchor tags are read as HTML; other tags stay text as before
+    let result = parser
+        .parse_str("Press <kbd>Ctrl</kbd> or <a href=\"x.md\">here</a> <a name='n'>")
+        .unwrap();
+    let words: Vec<&str> = result.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(
+        words,
+        vec![
+            "Press",
+            "<kbd>Ctrl</kbd>",
+            "or",
+            "<a",
+            "href=\"x.md\">here</a>"
+        ]
+    );
+    assert_eq!(result.anchors.get("n"), Some(&5));
+}
+
+#[test]
+fn test_stream_parser_emits_complete_blocks() {
+    use rsvp_term::parser::StreamParser;
+
+    let mut parser = StreamParser::new();
+    assert!(parser
+        .push("# Build log\n\nCompiling cr")
+        .unwrap()
+        .is_some());
+    let words = |doc: &rsvp_term::parser::ParsedDocument| {
+        doc.tokens
+            .iter()
+            .map(|t| t.word.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let doc = parser.push("ate one\n\n## Tests").unwrap().unwrap();
+    assert_eq!(words(&doc), vec!["Compiling", "crate", "one"]);
+    assert!(doc.sections.is_empty());
+
+    assert!(parser.push("\nall passed").unwrap().is_none());
+    let doc = parser.finish().unwrap().unwrap();
+    assert_eq!(doc.sections[0].title, "Tests");
+    assert_eq!(words(&doc), vec!["Tests", "all", "passed"]);
+    assert!(parser.finish().unwrap().is_none());
+}
+
+#[test]
+fn test_stream_parser_reads_output_without_blank_lines() {
+    use rsvp_term::parser::StreamParser;
+
+    let words = |doc: Option<rsvp_term::parser::ParsedDocument>| {
+        doc.map(|doc| doc.tokens.into_iter().map(|t| t.word).collect::<Vec<_>>())
+    };
+    let mut parser = StreamParser::new();
+    assert_eq!(words(parser.push("Compiling one\n").unwrap()), None);
+    assert_eq!(
+        words(parser.push("Compiling two\nCompiling th").unwrap()),
+        Some(vec!["Compiling".to_string(), "one".to_string()])
+    );
+    assert_eq!(
+        words(parser.push("ree\n").unwrap()),
+        Some(vec!["Compiling".to_string(), "two".to_string()])
+    );
+
+    // The last line shows once the input goes quiet
+    assert_eq!(
+        words(parser.flush().unwrap()),
+        Some(vec!["Compiling".to_string(), "three".to_string()])
+    );
+    assert_eq!(words(parser.flush().unwrap()), None);
+}
+
+#[test]
+fn test_stream_parser_front_matter_only_at_start() {
+    use rsvp_term::parser::StreamParser;
+
+    let mut parser = StreamParser::new();
+    let doc = parser
+        .push("---\ntitle: Live notes\n---\n\nHello\n\n")
+        .unwrap()
+        .unwrap();
+    assert_eq!(doc.metadata.title.as_deref(), Some("Live notes"));
+
+    // A later rule is not front matter
+    let doc = parser.push("---\n\nafter rule\n\n").unwrap().unwrap();
+    assert_eq!(doc.metadata.title.as_deref(), Some("Live notes"));
+    assert_eq!(doc.tokens.last().unwrap().word, "rule");
+}
+
+#[test]
+fn test_stream_parser_front_matter_split_across_pushes() {
+    use rsvp_term::parser::StreamParser;
+
+    let mut parser = StreamParser::new();
+    assert!(parser.push("---\ntitle: Live").unwrap().is_none());
+    assert!(parser.push(" notes\n").unwrap().is_none());
+    let doc = parser.push("---\n\nHello\n\n").unwrap().unwrap();
+    assert_eq!(doc.metadata.title.as_deref(), Some("Live notes"));
+    let words: Vec<&str> = doc.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Hello"]);
+
+    // A rule that never closes is read once the input ends
+    let mut parser = StreamParser::new();
+    assert!(parser.push("---\n\nJust prose\n\n").unwrap().is_none());
+    let doc = parser.finish().unwrap().unwrap();
+    assert_eq!(doc.metadata.title, None);
+    let words: Vec<&str> = doc.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Just", "prose"]);
+}
+
+#[test]
+fn test_legacy_encodings_are_detected() {
+    let dir = temp_dir("parser", "encoding");
+    let path = dir.join("latin1.md");
+    std::fs::write(&path, b"# Title\n\nCaf\xe9 au lait, \x93hot\x94\n").unwrap();
+
+    let doc = MarkdownParser::new().parse_file(&path).unwrap();
+    let words: Vec<&str> = doc.tokens.iter().map(|t| t.word.as_str()).collect();
+    assert_eq!(words, vec!["Title", "Café", "au", "lait,", "“hot”"]);
+    assert_eq!(doc.metadata.encoding, Some(Encoding::Windows1252));
+
+    // Forcing UTF-8 reports where the text stops being valid
+    let options = MarkdownOptions {
+        encoding: Some(Encoding::Utf8),
+        ..MarkdownOptions::default()
+    };
+    let error = MarkdownParser::with_options(options)
+        .parse_file(&path)
+        .unwrap_err();
+    let ParseError::Encoding { location, .. } = &error else {
+        panic!("expected an encoding error, got {error:?}");
+    };
+    assert_eq!(location.file.as_deref(), Some(path.as_path()));
+    assert_eq!((location.line, location.column), (Some(3), Some(4)));
+    assert!(error
+        .to_string()
+        .starts_with(&format!("{}, line 3, column 4: ", path.display())));
+    assert_eq!(error.exit_code(), 6);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_byte_order_marks_choose_the_encoding() {
+    let utf16 = |bom: [u8; 2], unit: fn(u16) -> [u8; 2]| {
+        let mut bytes = bom.to_vec();
+        bytes.extend("Grüße, Welt".encode_utf16().flat_map(unit));
+        bytes
+    };
+    let parser = MarkdownParser::new();
+    for (bytes, encoding) in [
+        (utf16([0xFF, 0xFE], u16::to_le_bytes), Encoding::Utf16Le),
+        (utf16([0xFE, 0xFF], u16::to_be_bytes), Encoding::Utf16Be),
+        (
+            b"\xEF\xBB\xBFGr\xC3\xBC\xC3\x9Fe, Welt".to_vec(),
+            Encoding::Utf8,
+        ),
+    ] {
+        let doc = parser.parse_bytes(&bytes).unwrap();
+        let words: Vec<&str> = doc.tokens.iter().map(|t| t.word.as_str()).collect();
+        assert_eq!(words, vec!["Grüße,", "Welt"], "{encoding}");
+        assert_eq!(doc.metadata.encoding, Some(encoding));
+    }
+
+    assert_eq!("Latin1".parse(), Ok(Encoding::Latin1));
+    assert_eq!("cp1252".parse(), Ok(Encoding::Windows1252));
+    assert!("ebcdic".parse::<Encoding>().is_err());
+}
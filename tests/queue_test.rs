@@ -0,0 +1,45 @@
+mod common;
+
+use common::temp_dir;
+use rsvp_term::queue::{parse_queue, read_queue_file, QueueEntry};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_parse_queue_skips_comments_and_blank_lines() {
+    let content = "# Monday reading\nrfc-1.md\n\n  design/api.md  \n# done\n";
+    assert_eq!(
+        parse_queue(content, Path::new("docs")),
+        vec![
+            PathBuf::from("docs/rfc-1.md"),
+            PathBuf::from("docs/design/api.md")
+        ]
+    );
+}
+
+#[test]
+fn test_read_queue_file_relative_to_queue() {
+    let dir = temp_dir("queue", "relative");
+    let queue = dir.join("morning.txt");
+    std::fs::write(&queue, "a.md\nb.epub\n").unwrap();
+
+    let paths = read_queue_file(&queue).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(paths, vec![dir.join("a.md"), dir.join("b.epub")]);
+}
+
+#[test]
+fn test_queue_entry_label_and_progress() {
+    let mut entry = QueueEntry::new(PathBuf::from("notes/rfc-7.md"));
+    assert_eq!(entry.label(), "rfc-7.md");
+    assert!(entry.progress().abs() < f64::EPSILON);
+    assert!(!entry.is_finished());
+
+    entry.title = Some("Streaming API".to_string());
+    entry.token_count = Some(5);
+    entry.position = 2;
+    assert_eq!(entry.label(), "Streaming API");
+    assert!((entry.progress() - 0.5).abs() < f64::EPSILON);
+
+    entry.position = 4;
+    assert!(entry.is_finished());
+}
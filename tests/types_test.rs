@@ -13,6 +13,7 @@ fn test_token_creation() {
 }
 
 #[test]
+#[allow(clippy::useless_vec)]
 fn test_token_style_variants() {
     let styles = vec![
         TokenStyle::Normal,
@@ -24,3 +25,53 @@ fn test_token_style_variants() {
     ];
     assert_eq!(styles.len(), 6);
 }
+
+#[test]
+fn test_persistence_key_prefers_identifier() {
+    use rsvp_term::types::DocumentMetadata;
+
+    let mut metadata = DocumentMetadata {
+        title: Some("Book".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(metadata.persistence_key(), "title:Book");
+
+    metadata.source_path = Some("missing/book.md".into());
+    assert_eq!(metadata.persistence_key(), "path:missing/book.md");
+
+    metadata.identifiers.push("urn:isbn:123".to_string());
+    assert_eq!(metadata.persistence_key(), "id:urn:isbn:123");
+
+    assert_eq!(DocumentMetadata::default().persistence_key(), "untitled");
+}
+
+#[test]
+fn test_byline() {
+    use rsvp_term::types::DocumentMetadata;
+
+    let metadata = DocumentMetadata {
+        title: Some("Book".to_string()),
+        authors: vec!["A".to_string(), "B".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(metadata.byline().as_deref(), Some("Book by A, B"));
+}
+
+#[test]
+fn test_reading_progress() {
+    use rsvp_term::types::ReadingProgress;
+
+    let halfway = ReadingProgress {
+        position: 5,
+        token_count: 11,
+    };
+    assert!((halfway.fraction() - 0.5).abs() < f64::EPSILON);
+    assert!(!halfway.is_finished());
+
+    let last = ReadingProgress {
+        position: 0,
+        token_count: 1,
+    };
+    assert!((last.fraction() - 1.0).abs() < f64::EPSILON);
+    assert!(last.is_finished());
+}